@@ -0,0 +1,56 @@
+//! `cargo bench --features bench`: what a snapshot costs, per backend and per way of calling
+//! `sysinfo(2)`, and what the code built on top of it adds.
+use std::hint::black_box;
+use std::mem::MaybeUninit;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sysinfo_dot_h::backend::Backend;
+use sysinfo_dot_h::check::{Check, Metric};
+use sysinfo_dot_h::meminfo::MemInfo;
+use sysinfo_dot_h::{prometheus, provider, sysinfo, SystemInfo};
+
+fn syscall(c: &mut Criterion) {
+    let mut group = c.benchmark_group("syscall");
+    // what `try_collect` does
+    group.bench_function("zeroed", |b| {
+        b.iter(|| unsafe {
+            let mut info: sysinfo = std::mem::zeroed();
+            assert_eq!(sysinfo(&mut info), 0);
+            black_box(info)
+        });
+    });
+    group.bench_function("maybe_uninit", |b| {
+        b.iter(|| unsafe {
+            let mut info = MaybeUninit::<sysinfo>::uninit();
+            assert_eq!(sysinfo(info.as_mut_ptr()), 0);
+            black_box(info.assume_init())
+        });
+    });
+    group.finish();
+}
+
+fn backends(c: &mut Criterion) {
+    let mut group = c.benchmark_group("backend");
+    for backend in Backend::ALL {
+        // the raw syscall isn't there on every architecture
+        if backend.collect().is_ok() {
+            group.bench_function(backend.name(), |b| b.iter(|| black_box(backend.collect())));
+        }
+    }
+    group.finish();
+}
+
+fn on_top(c: &mut Criterion) {
+    let info = sysinfo_dot_h::collect_info();
+    let meminfo = std::fs::read_to_string("/proc/meminfo").unwrap_or_default();
+    c.bench_function("meminfo_parse", |b| b.iter(|| black_box(meminfo.parse::<MemInfo>())));
+    c.bench_function("prometheus_encode", |b| b.iter(|| black_box(prometheus::encode(&prometheus::gauges(black_box(&info))))));
+    c.bench_function("json", |b| b.iter(|| black_box(SystemInfo::to_json(black_box(&info)))));
+
+    let check = Check::new("MEM").rule(Metric::FreeRamPercent, "10:", "5:").unwrap();
+    let snapshot = provider::from_fn(move || Ok(info));
+    c.bench_function("check_run", |b| b.iter(|| black_box(check.run(&snapshot))));
+}
+
+criterion_group!(benches, syscall, backends, on_top);
+criterion_main!(benches);
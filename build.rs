@@ -0,0 +1,9 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        // protox is a pure rust protobuf compiler, so users don't need protoc installed
+        let fds = protox::compile(["proto/sysinfo_dot_h.proto"], ["proto"]).expect("invalid proto/sysinfo_dot_h.proto");
+        tonic_prost_build::configure().compile_fds(fds).expect("failed to generate the gRPC bindings");
+        println!("cargo:rerun-if-changed=proto/sysinfo_dot_h.proto");
+    }
+}
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sysinfo_dot_h::backend::parse_procfs;
+
+// /proc/uptime, /proc/loadavg and /proc/meminfo, separated by NULs which none of them contain
+fuzz_target!(|contents: &str| {
+    let mut files = contents.splitn(3, '\0');
+    let (uptime, loadavg, meminfo) = (files.next().unwrap_or_default(), files.next().unwrap_or_default(), files.next().unwrap_or_default());
+    if let Ok(info) = parse_procfs(uptime, loadavg, meminfo) {
+        assert!(info.loads.iter().all(|load| load.is_finite()));
+    }
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sysinfo_dot_h::vmstat::VmStat;
+
+fuzz_target!(|contents: &str| {
+    let _ = contents.parse::<VmStat>();
+});
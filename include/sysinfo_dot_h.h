@@ -0,0 +1,63 @@
+#ifndef SYSINFO_DOT_H_H
+#define SYSINFO_DOT_H_H
+
+/* Generated by cbindgen from src/capi.rs, don't edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Bumped whenever the snapshot struct or a function signature changes incompatibly.
+#define SYSINFO_DOT_H_ABI_VERSION 1
+
+// The normalized snapshot: memory in bytes (already multiplied by `mem_unit`), load averages as
+// doubles and the uptime in seconds.
+typedef struct sysinfo_dot_h_snapshot {
+  // Seconds since boot
+  uint64_t uptime;
+  // 1, 5, and 15 minute load averages
+  double loads[3];
+  // Total usable main memory size in bytes
+  uint64_t total_ram;
+  // Free memory in bytes. (note: freeram != available memory)
+  uint64_t free_ram;
+  // Amount of shared memory in bytes
+  uint64_t shared_ram;
+  // Memory used by buffers in bytes
+  uint64_t buffer_ram;
+  // Total swap space size in bytes
+  uint64_t total_swap;
+  // Swap space still available in bytes
+  uint64_t free_swap;
+  // Total high memory size in bytes
+  uint64_t total_high;
+  // Available high memory size in bytes
+  uint64_t free_high;
+  // Number of current processes
+  uint16_t procs;
+} sysinfo_dot_h_snapshot;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns the `SYSINFO_DOT_H_ABI_VERSION` of the loaded library, so callers can check it against
+// the header they were compiled with.
+uint32_t sysinfo_dot_h_abi_version(void);
+
+// Fills `out` with a fresh snapshot. Returns 0 on success and -1 on failure, in which case `out`
+// is untouched and `errno` is set: by `sysinfo(2)` when it fails, `EINVAL` when `out` is null or
+// the crate couldn't use what the kernel returned (e.g. a malformed `SYSINFO_DOT_H_FAKE`).
+//
+// # Safety
+//
+// `out` must be null (which fails) or point to memory valid for writing a snapshot.
+int sysinfo_dot_h_collect(struct sysinfo_dot_h_snapshot *out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SYSINFO_DOT_H_H */
@@ -0,0 +1,199 @@
+//! The different ways of getting a snapshot out of the kernel.
+//!
+//! [`Backend::Libc`] is what [`try_collect`](crate::try_collect) does and the right choice almost
+//! always. The others exist for measuring (`sysinfo-dot-h bench`) and for environments where
+//! the libc wrapper or the syscall itself isn't usable.
+//!
+//! ```rust
+//! use sysinfo_dot_h::backend::Backend;
+//!
+//! for backend in Backend::ALL {
+//!     if let Ok(info) = backend.collect() {
+//!         println!("{backend}: {} bytes free", info.free_ram);
+//!     }
+//! }
+//! ```
+use std::fmt;
+use std::time::Duration;
+
+use crate::meminfo::MemInfo;
+use crate::{sysinfo, SysinfoError, SystemInfo};
+
+/// A way of collecting a [`SystemInfo`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// `sysinfo(2)` through the libc wrapper
+    Libc,
+    /// `sysinfo(2)` as a raw syscall instruction, skipping libc. Only on x86_64 and aarch64.
+    Syscall,
+    /// Parsing `/proc/uptime`, `/proc/loadavg` and `/proc/meminfo`, for when the syscall is
+    /// filtered (seccomp). Slower by orders of magnitude.
+    Procfs,
+}
+
+impl Backend {
+    /// Every backend, fastest first.
+    pub const ALL: [Backend; 3] = [Backend::Syscall, Backend::Libc, Backend::Procfs];
+
+    /// A short name, as used by `sysinfo-dot-h bench`.
+    #[must_use] pub fn name(self) -> &'static str {
+        match self {
+            Backend::Libc => "libc",
+            Backend::Syscall => "syscall",
+            Backend::Procfs => "procfs",
+        }
+    }
+
+    /// Collects a snapshot with this backend.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SysinfoError::Syscall`] if the syscall fails (with `ErrorKind::Unsupported` if
+    /// the backend isn't available on this architecture), and the procfs errors if the files
+    /// can't be read or parsed.
+    pub fn collect(self) -> Result<SystemInfo, SysinfoError> {
+        match self {
+            Backend::Libc => crate::try_collect_info(),
+            Backend::Syscall => raw_syscall().map(SystemInfo::from),
+            Backend::Procfs => procfs(),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn raw_syscall() -> Result<sysinfo, SysinfoError> {
+    #[allow(unused_mut)]
+    let mut info = crate::raw::try_collect().map_err(|e| SysinfoError::Syscall(std::io::Error::from_raw_os_error(e.0)))?;
+    #[cfg(feature = "fake")] crate::fake::apply(&mut info).map_err(SysinfoError::Fake)?;
+    Ok(info)
+}
+
+#[cfg(not(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+fn raw_syscall() -> Result<sysinfo, SysinfoError> {
+    Err(SysinfoError::Syscall(std::io::ErrorKind::Unsupported.into()))
+}
+
+fn procfs() -> Result<SystemInfo, SysinfoError> {
+    let read = crate::read_proc;
+    #[allow(unused_mut)]
+    let mut info = parse_procfs(&read("/proc/uptime")?, &read("/proc/loadavg")?, &read("/proc/meminfo")?)?;
+    #[cfg(feature = "fake")] crate::fake::apply_info(&mut info).map_err(SysinfoError::Fake)?;
+    Ok(info)
+}
+
+/// [`try_collect_info`](crate::try_collect_info), falling back to [`Backend::Procfs`] when the
+/// syscall itself fails, which is what a seccomp filter returning `EPERM` (or `ENOSYS`) looks
+/// like. Keeps working in locked down sandboxes and containers, at the cost of reading three
+/// files there.
+///
+/// ```rust
+/// let info = sysinfo_dot_h::try_collect_with_fallback().unwrap();
+/// assert!(info.total_ram > 0);
+/// ```
+///
+/// # Errors
+///
+/// Returns the `/proc` error if both fail, and [`SysinfoError::Fake`] if `SYSINFO_DOT_H_FAKE` is
+/// malformed (that isn't something `/proc` can fix).
+pub fn try_collect_with_fallback() -> Result<SystemInfo, SysinfoError> {
+    with_fallback(crate::try_collect_info)
+}
+
+fn with_fallback(syscall: impl FnOnce() -> Result<SystemInfo, SysinfoError>) -> Result<SystemInfo, SysinfoError> {
+    match syscall() {
+        Err(SysinfoError::Syscall(_)) => procfs(),
+        result => result,
+    }
+}
+
+/// [`Backend::Procfs`] without the reading: a snapshot out of the contents of `/proc/uptime`,
+/// `/proc/loadavg` and `/proc/meminfo`. Never panics, whatever it's given.
+///
+/// # Errors
+///
+/// Returns [`SysinfoError::Parse`] (or [`SysinfoError::Missing`]) if one of them doesn't look
+/// like what the kernel writes.
+pub fn parse_procfs(uptime: &str, loadavg: &str, meminfo: &str) -> Result<SystemInfo, SysinfoError> {
+    let meminfo: MemInfo = meminfo.parse()?;
+    let (uptime, loads, procs) = parse_uptime_and_loadavg(uptime, loadavg)?;
+    Ok(SystemInfo {
+        uptime,
+        loads,
+        total_ram: meminfo.total,
+        free_ram: meminfo.free,
+        shared_ram: meminfo.shmem,
+        buffer_ram: meminfo.buffers,
+        total_swap: meminfo.swap_total,
+        free_swap: meminfo.swap_free,
+        total_high: meminfo.high_total,
+        free_high: meminfo.high_free,
+        procs,
+    })
+}
+
+/// `3011.65 1654.03` and `0.68 0.71 0.58 2/72 2721`. `procs` is the total after the slash, which
+/// counts threads just like the syscall does.
+pub(crate) fn parse_uptime_and_loadavg(uptime: &str, loadavg: &str) -> Result<(Duration, [f64; 3], u16), SysinfoError> {
+    let invalid = |file: &str, contents: &str| SysinfoError::parse(file, contents.trim_end());
+    // the kernel never writes inf, NaN or negative numbers, but `f64::from_str` takes them
+    let number = |s: &str| s.parse::<f64>().ok().filter(|n| n.is_finite() && *n >= 0.0);
+    let secs = uptime.split_whitespace().next().and_then(number).ok_or_else(|| invalid("/proc/uptime", uptime))?;
+
+    let fields: Vec<&str> = loadavg.split_whitespace().collect();
+    let (Some(loads), Some(procs)) = (fields.get(..3), fields.get(3)) else {
+        return Err(invalid("/proc/loadavg", loadavg));
+    };
+    let loads: Vec<f64> = loads.iter().map(|load| number(load)).collect::<Option<_>>().ok_or_else(|| invalid("/proc/loadavg", loadavg))?;
+    let procs: u64 = procs.split_once('/').and_then(|(_, total)| total.parse().ok()).ok_or_else(|| invalid("/proc/loadavg", loadavg))?;
+
+    // the syscall truncates to whole seconds and a 16 bit process count, do the same
+    Ok((Duration::from_secs(secs as u64), [loads[0], loads[1], loads[2]], procs.min(u64::from(u16::MAX)) as u16))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_proc_files() {
+        let (uptime, loads, procs) = parse_uptime_and_loadavg("3011.65 1654.03\n", "0.68 0.71 0.58 2/72 2721\n").unwrap();
+        assert_eq!(uptime, Duration::from_secs(3011));
+        assert_eq!(loads, [0.68, 0.71, 0.58]);
+        assert_eq!(procs, 72);
+        assert!(parse_uptime_and_loadavg("", "0.68 0.71 0.58 2/72 2721").is_err());
+        assert!(parse_uptime_and_loadavg("1.0 1.0", "0.68 0.71").is_err());
+        assert!(parse_uptime_and_loadavg("inf 1.0", "0.68 0.71 0.58 2/72 2721").is_err());
+        assert!(parse_uptime_and_loadavg("1.0 1.0", "NaN 0.71 0.58 2/72 2721").is_err());
+        assert!(parse_procfs("3011.65 1654.03\n", "0.68 0.71 0.58 2/72 2721\n", "MemFree: 1 kB\n").is_err());
+    }
+
+    #[test]
+    fn falls_back_to_procfs() {
+        let filtered = || Err(SysinfoError::Syscall(std::io::Error::from_raw_os_error(1)));
+        let info = with_fallback(filtered).unwrap();
+        assert_eq!(info.total_ram, Backend::Libc.collect().unwrap().total_ram);
+
+        let fake = || Err(SysinfoError::Fake("invalid SYSINFO_DOT_H_FAKE: nope".to_string()));
+        assert!(matches!(with_fallback(fake), Err(SysinfoError::Fake(e)) if e == "invalid SYSINFO_DOT_H_FAKE: nope"));
+        assert!(try_collect_with_fallback().is_ok());
+    }
+
+    #[test]
+    fn backends_agree() {
+        let libc = Backend::Libc.collect().unwrap();
+        for backend in Backend::ALL {
+            let Ok(info) = backend.collect() else {
+                assert_eq!(backend, Backend::Syscall, "only the raw syscall may be missing");
+                continue;
+            };
+            assert_eq!(info.total_ram, libc.total_ram, "{backend}");
+            assert!(info.uptime.abs_diff(libc.uptime) <= Duration::from_secs(2), "{backend}");
+        }
+    }
+}
@@ -0,0 +1,384 @@
+//! Command line parsing. Hand rolled to keep the binary dependency free.
+use std::path::PathBuf;
+use std::time::Duration;
+
+use sysinfo_dot_h::prometheus;
+
+use crate::assert::Assertion;
+use crate::human::Units;
+use crate::{bench, config};
+use crate::format::Format;
+use crate::template::Template;
+
+pub const USAGE: &str = "\
+Usage: sysinfo-dot-h [OPTIONS]
+       sysinfo-dot-h diff BEFORE.json AFTER.json
+       sysinfo-dot-h bench [DURATION]
+       sysinfo-dot-h record --out FILE
+
+Prints the sysinfo struct, both raw and with the units worked out.
+
+Commands:
+  diff BEFORE AFTER
+                   Compare two snapshots saved with --json, with per second rates
+  bench [DURATION]
+                   Measure every collection backend (libc, raw syscall, procfs) for
+                   DURATION (default 1s) each
+  record --out FILE
+                   Record a sample every --interval (default 1s) into FILE, until killed
+
+Options:
+      --json       Print the snapshot as JSON (the same schema as SystemInfo::to_json)
+      --pretty     Like --json, but indented
+      --format FORMAT
+                   Print FORMAT with {field} placeholders filled in, e.g.
+                   '{available_h} free, load {load1:.2}'
+      --template FILE
+                   Print FILE with {{ field | filter }} expressions and {% for %} loops
+                   filled in, see the README
+      --free       Print the same table as `free -h`
+      --uptime     Print the same line as `uptime`
+      --line       Print a one line summary: uptime, used of total RAM and swap, load and
+                   processes
+      --watch [INTERVAL]
+                   Refresh every INTERVAL (default 2s) in place, showing what changed since
+                   the previous refresh. With --json, prints one line per sample instead
+      --listen ADDR
+                   Serve Prometheus metrics on http://ADDR/metrics (`:9100` for every interface)
+      --top        Refresh a table of derived metrics (used %, available, swap in/out and
+                   major faults per second, ...) every --interval (default 2s), whatever got
+                   worse the most since the last refresh first
+      --tui        Live gauges and sparklines (needs the `tui` feature), q quits
+      --label KEY=VALUE
+                   Add a label to every --listen metric, can be given more than once
+      --log-csv FILE
+                   Append a row to FILE every --interval, until killed
+      --interval INTERVAL
+                   Time between samples for --log-csv (default 5s), --listen and --tui (1s),
+                   and --top (2s)
+      --rotate-size SIZE
+                   Move FILE to FILE.1 once it's bigger than SIZE (e.g. 10MiB)
+      --assert CONDITION
+                   Exit with 1 unless CONDITION holds, e.g. 'available>512MiB' or 'load1<8'.
+                   Can be given more than once, prints nothing but the failed conditions
+      --config FILE
+                   Read options from a TOML file, see the README. The command line wins
+      --pressure   Show the share of time tasks stalled on cpu, memory and io (PSI) over the
+                   last 10s, 60s and 300s next to the load averages, with --watch or --tui
+      --binary     Print sizes in powers of 1024 (KiB, MiB, ...), the default
+      --si         Print sizes in powers of 1000 (kB, MB, ...)
+      --bytes      Print sizes as plain numbers of bytes
+      --cgroup     Show this process's cgroup (container) instead of the host: totals capped
+                   at the cgroup's limits, free amounts worked out from its usage
+      --strict     Exit with 1 and print the errors as JSON on stderr if anything the other
+                   options need (sysinfo, /proc/meminfo, PSI, ...) can't be read, instead of
+                   leaving it out
+      --replay FILE
+                   Read the samples from a recording instead of this machine, following the
+                   recorded timing. One shot modes print the last sample
+  -h, --help       Print this help
+  -V, --version    Print the version
+";
+
+/// Everything the command line can ask for.
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub command: Option<Command>,
+    pub output: Output,
+    pub watch: Option<Duration>,
+    pub tui: bool,
+    pub top: bool,
+    pub listen: Option<String>,
+    pub labels: Vec<(String, String)>,
+    pub log_csv: Option<PathBuf>,
+    pub interval: Option<Duration>,
+    pub rotate_size: Option<u64>,
+    pub asserts: Vec<Assertion>,
+    pub replay: Option<PathBuf>,
+    pub cgroup: bool,
+    pub pressure: bool,
+    pub units: Units,
+    pub strict: bool,
+    pub help: bool,
+    pub version: bool,
+}
+
+/// Subcommands, doing something else than printing a snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Diff(PathBuf, PathBuf),
+    Bench(Duration),
+    Record(PathBuf),
+}
+
+/// How to print a snapshot.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum Output {
+    #[default]
+    Human,
+    Json,
+    PrettyJson,
+    /// `free -h`
+    Free,
+    /// `uptime`
+    Uptime,
+    /// `--line`, [`SystemInfo`](sysinfo_dot_h::SystemInfo)'s `Display`
+    Line,
+    /// `--format`
+    Format(Format),
+    /// `--template`
+    Template(Template),
+}
+
+/// `--watch`'s interval when none is given, same as `watch(1)`.
+pub const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// `--log-csv`'s interval when there's no `--interval`.
+pub const DEFAULT_LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Parses `500ms`, `2s`, `1.5s`, `5m` or `1h`. A bare number means seconds. Zero isn't a duration
+/// anything here can use, an interval of 0 would just spin.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration {s:?}");
+    let split = s.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+    let secs = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return Err(invalid()),
+    };
+    Duration::try_from_secs_f64(secs).ok().filter(|duration| !duration.is_zero()).ok_or_else(invalid)
+}
+
+/// Parses a whole number of bytes like `10MiB`, `1GB` or `4096`, with the suffixes of
+/// [`crate::fields::parse_value`] (but no `%`). Zero isn't a size either.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    const SUFFIXES: [(&str, u64); 15] = [
+        ("KiB", 1 << 10), ("MiB", 1 << 20), ("GiB", 1 << 30), ("TiB", 1 << 40),
+        ("kB", 1_000), ("MB", 1_000_000), ("GB", 1_000_000_000), ("TB", 1_000_000_000_000),
+        ("K", 1 << 10), ("M", 1 << 20), ("G", 1 << 30), ("T", 1 << 40),
+        ("k", 1 << 10), ("B", 1), ("", 1),
+    ];
+    SUFFIXES.iter()
+        .find_map(|&(suffix, multiplier)| s.strip_suffix(suffix)?.trim().parse::<u64>().ok()?.checked_mul(multiplier))
+        .filter(|size| *size > 0)
+        .ok_or_else(|| format!("invalid size {s:?}"))
+}
+
+/// Replaces `--config FILE` with the options from FILE. They go first, so the rest of the
+/// command line overrides them.
+pub fn with_config(mut args: Vec<String>) -> Result<Vec<String>, String> {
+    let Some(at) = args.iter().position(|arg| arg == "--config") else { return Ok(args) };
+    let path = args.get(at + 1).ok_or("--config needs a file")?;
+    let mut expanded = config::load(path)?;
+    args.drain(at..at + 2);
+    expanded.append(&mut args);
+    Ok(expanded)
+}
+
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "diff" if options.command.is_none() => {
+                let (Some(before), Some(after)) = (args.next(), args.next()) else {
+                    return Err("diff needs two snapshot files".to_string());
+                };
+                options.command = Some(Command::Diff(before.into(), after.into()));
+            }
+            "bench" if options.command.is_none() => {
+                let duration = args.next_if(|next| parse_duration(next).is_ok());
+                options.command = Some(Command::Bench(duration.map_or(Ok(bench::DEFAULT_DURATION), |d| parse_duration(&d))?));
+            }
+            "--watch" => {
+                // the interval is optional, so only take the next argument if it is one
+                let interval = args.next_if(|next| parse_duration(next).is_ok());
+                options.watch = Some(interval.map_or(Ok(DEFAULT_WATCH_INTERVAL), |i| parse_duration(&i))?);
+            }
+            "record" if options.command.is_none() => {
+                if args.next().as_deref() != Some("--out") {
+                    return Err("record needs --out FILE".to_string());
+                }
+                options.command = Some(Command::Record(args.next().ok_or("--out needs a file")?.into()));
+            }
+            "--replay" => options.replay = Some(args.next().ok_or("--replay needs a recording")?.into()),
+            "--listen" => options.listen = Some(args.next().ok_or("--listen needs an address")?),
+            "--label" => {
+                let label = args.next().ok_or("--label needs KEY=VALUE")?;
+                let (key, value) = label.split_once('=').ok_or_else(|| format!("invalid label {label:?}, expected KEY=VALUE"))?;
+                prometheus::check_label_name(key)?;
+                options.labels.push((key.to_string(), value.to_string()));
+            }
+            "--log-csv" => options.log_csv = Some(args.next().ok_or("--log-csv needs a file")?.into()),
+            "--interval" => options.interval = Some(parse_duration(&args.next().ok_or("--interval needs a duration")?)?),
+            "--rotate-size" => {
+                let size = args.next().ok_or("--rotate-size needs a size")?;
+                options.rotate_size = Some(parse_size(&size)?);
+            }
+            "--assert" => options.asserts.push(args.next().ok_or("--assert needs a condition")?.parse()?),
+            "--tui" => options.tui = true,
+            "--top" => options.top = true,
+            "--cgroup" => options.cgroup = true,
+            "--pressure" => options.pressure = true,
+            "--binary" => options.units = Units::Binary,
+            "--si" => options.units = Units::Si,
+            "--bytes" => options.units = Units::Bytes,
+            "--strict" => options.strict = true,
+            "--json" => options.output = Output::Json,
+            "--pretty" => options.output = Output::PrettyJson,
+            "--format" => options.output = Output::Format(args.next().ok_or("--format needs a format string")?.parse()?),
+            "--template" => {
+                let path = args.next().ok_or("--template needs a file")?;
+                let template = std::fs::read_to_string(&path).map_err(|e| format!("can't read {path}: {e}"))?;
+                options.output = Output::Template(Template::parse(&template).map_err(|e| format!("{path}: {e}"))?);
+            }
+            "--free" => options.output = Output::Free,
+            "--uptime" => options.output = Output::Uptime,
+            "--line" => options.output = Output::Line,
+            "-h" | "--help" => options.help = true,
+            "-V" | "--version" => options.version = true,
+            _ => return Err(format!("unexpected argument {arg:?}")),
+        }
+    }
+    if options.rotate_size.is_some() && options.log_csv.is_none() {
+        return Err("--rotate-size only makes sense with --log-csv".to_string());
+    }
+    if options.pressure && options.watch.is_none() && !options.tui {
+        return Err("--pressure only makes sense with --watch or --tui".to_string());
+    }
+    if options.cgroup && options.replay.is_some() {
+        return Err("--cgroup reads this machine, it can't be combined with --replay".to_string());
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &[&str]) -> Result<Options, String> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn flags() {
+        assert_eq!(parse_str(&[]), Ok(Options::default()));
+        assert!(parse_str(&["--help"]).unwrap().help);
+        assert!(parse_str(&["-V"]).unwrap().version);
+        assert!(parse_str(&["--tui"]).unwrap().tui);
+        assert!(parse_str(&["--top"]).unwrap().top);
+        assert!(parse_str(&["--strict"]).unwrap().strict);
+        assert!(parse_str(&["--cgroup"]).unwrap().cgroup);
+        assert!(parse_str(&["--tui", "--pressure"]).unwrap().pressure);
+        assert!(parse_str(&["--pressure"]).is_err());
+        assert_eq!(parse_str(&["--si"]).unwrap().units, Units::Si);
+        assert_eq!(parse_str(&["--si", "--bytes"]).unwrap().units, Units::Bytes);
+        assert_eq!(parse_str(&["--bytes", "--binary"]).unwrap().units, Units::Binary);
+        assert!(parse_str(&["--nope"]).is_err());
+        assert_eq!(parse_str(&["--json"]).unwrap().output, Output::Json);
+        assert_eq!(parse_str(&["--json", "--pretty"]).unwrap().output, Output::PrettyJson);
+        assert_eq!(parse_str(&["--free"]).unwrap().output, Output::Free);
+        assert_eq!(parse_str(&["--uptime"]).unwrap().output, Output::Uptime);
+        assert_eq!(parse_str(&["--line"]).unwrap().output, Output::Line);
+        assert_eq!(parse_str(&["--format", "{load1}"]).unwrap().output, Output::Format("{load1}".parse().unwrap()));
+        assert!(parse_str(&["--format", "{nope}"]).is_err());
+        assert!(parse_str(&["--template", "/nonexistent"]).is_err());
+    }
+
+    #[test]
+    fn diff() {
+        let options = parse_str(&["diff", "a.json", "b.json"]).unwrap();
+        assert_eq!(options.command, Some(Command::Diff("a.json".into(), "b.json".into())));
+        assert!(parse_str(&["diff", "a.json"]).is_err());
+        assert!(parse_str(&["diff", "a.json", "b.json", "diff", "c.json", "d.json"]).is_err());
+    }
+
+    #[test]
+    fn bench() {
+        assert_eq!(parse_str(&["bench"]).unwrap().command, Some(Command::Bench(bench::DEFAULT_DURATION)));
+        assert_eq!(parse_str(&["bench", "200ms"]).unwrap().command, Some(Command::Bench(Duration::from_millis(200))));
+    }
+
+    #[test]
+    fn record_and_replay() {
+        assert_eq!(parse_str(&["record", "--out", "trace"]).unwrap().command, Some(Command::Record("trace".into())));
+        assert!(parse_str(&["record"]).is_err());
+        assert!(parse_str(&["record", "--out"]).is_err());
+        assert_eq!(parse_str(&["--replay", "trace", "--watch"]).unwrap().replay, Some("trace".into()));
+        assert!(parse_str(&["--replay", "trace", "--cgroup"]).is_err());
+    }
+
+    #[test]
+    fn watch() {
+        assert_eq!(parse_str(&["--watch"]).unwrap().watch, Some(DEFAULT_WATCH_INTERVAL));
+        assert_eq!(parse_str(&["--watch", "500ms"]).unwrap().watch, Some(Duration::from_millis(500)));
+        let options = parse_str(&["--watch", "--json"]).unwrap();
+        assert_eq!((options.watch, options.output), (Some(DEFAULT_WATCH_INTERVAL), Output::Json));
+        assert!(parse_str(&["--pressure", "--watch", "1s"]).unwrap().pressure);
+    }
+
+    #[test]
+    fn listen() {
+        assert_eq!(parse_str(&["--listen", ":9100"]).unwrap().listen.as_deref(), Some(":9100"));
+        assert!(parse_str(&["--listen"]).is_err());
+    }
+
+    #[test]
+    fn asserts() {
+        let options = parse_str(&["--assert", "load1<8", "--assert", "available>512MiB"]).unwrap();
+        assert_eq!(options.asserts, vec!["load1<8".parse().unwrap(), "available>512MiB".parse().unwrap()]);
+        assert!(parse_str(&["--assert", "load1"]).is_err());
+    }
+
+    #[test]
+    fn labels() {
+        let options = parse_str(&["--label", "host=web-01", "--label", "dc=a=b"]).unwrap();
+        assert_eq!(options.labels, [("host".to_string(), "web-01".to_string()), ("dc".to_string(), "a=b".to_string())]);
+        assert!(parse_str(&["--label", "host"]).is_err());
+        for invalid in ["", "1host", "host-name", "hôst", "__name__"] {
+            assert!(parse_str(&["--label", &format!("{invalid}=x")]).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn config_goes_first() {
+        let path = std::env::temp_dir().join(format!("sysinfo_dot_h-{}.toml", std::process::id()));
+        std::fs::write(&path, "output = \"json\"\ninterval = \"30s\"\n").unwrap();
+        let args = ["--interval", "5s", "--config", path.to_str().unwrap()].map(String::from).to_vec();
+        let options = with_config(args).and_then(parse).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((options.output, options.interval), (Output::Json, Some(Duration::from_secs(5))));
+    }
+
+    #[test]
+    fn log_csv() {
+        let options = parse_str(&["--log-csv", "mem.csv", "--interval", "5s", "--rotate-size", "10MiB"]).unwrap();
+        assert_eq!(options.log_csv, Some(PathBuf::from("mem.csv")));
+        assert_eq!(options.interval, Some(Duration::from_secs(5)));
+        assert_eq!(options.rotate_size, Some(10 << 20));
+        assert!(parse_str(&["--rotate-size", "10MiB"]).is_err());
+        assert!(parse_str(&["--log-csv", "mem.csv", "--rotate-size", "big"]).is_err());
+        for invalid in ["0", "-1MiB", "1.5MiB", "50%", "99999999TiB"] {
+            assert!(parse_size(invalid).is_err(), "{invalid}");
+        }
+        assert_eq!(parse_size("1GB"), Ok(1_000_000_000));
+        assert_eq!(parse_size("4096"), Ok(4096));
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("2"), Ok(Duration::from_secs(2)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert!(parse_duration("5 parsecs").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("0ms").is_err());
+    }
+}
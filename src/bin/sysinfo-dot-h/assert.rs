@@ -0,0 +1,111 @@
+//! `--assert 'available>512MiB'`: conditions on the snapshot, for gating shell scripts and CI
+//! jobs on the exit code.
+use std::str::FromStr;
+
+use crate::fields::{self, Sample};
+use crate::human;
+
+/// A comparison between a [field](fields::NAMES) and a constant.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assertion {
+    field: String,
+    op: Op,
+    value: f64,
+    text: String,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Op {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Op {
+    /// Longest first, so `<=` isn't taken for `<`.
+    const ALL: [(&'static str, Op); 7] = [
+        ("<=", Op::LessOrEqual), (">=", Op::GreaterOrEqual), ("==", Op::Equal), ("!=", Op::NotEqual),
+        ("<", Op::Less), (">", Op::Greater), ("=", Op::Equal),
+    ];
+
+    fn holds(self, left: f64, right: f64) -> bool {
+        match self {
+            Op::Less => left < right,
+            Op::LessOrEqual => left <= right,
+            Op::Greater => left > right,
+            Op::GreaterOrEqual => left >= right,
+            Op::Equal => left == right,
+            Op::NotEqual => left != right,
+        }
+    }
+}
+
+impl FromStr for Assertion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (at, symbol, op) = Op::ALL
+            .iter()
+            .filter_map(|&(symbol, op)| Some((s.find(symbol)?, symbol, op)))
+            .min_by_key(|&(at, symbol, _)| (at, usize::MAX - symbol.len()))
+            .ok_or_else(|| format!("no comparison in assertion {s:?}"))?;
+        let field = s[..at].trim();
+        if !fields::NAMES.contains(&field) {
+            return Err(format!("unknown field {field:?} in assertion {s:?}"));
+        }
+        let value = fields::parse_value(s[at + symbol.len()..].trim()).ok_or_else(|| format!("invalid value in assertion {s:?}"))?;
+        Ok(Self { field: field.to_string(), op, value, text: s.to_string() })
+    }
+}
+
+impl Assertion {
+    /// `Ok` if it holds, otherwise why not.
+    pub fn check(&self, sample: &Sample) -> Result<(), String> {
+        let Some(actual) = sample.get(&self.field) else {
+            return Err(format!("{}: {} is unknown on this machine", self.text, self.field));
+        };
+        if self.op.holds(actual, self.value) {
+            return Ok(());
+        }
+        let actual = if fields::is_bytes(&self.field) { human::bytes(actual as u64) } else { format!("{actual:.2}") };
+        Err(format!("{}: {} is {actual}", self.text, self.field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sysinfo_dot_h::meminfo::MemInfo;
+    use sysinfo_dot_h::SystemInfo;
+
+    fn sample() -> Sample {
+        Sample {
+            info: SystemInfo { loads: [4.0, 2.0, 1.0], total_ram: 1 << 30, ..Default::default() },
+            meminfo: Some(MemInfo { available: Some(256 << 20), ..Default::default() }),
+        }
+    }
+
+    fn check(assertion: &str) -> Result<(), String> {
+        assertion.parse::<Assertion>()?.check(&sample())
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_eq!(check("load1<8"), Ok(()));
+        assert_eq!(check("load1 <= 4"), Ok(()));
+        assert_eq!(check("available>=256MiB"), Ok(()));
+        assert_eq!(check("available>512MiB"), Err("available>512MiB: available is 256.0 MiB".to_string()));
+        assert_eq!(check("available_percent==25%"), Ok(()));
+        assert_eq!(check("load5!=2"), Err("load5!=2: load5 is 2.00".to_string()));
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!("load1".parse::<Assertion>().is_err());
+        assert!("load9<1".parse::<Assertion>().is_err());
+        assert!("load1<".parse::<Assertion>().is_err());
+    }
+}
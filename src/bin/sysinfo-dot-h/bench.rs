@@ -0,0 +1,81 @@
+//! `bench`: how long each [`Backend`] takes on this machine.
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use sysinfo_dot_h::backend::Backend;
+
+/// `bench`'s time per backend when none is given.
+pub const DEFAULT_DURATION: Duration = Duration::from_secs(1);
+
+/// Latency statistics of one backend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub backend: Backend,
+    /// Sorted, one per call
+    latencies: Vec<Duration>,
+    elapsed: Duration,
+}
+
+impl Measurement {
+    fn percentile(&self, percent: usize) -> Duration {
+        self.latencies[(self.latencies.len() - 1) * percent / 100]
+    }
+
+    fn calls_per_sec(&self) -> f64 {
+        self.latencies.len() as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+/// Calls `backend` for about `duration`, after a short warm up. `Err` if it doesn't work here.
+pub fn measure(backend: Backend, duration: Duration) -> Result<Measurement, String> {
+    for _ in 0..16 {
+        backend.collect()?;
+    }
+    let mut latencies = Vec::new();
+    let start = Instant::now();
+    while start.elapsed() < duration || latencies.is_empty() {
+        let call = Instant::now();
+        let _ = std::hint::black_box(backend.collect());
+        latencies.push(call.elapsed());
+    }
+    let elapsed = start.elapsed();
+    latencies.sort_unstable();
+    Ok(Measurement { backend, latencies, elapsed })
+}
+
+/// The results table, one row per backend.
+pub struct Table<'a>(pub &'a [Result<Measurement, (Backend, String)>]);
+
+impl fmt::Display for Table<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<10}{:>10}{:>12}{:>12}{:>12}{:>14}", "backend", "calls", "min", "median", "p99", "calls/s")?;
+        for result in self.0 {
+            match result {
+                Ok(m) => writeln!(
+                    f, "{:<10}{:>10}{:>12}{:>12}{:>12}{:>14.0}",
+                    m.backend.name(), m.latencies.len(),
+                    format!("{:.2?}", m.latencies[0]), format!("{:.2?}", m.percentile(50)), format!("{:.2?}", m.percentile(99)),
+                    m.calls_per_sec(),
+                )?,
+                Err((backend, e)) => writeln!(f, "{:<10}  unavailable: {e}", backend.name())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_and_prints() {
+        let libc = measure(Backend::Libc, Duration::from_millis(10)).unwrap();
+        assert!(libc.percentile(0) <= libc.percentile(50) && libc.percentile(50) <= libc.percentile(99));
+
+        let table = Table(&[Ok(libc), Err((Backend::Syscall, "nope".to_string()))]).to_string();
+        assert!(table.starts_with("backend        calls"));
+        assert!(table.contains("\nlibc "));
+        assert!(table.ends_with("\nsyscall     unavailable: nope\n"));
+    }
+}
@@ -0,0 +1,219 @@
+//! `--config FILE`: the long options as a TOML file, for deploying the exporter/logger modes
+//! declaratively.
+//!
+//! ```toml
+//! listen = ":9100"
+//! interval = "15s"
+//! assert = ["available>512MiB", "load1<8"]
+//!
+//! [labels]
+//! host = "web-01"
+//! ```
+//!
+//! Every key is the option without its dashes (`log_csv` or `log-csv` for `--log-csv`), and
+//! `output = "json"` picks one of `--json`, `--pretty`, `--free`, `--uptime` and `--line`
+//! (`units` one of `--binary`, `--si` and `--bytes`). The file is turned into command line arguments placed
+//! before the real ones, so the command line wins.
+//!
+//! Only the subset of TOML this needs is supported (tables, strings, numbers, booleans and
+//! single line arrays), to keep the binary dependency free.
+
+/// Reads `path` and turns it into arguments.
+pub fn load(path: &str) -> Result<Vec<String>, String> {
+    let toml = std::fs::read_to_string(path).map_err(|e| format!("can't read {path}: {e}"))?;
+    to_args(&toml).map_err(|e| format!("{path}: {e}"))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    String(String),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    fn into_string(self, key: &str) -> Result<String, String> {
+        match self {
+            Value::String(s) => Ok(s),
+            _ => Err(format!("{key} must be a string")),
+        }
+    }
+}
+
+fn to_args(toml: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut table = String::new();
+    for (number, line) in toml.lines().enumerate() {
+        let at_line = |e: String| format!("line {}: {e}", number + 1);
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            table = name.trim().to_string();
+            if table != "labels" {
+                return Err(at_line(format!("unknown table [{table}]")));
+            }
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or_else(|| at_line("expected key = value".to_string()))?;
+        let key = key.trim().trim_matches('"');
+        let (value, rest) = parse_value(value.trim()).map_err(at_line)?;
+        if !rest.trim().is_empty() {
+            return Err(at_line(format!("unexpected {rest:?} after the value")));
+        }
+
+        if table == "labels" {
+            sysinfo_dot_h::prometheus::check_label_name(key).map_err(|e| at_line(e.into()))?;
+            args.push("--label".to_string());
+            args.push(format!("{key}={}", value.into_string(key).map_err(at_line)?));
+        } else {
+            option(key, value, &mut args).map_err(at_line)?;
+        }
+    }
+    Ok(args)
+}
+
+/// A top level `key = value` as arguments.
+fn option(key: &str, value: Value, args: &mut Vec<String>) -> Result<(), String> {
+    let flag = format!("--{}", key.replace('_', "-"));
+    match (key.replace('-', "_").as_str(), value) {
+        ("output", Value::String(output)) => match output.as_str() {
+            "human" => {}
+            "json" | "pretty" | "free" | "uptime" | "line" => args.push(format!("--{output}")),
+            _ => return Err(format!("unknown output {output:?}")),
+        },
+        ("units", Value::String(units)) => match units.as_str() {
+            "binary" | "si" | "bytes" => args.push(format!("--{units}")),
+            _ => return Err(format!("unknown units {units:?}")),
+        },
+        ("watch" | "top" | "cgroup" | "pressure" | "strict", Value::Bool(on)) => {
+            if on {
+                args.push(flag);
+            }
+        }
+        ("assert", Value::Array(conditions)) => {
+            for condition in conditions {
+                args.push(flag.clone());
+                args.push(condition.into_string(key)?);
+            }
+        }
+        ("watch" | "listen" | "log_csv" | "interval" | "rotate_size" | "format" | "template" | "assert" | "replay", value) => {
+            args.push(flag);
+            args.push(value.into_string(key)?);
+        }
+        _ => return Err(format!("unknown key {key:?}")),
+    }
+    Ok(())
+}
+
+/// Everything before a `#` that isn't inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+/// Parses the value at the start of `s`, returning it and whatever follows.
+fn parse_value(s: &str) -> Result<(Value, &str), String> {
+    if let Some(rest) = s.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((Value::String(value), &rest[i + 1..])),
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(c @ ('"' | '\\')) => value.push(c),
+                    c => return Err(format!("unsupported escape \\{}", c.unwrap_or(' '))),
+                },
+                c => value.push(c),
+            }
+        }
+        return Err("unterminated string".to_string());
+    }
+    if let Some(rest) = s.strip_prefix('\'') {
+        let (value, rest) = rest.split_once('\'').ok_or("unterminated string")?;
+        return Ok((Value::String(value.to_string()), rest));
+    }
+    if let Some(mut rest) = s.strip_prefix('[') {
+        let mut values = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(rest) = rest.strip_prefix(']') {
+                return Ok((Value::Array(values), rest));
+            }
+            let (value, after) = parse_value(rest)?;
+            values.push(value);
+            rest = after.trim_start();
+            rest = rest.strip_prefix(',').unwrap_or(rest);
+            if rest.is_empty() {
+                return Err("unterminated array (arrays have to fit on one line)".to_string());
+            }
+        }
+    }
+    // bare words: booleans and numbers, which are passed on as strings (`interval = 5` is 5s)
+    let end = s.find(|c: char| c == ',' || c == ']' || c.is_whitespace()).unwrap_or(s.len());
+    let (word, rest) = s.split_at(end);
+    match word {
+        "true" => Ok((Value::Bool(true), rest)),
+        "false" => Ok((Value::Bool(false), rest)),
+        word if word.parse::<f64>().is_ok() => Ok((Value::String(word.to_string()), rest)),
+        word => Err(format!("invalid value {word:?}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options() {
+        let args = to_args(r#"
+            # a comment
+            listen = ":9100"  # another one
+            interval = 15
+            output = "json"
+            units = "si"
+            watch = true
+            cgroup = true
+            log-csv = '/var/log/mem#1.csv'
+            assert = ["available>512MiB", 'load1<8',]
+
+            [labels]
+            host = "web \"01\""
+        "#).unwrap();
+        assert_eq!(args, [
+            "--listen", ":9100", "--interval", "15", "--json", "--si", "--watch", "--cgroup", "--log-csv", "/var/log/mem#1.csv",
+            "--assert", "available>512MiB", "--assert", "load1<8", "--label", "host=web \"01\"",
+        ]);
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        assert_eq!(to_args("listen = \":9100\"\nnope = 1\n"), Err("line 2: unknown key \"nope\"".to_string()));
+        assert!(to_args("[server]\n").is_err());
+        assert!(to_args("listen = \":9100\n").is_err());
+        assert!(to_args("assert = [\"load1<8\"\n").is_err());
+        assert!(to_args("output = \"yaml\"\n").is_err());
+        assert!(to_args("listen = :9100\n").is_err());
+        assert_eq!(
+            to_args("[labels]\n\"host-name\" = \"web\"\n"),
+            Err("line 2: invalid label name \"host-name\", expected letters, digits and _ not starting with a digit".to_string()),
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! `--log-csv FILE`: a flight recorder appending one row per sample, for chasing slow leaks on
+//! machines without monitoring.
+use std::convert::Infallible;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::fields::Sample;
+use crate::source::Source;
+
+const HEADER: &str = "timestamp,uptime,load1,load5,load15,total_ram,free_ram,shared_ram,buffer_ram,available,total_swap,free_swap,total_high,free_high,procs";
+
+/// Logs a sample every `interval` until the process is killed. Only fails if `path` can't be
+/// opened; later write errors are reported and the sample is skipped.
+pub fn run(source: &Source, path: &Path, interval: Duration, rotate_size: Option<u64>) -> io::Result<Infallible> {
+    let mut log = CsvLog::open(path, rotate_size)?;
+    let source = source.clone();
+    let _sampler = source
+        .sampler()
+        .interval(interval)
+        .on_sample(move |info| {
+            if let Err(e) = log.append(&source.complete(*info)) {
+                eprintln!("sysinfo-dot-h: can't write to {}: {e}", log.path.display());
+            }
+        })
+        .start();
+
+    loop {
+        std::thread::park();
+    }
+}
+
+/// An append only CSV file, optionally rotated to `FILE.1` once it grows past a size.
+#[derive(Debug)]
+pub struct CsvLog {
+    path: PathBuf,
+    rotate_size: Option<u64>,
+    file: File,
+    size: u64,
+}
+
+impl CsvLog {
+    /// Opens (or creates) `path`, writing the header if the file is new.
+    pub fn open(path: &Path, rotate_size: Option<u64>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        let mut log = Self { path: path.to_path_buf(), rotate_size, file, size };
+        if log.size == 0 {
+            log.write_line(HEADER)?;
+        }
+        Ok(log)
+    }
+
+    /// Appends `sample`, rotating first if the file is already too big.
+    pub fn append(&mut self, sample: &Sample) -> io::Result<()> {
+        if self.rotate_size.is_some_and(|limit| self.size >= limit) {
+            self.rotate()?;
+        }
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        self.write_line(&row(timestamp, sample))
+    }
+
+    /// Moves the current file to `FILE.1` (replacing an older one) and starts a new one.
+    fn rotate(&mut self) -> io::Result<()> {
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(".1");
+        std::fs::rename(&self.path, rotated)?;
+        *self = Self::open(&self.path, self.rotate_size)?;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        // one write per line, so a crash can't leave half a row behind
+        self.file.write_all(format!("{line}\n").as_bytes())?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+fn row(timestamp: u64, sample: &Sample) -> String {
+    let info = &sample.info;
+    let available = sample.meminfo.and_then(|meminfo| meminfo.available).map_or(String::new(), |available| available.to_string());
+    format!(
+        "{timestamp},{},{},{},{},{},{},{},{},{available},{},{},{},{},{}",
+        info.uptime.as_secs(), info.loads[0], info.loads[1], info.loads[2],
+        info.total_ram, info.free_ram, info.shared_ram, info.buffer_ram,
+        info.total_swap, info.free_swap, info.total_high, info.free_high, info.procs,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sysinfo_dot_h::SystemInfo;
+
+    fn sample() -> Sample {
+        Sample { info: SystemInfo { free_ram: 4096, procs: 3, ..Default::default() }, meminfo: None }
+    }
+
+    #[test]
+    fn rows_match_the_header() {
+        let row = row(1700000000, &sample());
+        assert_eq!(row, "1700000000,0,0,0,0,0,4096,0,0,,0,0,0,0,3");
+        assert_eq!(row.split(',').count(), HEADER.split(',').count());
+    }
+
+    #[test]
+    fn appends_and_rotates() {
+        let path = std::env::temp_dir().join(format!("sysinfo_dot_h-{}.csv", std::process::id()));
+        let rotated = path.with_extension("csv.1");
+
+        let mut log = CsvLog::open(&path, None).unwrap();
+        log.append(&sample()).unwrap();
+        drop(log);
+        // reopening appends without a second header
+        let mut log = CsvLog::open(&path, Some(1)).unwrap();
+        log.append(&sample()).unwrap();
+        let contents = std::fs::read_to_string(&rotated).unwrap();
+        assert_eq!(contents.lines().count(), 2);
+        assert!(contents.starts_with("timestamp,"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&rotated).unwrap();
+    }
+}
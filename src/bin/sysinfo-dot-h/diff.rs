@@ -0,0 +1,92 @@
+//! `diff before.json after.json`: per field deltas and rates between two saved snapshots, for
+//! before/after comparisons around deployments or test runs.
+use std::fmt;
+use std::path::Path;
+
+use sysinfo_dot_h::SystemInfo;
+
+use crate::human;
+
+/// Reads a snapshot saved with `--json` (or `--pretty`).
+pub fn load(path: &Path) -> Result<SystemInfo, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+    SystemInfo::from_json(&json).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// The comparison table.
+pub struct Diff<'a> {
+    pub before: &'a SystemInfo,
+    pub after: &'a SystemInfo,
+}
+
+impl fmt::Display for Diff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (before, after) = (self.before, self.after);
+        // the uptime doubles as the clock, unless the machine rebooted in between
+        let delta = before.diff(after);
+
+        writeln!(f, "{:<12}{:>14}{:>14}{:>14}{:>16}", "field", "before", "after", "delta", "rate")?;
+        match delta.elapsed {
+            Some(_) => writeln!(
+                f, "{:<12}{:>14}{:>14}{:>14}", "uptime",
+                human::duration(before.uptime), human::duration(after.uptime), format!("+{}", human::duration(after.uptime - before.uptime)),
+            )?,
+            None => writeln!(
+                f, "{:<12}{:>14}{:>14}{:>14}", "uptime",
+                human::duration(before.uptime), human::duration(after.uptime), "rebooted",
+            )?,
+        }
+        for (name, minutes) in [("load1", 0), ("load5", 1), ("load15", 2)] {
+            let (b, a) = (before.loads[minutes], after.loads[minutes]);
+            writeln!(f, "{name:<12}{b:>14.2}{a:>14.2}{:>14}", format!("{:+.2}", delta.loads[minutes]))?;
+        }
+
+        let bytes = [
+            ("total_ram", before.total_ram, after.total_ram, delta.total_ram),
+            ("free_ram", before.free_ram, after.free_ram, delta.free_ram),
+            ("shared_ram", before.shared_ram, after.shared_ram, delta.shared_ram),
+            ("buffer_ram", before.buffer_ram, after.buffer_ram, delta.buffer_ram),
+            ("total_swap", before.total_swap, after.total_swap, delta.total_swap),
+            ("free_swap", before.free_swap, after.free_swap, delta.free_swap),
+            ("total_high", before.total_high, after.total_high, delta.total_high),
+            ("free_high", before.free_high, after.free_high, delta.free_high),
+        ];
+        for (name, b, a, change) in bytes {
+            let rate = delta.rate(change).map_or(String::new(), |per_sec| {
+                let sign = if per_sec < 0.0 { '-' } else { '+' };
+                format!("{sign}{}/s", human::bytes(per_sec.abs() as u64))
+            });
+            writeln!(f, "{name:<12}{:>14}{:>14}{:>14}{rate:>16}", human::bytes(b), human::bytes(a), human::delta_bytes(b, a))?;
+        }
+
+        let rate = delta.rate(delta.procs).map_or(String::new(), |per_sec| format!("{per_sec:+.2}/s"));
+        writeln!(f, "{:<12}{:>14}{:>14}{:>14}{rate:>16}", "procs", before.procs, after.procs, format!("{:+}", delta.procs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn deltas_and_rates() {
+        let before = SystemInfo { uptime: Duration::from_secs(100), free_ram: 3 << 20, procs: 10, loads: [1.0, 0.5, 0.25], ..Default::default() };
+        let after = SystemInfo { uptime: Duration::from_secs(110), free_ram: 2 << 20, procs: 15, loads: [1.5, 0.5, 0.25], ..Default::default() };
+        let table = Diff { before: &before, after: &after }.to_string();
+
+        assert!(table.contains("\nuptime              1m 40s        1m 50s       +0m 10s\n"), "{table}");
+        assert!(table.contains("\nload1                 1.00          1.50         +0.50\n"), "{table}");
+        assert!(table.contains("\nfree_ram           3.0 MiB       2.0 MiB      -1.0 MiB    -102.4 KiB/s\n"), "{table}");
+        assert!(table.ends_with("\nprocs                   10            15            +5         +0.50/s\n"), "{table}");
+    }
+
+    #[test]
+    fn no_rates_across_reboots() {
+        let before = SystemInfo { uptime: Duration::from_secs(100), ..Default::default() };
+        let after = SystemInfo { uptime: Duration::from_secs(5), ..Default::default() };
+        let table = Diff { before: &before, after: &after }.to_string();
+        assert!(table.contains("rebooted"));
+        assert!(!table.contains("/s"));
+    }
+}
@@ -0,0 +1,111 @@
+//! `--listen`: a micro node_exporter serving the sampler's latest snapshot on `/metrics`.
+use std::convert::Infallible;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use sysinfo_dot_h::prometheus;
+
+use crate::source::Source;
+
+/// Binds `addr` (`host:port`, or `:port` for every interface like node_exporter) and serves
+/// forever, sampling every `interval` and putting `labels` on every metric.
+pub fn run(source: &Source, addr: &str, interval: Duration, labels: &[(String, String)]) -> io::Result<Infallible> {
+    let addr = match addr.strip_prefix(':') {
+        Some(port) => format!("0.0.0.0:{port}"),
+        None => addr.to_string(),
+    };
+    let listener = TcpListener::bind(&addr)?;
+    eprintln!("sysinfo-dot-h: serving metrics on http://{}/metrics", listener.local_addr()?);
+
+    // the body is rendered once per sample instead of once per scrape
+    let body = Arc::new(Mutex::new(String::new()));
+    let _sampler = source
+        .sampler()
+        .interval(interval)
+        .on_sample({
+            let body = Arc::clone(&body);
+            let labels = labels.to_vec();
+            let source = source.clone();
+            move |info| {
+                let mut gauges = prometheus::gauges(info);
+                if let Ok(meminfo) = source.meminfo() {
+                    gauges.extend(prometheus::meminfo_gauges(&meminfo));
+                }
+                let labels: Vec<(&str, &str)> = labels.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect();
+                // the names were checked when the options were parsed
+                if let Ok(text) = prometheus::encode_with_labels(&gauges, &labels) {
+                    *body.lock().unwrap_or_else(PoisonError::into_inner) = text;
+                }
+            }
+        })
+        .start();
+
+    for stream in listener.incoming() {
+        let body = body.lock().unwrap_or_else(PoisonError::into_inner).clone();
+        // a misbehaving client mustn't take the exporter down
+        let _ = stream.and_then(|stream| handle(stream, &body));
+    }
+    unreachable!("incoming() never ends")
+}
+
+fn handle(mut stream: TcpStream, body: &str) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // read (and ignore) the headers, closing with unread data would reset the connection
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim_end().is_empty() {
+        header.clear();
+    }
+    stream.write_all(response(&request, body).as_bytes())
+}
+
+fn response(request_line: &str, body: &str) -> String {
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) if body.is_empty() => ("503 Service Unavailable", "text/plain", "no sample yet\n"),
+        (Some("GET"), Some("/metrics")) => ("200 OK", prometheus::CONTENT_TYPE, body),
+        (Some("GET"), Some("/")) => ("200 OK", "text/html", "<a href=\"/metrics\">Metrics</a>\n"),
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "not found\n"),
+        _ => ("405 Method Not Allowed", "text/plain", "only GET is supported\n"),
+    };
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes() {
+        let metrics = response("GET /metrics HTTP/1.1\r\n", "node_procs 1\n");
+        assert!(metrics.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4"));
+        assert!(metrics.ends_with("Content-Length: 13\r\nConnection: close\r\n\r\nnode_procs 1\n"));
+
+        assert!(response("GET /metrics HTTP/1.1\r\n", "").starts_with("HTTP/1.1 503 "));
+        assert!(response("GET /favicon.ico HTTP/1.1\r\n", "").starts_with("HTTP/1.1 404 "));
+        assert!(response("POST /metrics HTTP/1.1\r\n", "").starts_with("HTTP/1.1 405 "));
+    }
+
+    #[test]
+    fn serves_a_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+            let mut response = String::new();
+            io::Read::read_to_string(&mut stream, &mut response).unwrap();
+            response
+        });
+        let (stream, _) = listener.accept().unwrap();
+        handle(stream, "node_procs 1\n").unwrap();
+        assert!(client.join().unwrap().ends_with("\r\n\r\nnode_procs 1\n"));
+    }
+}
@@ -0,0 +1,103 @@
+//! The named values the command line can refer to (`--assert`, ...).
+use sysinfo_dot_h::meminfo::MemInfo;
+use sysinfo_dot_h::SystemInfo;
+
+/// Every field name, in the order they're documented in.
+pub const NAMES: [&str; 19] = [
+    "uptime", "load1", "load5", "load15", "total_ram", "free_ram", "shared_ram", "buffer_ram",
+    "available", "used", "free_ram_percent", "available_percent", "total_swap", "free_swap",
+    "used_swap", "swap_used_percent", "total_high", "free_high", "procs",
+];
+
+/// A snapshot along with the `/proc/meminfo` numbers `sysinfo(2)` doesn't have.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sample {
+    pub info: SystemInfo,
+    pub meminfo: Option<MemInfo>,
+}
+
+impl Sample {
+    /// Reads meminfo to go along with `info`. Unreadable meminfo isn't an error, the fields
+    /// depending on it are just missing.
+    pub fn new(info: SystemInfo) -> Self {
+        Self { info, meminfo: MemInfo::read().ok() }
+    }
+
+    /// The value of the field called `name`, `None` if there's no such field or it's unknown
+    /// on this machine.
+    pub fn get(&self, name: &str) -> Option<f64> {
+        let info = &self.info;
+        let percent = |part: u64, total: u64| if total == 0 { 0.0 } else { part as f64 * 100.0 / total as f64 };
+        let available = self.meminfo.and_then(|meminfo| meminfo.available);
+        Some(match name {
+            "uptime" => info.uptime.as_secs() as f64,
+            "load1" => info.loads[0],
+            "load5" => info.loads[1],
+            "load15" => info.loads[2],
+            "total_ram" => info.total_ram as f64,
+            "free_ram" => info.free_ram as f64,
+            "shared_ram" => info.shared_ram as f64,
+            "buffer_ram" => info.buffer_ram as f64,
+            "available" => available? as f64,
+            "used" => info.total_ram.saturating_sub(available?) as f64,
+            "free_ram_percent" => percent(info.free_ram, info.total_ram),
+            "available_percent" => percent(available?, info.total_ram),
+            "total_swap" => info.total_swap as f64,
+            "free_swap" => info.free_swap as f64,
+            "used_swap" => info.total_swap.saturating_sub(info.free_swap) as f64,
+            "swap_used_percent" => percent(info.total_swap.saturating_sub(info.free_swap), info.total_swap),
+            "total_high" => info.total_high as f64,
+            "free_high" => info.free_high as f64,
+            "procs" => f64::from(info.procs),
+            _ => return None,
+        })
+    }
+}
+
+/// Whether `name` holds bytes, for picking units when printing it.
+pub fn is_bytes(name: &str) -> bool {
+    !name.ends_with("_percent") && !matches!(name, "uptime" | "load1" | "load5" | "load15" | "procs")
+}
+
+/// A number, optionally with a size suffix: `512MiB`, `2G` (binary), `500MB` (decimal), or a
+/// trailing `%` which is just ignored.
+pub fn parse_value(s: &str) -> Option<f64> {
+    const SUFFIXES: [(&str, f64); 16] = [
+        ("KiB", 1024.0), ("MiB", 1048576.0), ("GiB", 1073741824.0), ("TiB", 1099511627776.0),
+        ("kB", 1e3), ("MB", 1e6), ("GB", 1e9), ("TB", 1e12),
+        ("K", 1024.0), ("M", 1048576.0), ("G", 1073741824.0), ("T", 1099511627776.0),
+        ("k", 1024.0), ("B", 1.0), ("%", 1.0), ("", 1.0),
+    ];
+    SUFFIXES.iter().find_map(|&(suffix, multiplier)| {
+        Some(s.strip_suffix(suffix)?.trim().parse::<f64>().ok()? * multiplier)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_name_resolves() {
+        let sample = Sample {
+            info: SystemInfo { total_ram: 4096, free_ram: 1024, ..Default::default() },
+            meminfo: Some(MemInfo { available: Some(3072), ..Default::default() }),
+        };
+        for name in NAMES {
+            assert!(sample.get(name).is_some(), "{name}");
+        }
+        assert_eq!(sample.get("used"), Some(1024.0));
+        assert_eq!(sample.get("available_percent"), Some(75.0));
+        assert_eq!(sample.get("nope"), None);
+        assert_eq!(Sample { meminfo: None, ..sample }.get("available"), None);
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(parse_value("512MiB"), Some(536870912.0));
+        assert_eq!(parse_value("1.5G"), Some(1610612736.0));
+        assert_eq!(parse_value("500 MB"), Some(5e8));
+        assert_eq!(parse_value("8"), Some(8.0));
+        assert_eq!(parse_value("lots"), None);
+    }
+}
@@ -0,0 +1,150 @@
+//! `--format '{available_h} free, load {load1:.2}'`: one line built from placeholders, for
+//! status bars (i3status, polybar, ...) and scripts.
+//!
+//! A placeholder is a [field](fields::NAMES) name, optionally followed by `_h` for the human
+//! readable form (`3.2 GiB`, `4h 2m`, `12.5%`) and `:.N` for N decimals. `{{` and `}}` are
+//! literal braces.
+use std::str::FromStr;
+
+use crate::fields::{self, Sample};
+use crate::human;
+
+/// A parsed format string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Format(Vec<Piece>);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Literal(String),
+    Field { name: String, human: bool, precision: Option<usize> },
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let (placeholder, rest) = chars.as_str().split_once('}').ok_or_else(|| format!("unclosed {{ in {s:?}"))?;
+                    chars = rest.chars();
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(placeholder.parse()?);
+                }
+                '}' => return Err(format!("unmatched }} in {s:?}, write }}}} for a literal one")),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(Self(pieces))
+    }
+}
+
+impl FromStr for Piece {
+    type Err = String;
+
+    fn from_str(placeholder: &str) -> Result<Self, String> {
+        let (name, spec) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+        let precision = match spec {
+            "" => None,
+            spec => Some(
+                spec.strip_prefix('.').and_then(|digits| digits.parse().ok())
+                    .ok_or_else(|| format!("invalid format spec {spec:?} in {{{placeholder}}}, only .N is supported"))?,
+            ),
+        };
+        let (name, human) = match name.strip_suffix("_h") {
+            Some(name) => (name, true),
+            None => (name, false),
+        };
+        if !fields::NAMES.contains(&name) {
+            return Err(format!("unknown placeholder {{{placeholder}}}"));
+        }
+        Ok(Piece::Field { name: name.to_string(), human, precision })
+    }
+}
+
+impl Format {
+    /// Fills in the placeholders. Fields that are unknown on this machine become `?`.
+    pub fn render(&self, sample: &Sample) -> String {
+        let mut out = String::new();
+        for piece in &self.0 {
+            match piece {
+                Piece::Literal(literal) => out.push_str(literal),
+                Piece::Field { name, human, precision } => match sample.get(name) {
+                    Some(value) => out.push_str(&field(name, value, *human, *precision)),
+                    None => out.push('?'),
+                },
+            }
+        }
+        out
+    }
+}
+
+fn field(name: &str, value: f64, human: bool, precision: Option<usize>) -> String {
+    match (human, precision) {
+        (true, _) if fields::is_bytes(name) => human::bytes(value as u64),
+        (true, _) if name == "uptime" => human::duration(std::time::Duration::from_secs_f64(value)),
+        (true, precision) if name.ends_with("_percent") => format!("{value:.*}%", precision.unwrap_or(1)),
+        (_, Some(precision)) => format!("{value:.precision$}"),
+        (_, None) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use sysinfo_dot_h::meminfo::MemInfo;
+    use sysinfo_dot_h::SystemInfo;
+
+    fn render(format: &str) -> Result<String, String> {
+        let sample = Sample {
+            info: SystemInfo {
+                uptime: Duration::from_secs(4 * 3600 + 120),
+                loads: [0.1875, 0.5, 0.25],
+                total_ram: 8 << 30,
+                ..Default::default()
+            },
+            meminfo: Some(MemInfo { available: Some(2 << 30), ..Default::default() }),
+        };
+        Ok(format.parse::<Format>()?.render(&sample))
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(render("{available_h} free, load {load1:.2}").unwrap(), "2.0 GiB free, load 0.19");
+        assert_eq!(render("{available} {load1} {procs}").unwrap(), "2147483648 0.1875 0");
+        assert_eq!(render("up {uptime_h} ({uptime}s)").unwrap(), "up 4h 2m (14520s)");
+        assert_eq!(render("{available_percent_h} {available_percent:.0}").unwrap(), "25.0% 25");
+        assert_eq!(render("{{literal}} {load5:.1}").unwrap(), "{literal} 0.5");
+    }
+
+    #[test]
+    fn unknown_values() {
+        let sample = Sample { info: SystemInfo::default(), meminfo: None };
+        assert_eq!("{available_h} free".parse::<Format>().unwrap().render(&sample), "? free");
+    }
+
+    #[test]
+    fn invalid_formats() {
+        assert!(render("{nope}").is_err());
+        assert!(render("{load1").is_err());
+        assert!(render("load1}").is_err());
+        assert!(render("{load1:>8}").is_err());
+    }
+}
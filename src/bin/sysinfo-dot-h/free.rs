@@ -0,0 +1,94 @@
+//! `--free`: the same table as `free -h` from procps-ng, for images that don't ship it.
+use std::fmt;
+
+use sysinfo_dot_h::meminfo::MemInfo;
+
+use crate::human::{self, Units};
+
+/// `free -h`'s output for a meminfo (`free -h --si` with `--si`, `free -b` with `--bytes`).
+pub struct Free<'a>(pub &'a MemInfo);
+
+impl fmt::Display for Free<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let meminfo = self.0;
+        // old kernels have no MemAvailable; procps-ng 4 computes used as total - available
+        let available = meminfo.available.unwrap_or(meminfo.free);
+        let used = meminfo.total.saturating_sub(available);
+        let swap_used = meminfo.swap_total.saturating_sub(meminfo.swap_free);
+        let units = human::units();
+        let size = |bytes| size(bytes, units);
+
+        writeln!(f, "{:<7}{:>13}{:>12}{:>12}{:>12}{:>12}{:>12}", "", "total", "used", "free", "shared", "buff/cache", "available")?;
+        writeln!(
+            f, "{:<7}{:>13}{:>12}{:>12}{:>12}{:>12}{:>12}", "Mem:",
+            size(meminfo.total), size(used), size(meminfo.free), size(meminfo.shmem), size(meminfo.buff_cache()), size(available),
+        )?;
+        writeln!(f, "{:<7}{:>13}{:>12}{:>12}", "Swap:", size(meminfo.swap_total), size(swap_used), size(meminfo.swap_free))
+    }
+}
+
+/// Sizes the way `free -h` prints them: at most four characters plus the `i`, so "0B", "381Mi",
+/// "5.9Gi". `--si` drops the `i` (and divides by 1000), `--bytes` prints plain numbers.
+fn size(bytes: u64, units: Units) -> String {
+    const UNITS: [char; 6] = ['K', 'M', 'G', 'T', 'P', 'E'];
+
+    let (base, suffix) = match units {
+        Units::Binary => (1024.0, "i"),
+        Units::Si => (1000.0, ""),
+        Units::Bytes => return bytes.to_string(),
+    };
+    let plain = format!("{bytes}B");
+    if plain.len() <= 4 {
+        return plain;
+    }
+    let mut value = bytes as f64;
+    for unit in UNITS {
+        value /= base;
+        let decimal = format!("{value:.1}{unit}");
+        if decimal.len() <= 4 {
+            return decimal + suffix;
+        }
+        let whole = format!("{}{unit}", value as u64);
+        if whole.len() <= 4 {
+            return whole + suffix;
+        }
+    }
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        assert_eq!(size(0, Units::Binary), "0B");
+        assert_eq!(size(512, Units::Binary), "512B");
+        assert_eq!(size(9048 * 1024, Units::Binary), "8.8Mi");
+        assert_eq!(size(390248 * 1024, Units::Binary), "381Mi");
+        assert_eq!(size(6147400 * 1024, Units::Binary), "5.9Gi");
+        assert_eq!(size(16 << 30, Units::Binary), "16Gi");
+        assert_eq!(size(6147400 * 1024, Units::Si), "6.3G");
+        assert_eq!(size(6147400 * 1024, Units::Bytes), "6294937600");
+    }
+
+    #[test]
+    fn matches_procps() {
+        let meminfo = MemInfo {
+            total: 6147400 * 1024,
+            free: 390248 * 1024,
+            available: Some(5527028 * 1024),
+            buffers: 61276 * 1024,
+            cached: 5199940 * 1024,
+            shmem: 9048 * 1024,
+            s_reclaimable: 182128 * 1024,
+            ..Default::default()
+        };
+        // copied from `free -h` on the machine the numbers came from
+        // (no `\` continuation, it would eat the indentation of the header)
+        assert_eq!(Free(&meminfo).to_string(), "               total        used        free      shared  buff/cache   available
+Mem:           5.9Gi       605Mi       381Mi       8.8Mi       5.2Gi       5.3Gi
+Swap:             0B          0B          0B
+");
+    }
+}
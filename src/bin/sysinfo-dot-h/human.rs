@@ -0,0 +1,149 @@
+//! Human readable output.
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+use sysinfo_dot_h::psi::{self, Resource};
+use sysinfo_dot_h::{sysinfo, SystemInfo};
+
+/// How sizes are printed, `--binary` (the default), `--si` or `--bytes`.
+pub use sysinfo_dot_h::units::Units;
+
+/// Set once by `main`, read by everything printing sizes so every mode agrees.
+static UNITS: AtomicU8 = AtomicU8::new(Units::Binary as u8);
+
+pub fn set_units(units: Units) {
+    UNITS.store(units as u8, Ordering::Relaxed);
+}
+
+pub fn units() -> Units {
+    match UNITS.load(Ordering::Relaxed) {
+        1 => Units::Si,
+        2 => Units::Bytes,
+        _ => Units::Binary,
+    }
+}
+
+/// Formats `bytes` in the current [`units`] with one decimal ("3.2 GiB").
+pub fn bytes(bytes: u64) -> String {
+    sysinfo_dot_h::units::bytes(bytes, units())
+}
+
+/// "3d 4h 23m", leaving out leading zero units.
+pub fn duration(duration: Duration) -> String {
+    sysinfo_dot_h::units::duration(duration)
+}
+
+/// [`bytes`] for a difference, always with a sign ("+1.5 MiB", "-512 B", "+0 B").
+pub fn delta_bytes(before: u64, after: u64) -> String {
+    if after >= before {
+        format!("+{}", bytes(after - before))
+    } else {
+        format!("-{}", bytes(before - after))
+    }
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 { 0.0 } else { part as f64 * 100.0 / total as f64 }
+}
+
+/// The raw struct field by field, followed by the derived values.
+pub struct Report<'a>(pub &'a sysinfo);
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let raw = self.0;
+        let info = SystemInfo::from(*raw);
+
+        writeln!(f, "raw:")?;
+        writeln!(f, "  uptime     {}", raw.uptime)?;
+        writeln!(f, "  loads      {} {} {}", raw.loads[0], raw.loads[1], raw.loads[2])?;
+        for (name, value) in [
+            ("totalram", raw.totalram),
+            ("freeram", raw.freeram),
+            ("sharedram", raw.sharedram),
+            ("bufferram", raw.bufferram),
+            ("totalswap", raw.totalswap),
+            ("freeswap", raw.freeswap),
+        ] {
+            writeln!(f, "  {name:<10} {value}")?;
+        }
+        writeln!(f, "  procs      {}", raw.procs)?;
+        writeln!(f, "  totalhigh  {}", raw.totalhigh)?;
+        writeln!(f, "  freehigh   {}", raw.freehigh)?;
+        writeln!(f, "  mem_unit   {}", raw.mem_unit)?;
+
+        writeln!(f, "derived:")?;
+        write!(f, "{}", Derived(&info))
+    }
+}
+
+/// The normalized values, with the units worked out.
+pub struct Derived<'a>(pub &'a SystemInfo);
+
+impl fmt::Display for Derived<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let info = self.0;
+        writeln!(f, "  uptime     {}", duration(info.uptime))?;
+        writeln!(f, "  load       {:.2} {:.2} {:.2}", info.loads[0], info.loads[1], info.loads[2])?;
+        writeln!(
+            f,
+            "  ram        {} free of {} ({:.1}% free), {} shared, {} buffers",
+            bytes(info.free_ram), bytes(info.total_ram), percent(info.free_ram, info.total_ram),
+            bytes(info.shared_ram), bytes(info.buffer_ram),
+        )?;
+        writeln!(
+            f,
+            "  swap       {} free of {} ({:.1}% free)",
+            bytes(info.free_swap), bytes(info.total_swap), percent(info.free_swap, info.total_swap),
+        )?;
+        writeln!(f, "  high       {} free of {}", bytes(info.free_high), bytes(info.total_high))?;
+        writeln!(f, "  procs      {}", info.procs)
+    }
+}
+
+/// `--pressure`: the share of time something was stalled on each resource, as lines going
+/// along with [`Derived`].
+pub struct Pressure<'a>(pub &'a [(Resource, Result<psi::Pressure, String>)]);
+
+impl fmt::Display for Pressure<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  {:<10} {:>7} {:>7} {:>7}", "pressure", "avg10", "avg60", "avg300")?;
+        for (resource, pressure) in self.0 {
+            match pressure {
+                Ok(psi::Pressure { some, .. }) => {
+                    writeln!(f, "    {resource:<8} {:>6.2}% {:>6.2}% {:>6.2}%", some.avg10, some.avg60, some.avg300)?;
+                }
+                Err(e) => writeln!(f, "    {resource:<8} unavailable ({e})")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deltas() {
+        assert_eq!(delta_bytes(1024, 3072), "+2.0 KiB");
+        assert_eq!(delta_bytes(3072, 1024), "-2.0 KiB");
+        assert_eq!(delta_bytes(7, 7), "+0 B");
+    }
+
+    #[test]
+    fn pressure() {
+        let cpu = psi::Pressure { some: psi::Averages { avg10: 4.68, avg60: 4.9, avg300: 4.55, ..Default::default() }, full: None };
+        let lines = Pressure(&[(Resource::Cpu, Ok(cpu)), (Resource::Io, Err("no".to_string()))]).to_string();
+        assert_eq!(lines, "  pressure     avg10   avg60  avg300\n    cpu        4.68%   4.90%   4.55%\n    io       unavailable (no)\n");
+    }
+
+    #[test]
+    fn report_has_raw_and_derived_values() {
+        let report = Report(&sysinfo_dot_h::compat::collect()).to_string();
+        assert!(report.starts_with("raw:\n  uptime "));
+        assert!(report.contains("\n  mem_unit   "));
+        assert!(report.contains("\nderived:\n  uptime "));
+    }
+}
@@ -0,0 +1,212 @@
+//! `sysinfo-dot-h`: prints exactly what the library reports on this machine.
+//!
+//! Handy as a quick look for ops, and as a smoke test when bringing the crate up on a new
+//! architecture (the raw values show whether the struct layout is right).
+mod args;
+mod assert;
+mod bench;
+mod config;
+mod csv;
+mod diff;
+mod exporter;
+mod fields;
+mod format;
+mod free;
+mod human;
+mod json;
+mod source;
+mod strict;
+mod template;
+mod top;
+#[cfg(feature = "tui")]
+mod tui;
+mod uptime;
+mod watch;
+
+use std::process::ExitCode;
+
+use args::{Command, Options, Output};
+use source::Source;
+use sysinfo_dot_h::recording::Recorder;
+use sysinfo_dot_h::sampler;
+
+fn main() -> ExitCode {
+    let options = match args::with_config(std::env::args().skip(1).collect()).and_then(args::parse) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("sysinfo-dot-h: {e}\n\n{}", args::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    run(&options)
+}
+
+fn run(options: &Options) -> ExitCode {
+    if options.help {
+        print!("{}", args::USAGE);
+        return ExitCode::SUCCESS;
+    }
+    if options.version {
+        println!("sysinfo-dot-h {}", env!("CARGO_PKG_VERSION"));
+        return ExitCode::SUCCESS;
+    }
+    human::set_units(options.units);
+
+    let source = match (&options.replay, options.cgroup) {
+        (Some(path), _) => Source::replay(path),
+        (None, true) => Source::cgroup(),
+        (None, false) => Ok(Source::Live),
+    };
+    let source = match source {
+        Ok(source) => source,
+        Err(error) if options.strict => {
+            let source = if options.replay.is_some() { "replay" } else { "cgroup" };
+            eprintln!("{}", strict::report(&[strict::Failure { source: source.to_string(), error }]));
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            eprintln!("sysinfo-dot-h: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Some(command) = &options.command {
+        return match command {
+            Command::Diff(before, after) => compare(before, after),
+            Command::Bench(duration) => {
+                let results: Vec<_> = sysinfo_dot_h::backend::Backend::ALL
+                    .into_iter()
+                    .map(|backend| bench::measure(backend, *duration).map_err(|e| (backend, e)))
+                    .collect();
+                print!("{}", bench::Table(&results));
+                ExitCode::SUCCESS
+            }
+            Command::Record(path) => {
+                let Err(e) = record(&source, path, options.interval.unwrap_or(sampler::DEFAULT_INTERVAL));
+                eprintln!("sysinfo-dot-h: can't record to {}: {e}", path.display());
+                ExitCode::FAILURE
+            }
+        };
+    }
+    if options.strict {
+        let failures = strict::check(&source, options);
+        if !failures.is_empty() {
+            eprintln!("{}", strict::report(&failures));
+            return ExitCode::FAILURE;
+        }
+    }
+    if !options.asserts.is_empty() {
+        return check(&source, &options.asserts);
+    }
+    if options.tui {
+        return run_tui(&source, options.interval.unwrap_or(sampler::DEFAULT_INTERVAL), options.pressure);
+    }
+    if options.top {
+        top::run(&source, options.interval.unwrap_or(args::DEFAULT_WATCH_INTERVAL));
+    }
+    if let Some(path) = &options.log_csv {
+        let Err(e) = csv::run(&source, path, options.interval.unwrap_or(args::DEFAULT_LOG_INTERVAL), options.rotate_size);
+        eprintln!("sysinfo-dot-h: can't log to {}: {e}", path.display());
+        return ExitCode::FAILURE;
+    }
+    if let Some(addr) = &options.listen {
+        let Err(e) = exporter::run(&source, addr, options.interval.unwrap_or(sampler::DEFAULT_INTERVAL), &options.labels);
+        eprintln!("sysinfo-dot-h: can't serve on {addr}: {e}");
+        return ExitCode::FAILURE;
+    }
+    if let Some(interval) = options.watch {
+        watch::run(&source, interval, options.output.clone(), options.pressure);
+    }
+
+    match print_once(&source, &options.output) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("sysinfo-dot-h: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn print_once(source: &Source, output: &Output) -> Result<(), String> {
+    if let (Some(scope), Output::Human | Output::Free) = (source.scope(), output) {
+        println!("scope: {scope}");
+    }
+    match output {
+        // only a live snapshot has the raw struct
+        Output::Human if *source == Source::Live => print!("{}", human::Report(&sysinfo_dot_h::compat::try_collect()?)),
+        Output::Human => print!("derived:\n{}", human::Derived(&source.sample()?.info)),
+        Output::Json => println!("{}", source.sample()?.info.to_json()),
+        Output::PrettyJson => println!("{}", json::pretty(&source.sample()?.info.to_json())),
+        Output::Free => print!("{}", free::Free(&source.meminfo()?)),
+        Output::Uptime => println!("{}", uptime::line(&source.sample()?.info)),
+        Output::Line => println!("{}", source.sample()?.info.summary(human::units())),
+        Output::Format(format) => println!("{}", format.render(&source.sample()?)),
+        Output::Template(template) => print!("{}", template.render(&source.sample()?)),
+    }
+    Ok(())
+}
+
+/// `record`: appends a sample every `interval` to `path` until killed.
+fn record(source: &Source, path: &std::path::Path, interval: std::time::Duration) -> std::io::Result<std::convert::Infallible> {
+    let mut recorder = Recorder::new(std::fs::File::create(path)?);
+    let _sampler = source
+        .sampler()
+        .interval(interval)
+        .on_sample(move |info| {
+            if let Err(e) = recorder.record(info) {
+                eprintln!("sysinfo-dot-h: can't write the recording: {e}");
+            }
+        })
+        .start();
+    loop {
+        std::thread::park();
+    }
+}
+
+#[cfg(feature = "tui")]
+fn run_tui(source: &Source, interval: std::time::Duration, pressure: bool) -> ExitCode {
+    match tui::run(source, interval, pressure) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("sysinfo-dot-h: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(not(feature = "tui"))]
+fn run_tui(_source: &Source, _interval: std::time::Duration, _pressure: bool) -> ExitCode {
+    eprintln!("sysinfo-dot-h: built without --tui, reinstall with `--features tui`");
+    ExitCode::from(2)
+}
+
+fn compare(before: &std::path::Path, after: &std::path::Path) -> ExitCode {
+    match diff::load(before).and_then(|before| Ok((before, diff::load(after)?))) {
+        Ok((before, after)) => {
+            print!("{}", diff::Diff { before: &before, after: &after });
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("sysinfo-dot-h: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn check(source: &Source, asserts: &[assert::Assertion]) -> ExitCode {
+    let sample = match source.sample() {
+        Ok(sample) => sample,
+        Err(e) => {
+            eprintln!("sysinfo-dot-h: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut failed = false;
+    for assertion in asserts {
+        if let Err(e) = assertion.check(&sample) {
+            eprintln!("sysinfo-dot-h: assertion failed: {e}");
+            failed = true;
+        }
+    }
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
@@ -0,0 +1,124 @@
+//! Where the snapshots come from: this machine, this machine's cgroup (`--cgroup`), or a
+//! recording made with `record` and played back with `--replay`.
+use std::path::Path;
+use std::sync::Arc;
+
+use sysinfo_dot_h::cgroup::Cgroup;
+use sysinfo_dot_h::meminfo::MemInfo;
+use sysinfo_dot_h::psi::{Pressure, Resource};
+use sysinfo_dot_h::recording::{self, Recorded, ReplayProvider};
+use sysinfo_dot_h::sampler::{self, Sampler};
+use sysinfo_dot_h::vmstat::VmStat;
+use sysinfo_dot_h::SystemInfo;
+
+use crate::fields::Sample;
+use crate::human;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Live,
+    /// Live, with the totals and free amounts capped by the cgroup's limits
+    Cgroup,
+    /// Never empty
+    Replay(Arc<[Recorded]>),
+}
+
+impl Source {
+    /// Loads a recording for `--replay`.
+    pub fn replay(path: &Path) -> Result<Self, String> {
+        let samples = recording::open(path)?;
+        if samples.is_empty() {
+            return Err(format!("{} is an empty recording", path.display()));
+        }
+        Ok(Source::Replay(samples.into()))
+    }
+
+    /// `--cgroup`, checking there's a cgroup to go by.
+    pub fn cgroup() -> Result<Self, String> {
+        Cgroup::current()?;
+        Ok(Source::Cgroup)
+    }
+
+    /// What the numbers describe, for the human readable modes. `None` for the host, which is
+    /// what they describe without `--cgroup`.
+    pub fn scope(&self) -> Option<String> {
+        match self {
+            Source::Cgroup => Some(match Cgroup::current() {
+                Ok(Cgroup { version, path, memory_max: Some(max), .. }) => format!("cgroup v{version} {path}, limit {}", human::bytes(max)),
+                Ok(Cgroup { version, path, memory_max: None, .. }) => format!("cgroup v{version} {path}, no memory limit (host values)"),
+                Err(e) => format!("cgroup ({e})"),
+            }),
+            Source::Live | Source::Replay(_) => None,
+        }
+    }
+
+    /// A single snapshot for the one shot modes: a fresh one, or the last one recorded.
+    pub fn sample(&self) -> Result<Sample, String> {
+        match self {
+            Source::Live => Ok(Sample::new(sysinfo_dot_h::try_collect_info()?)),
+            Source::Cgroup => Ok(self.complete(Cgroup::current()?.apply(&sysinfo_dot_h::try_collect_info()?))),
+            Source::Replay(samples) => Ok(self.complete(samples[samples.len() - 1].info)),
+        }
+    }
+
+    /// Adds what this source knows beyond `info`. Recordings don't have `/proc/meminfo`.
+    pub fn complete(&self, info: SystemInfo) -> Sample {
+        match self {
+            Source::Live => Sample::new(info),
+            Source::Cgroup => Sample { info, meminfo: self.meminfo().ok() },
+            Source::Replay(_) => Sample { info, meminfo: None },
+        }
+    }
+
+    /// `/proc/meminfo`, which only exists live.
+    pub fn meminfo(&self) -> Result<MemInfo, String> {
+        match self {
+            Source::Live => Ok(MemInfo::read()?),
+            Source::Cgroup => Ok(Cgroup::current()?.apply_meminfo(&MemInfo::read()?)),
+            Source::Replay(_) => Err("/proc/meminfo isn't part of recordings".to_string()),
+        }
+    }
+
+    /// `/proc/vmstat`, which only exists live.
+    pub fn vmstat(&self) -> Result<VmStat, String> {
+        match self {
+            Source::Live | Source::Cgroup => Ok(VmStat::read()?),
+            Source::Replay(_) => Err("/proc/vmstat isn't part of recordings".to_string()),
+        }
+    }
+
+    /// `/proc/pressure` for every resource, which is always the host's and only exists live.
+    pub fn pressure(&self) -> Vec<(Resource, Result<Pressure, String>)> {
+        let read = |resource| match self {
+            Source::Live | Source::Cgroup => Ok(Pressure::read(resource)?),
+            Source::Replay(_) => Err("pressure isn't part of recordings".to_string()),
+        };
+        Resource::ALL.into_iter().map(|resource| (resource, read(resource))).collect()
+    }
+
+    /// A sampler builder fed by this source. Replays follow the recorded timing, whatever the
+    /// sampler's interval, and stay on the last sample once they're over.
+    pub fn sampler(&self) -> sampler::Builder {
+        match self {
+            Source::Live => Sampler::builder(),
+            Source::Cgroup => Sampler::builder().source(|| Ok(Cgroup::current()?.apply(&sysinfo_dot_h::try_collect_info()?))),
+            Source::Replay(samples) => Sampler::builder().provider(ReplayProvider::new(Arc::clone(samples)).timed()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn last_sample() {
+        let samples: Vec<Recorded> = (0..3u16)
+            .map(|i| Recorded { elapsed: Duration::from_secs(u64::from(i)), info: SystemInfo { procs: i, ..Default::default() } })
+            .collect();
+        let replay = Source::Replay(samples.into());
+        assert_eq!(replay.sample().unwrap().info.procs, 2);
+        assert!(replay.meminfo().is_err());
+    }
+}
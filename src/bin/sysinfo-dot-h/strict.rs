@@ -0,0 +1,74 @@
+//! `--strict`: fail instead of leaving out (or zeroing) whatever couldn't be read, for
+//! automation that would rather stop than act on partial numbers.
+//!
+//! Every source the options turn on is tried once before the mode starts. If any fails, the
+//! failures go to stderr as JSON and the exit code is 1:
+//!
+//! ```text
+//! {"errors":[{"source":"meminfo","error":"can't read /proc/meminfo: No such file or directory (os error 2)"}]}
+//! ```
+use crate::args::Options;
+use crate::json;
+use crate::source::Source;
+
+/// A source that couldn't be read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    /// `sysinfo`, `meminfo`, `vmstat`, `pressure/<resource>`, `cgroup` or `replay`
+    pub source: String,
+    pub error: String,
+}
+
+/// Tries everything the options need from `source`. `/proc/meminfo` is always needed live,
+/// pressure with `--pressure` and vmstat with `--top`.
+pub fn check(source: &Source, options: &Options) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    let mut record = |name: &str, result: Result<(), String>| {
+        if let Err(error) = result {
+            failures.push(Failure { source: name.to_string(), error });
+        }
+    };
+    record("sysinfo", source.sample().map(drop));
+    if !matches!(source, Source::Replay(_)) {
+        record("meminfo", source.meminfo().map(drop));
+    }
+    if options.top {
+        record("vmstat", source.vmstat().map(drop));
+    }
+    if options.pressure {
+        for (resource, pressure) in source.pressure() {
+            record(&format!("pressure/{resource}"), pressure.map(drop));
+        }
+    }
+    failures
+}
+
+/// The failures as one line of JSON.
+pub fn report(failures: &[Failure]) -> String {
+    let errors: Vec<String> = failures
+        .iter()
+        .map(|failure| format!("{{\"source\":{},\"error\":{}}}", json::string(&failure.source), json::string(&failure.error)))
+        .collect();
+    format!("{{\"errors\":[{}]}}", errors.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sysinfo_dot_h::recording::Recorded;
+
+    #[test]
+    fn only_whats_turned_on() {
+        let replay = Source::Replay(vec![Recorded { elapsed: Default::default(), info: Default::default() }].into());
+        // recordings have no meminfo, but that's expected rather than a failure
+        assert_eq!(check(&replay, &Options::default()), []);
+
+        let failures = check(&replay, &Options { pressure: true, watch: Some(Default::default()), ..Default::default() });
+        assert_eq!(failures.len(), 3);
+        assert_eq!(
+            report(&failures[..1]),
+            r#"{"errors":[{"source":"pressure/cpu","error":"pressure isn't part of recordings"}]}"#
+        );
+        assert_eq!(check(&Source::Live, &Options { top: true, ..Default::default() }), []);
+    }
+}
@@ -0,0 +1,284 @@
+//! `--template FILE`: a whole report in a file, for MOTD banners and the like. `--format` is
+//! the one line version.
+//!
+//! ```text
+//! Welcome to web-01, up {{ uptime | human_duration }}.
+//! {% for field in fields %}
+//!   {{ field.name | pad(12) }} {{ field.value | human }}
+//! {% endfor %}
+//! ```
+//!
+//! `{{ expression }}` is a [field](fields::NAMES) (or, in a loop, `field.name`/`field.value`)
+//! followed by any number of `| filter`s. `{% for x in fields %}...{% endfor %}` repeats its
+//! body for every field this machine knows. A `{% %}` tag on a line of its own takes the whole
+//! line with it, so loops don't leave blank lines behind.
+use std::fmt::Write;
+
+use crate::fields::{self, Sample};
+use crate::human;
+
+/// A parsed template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template(Vec<Node>);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Expression { subject: Subject, filters: Vec<Filter> },
+    For { variable: String, body: Vec<Node> },
+}
+
+/// What an expression starts from.
+#[derive(Debug, Clone, PartialEq)]
+enum Subject {
+    Field(String),
+    /// `x.name` in `{% for x in fields %}`
+    LoopName(String),
+    /// `x.value`
+    LoopValue(String),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Filter {
+    /// Like `--format`'s `_h`: bytes, durations or percentages depending on the field
+    Human,
+    HumanBytes,
+    HumanDuration,
+    Percent,
+    Round(usize),
+    /// Left aligned to a width
+    Pad(usize),
+    /// Right aligned to a width
+    Rpad(usize),
+    Upper,
+}
+
+/// A value halfway through its filters: still a number (and which field it came from), or
+/// already text.
+enum Rendered<'a> {
+    Number { field: &'a str, value: f64 },
+    Text(String),
+}
+
+impl Template {
+    /// Parses a template. Errors name the line they're on.
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut stack: Vec<(Option<String>, Vec<Node>)> = vec![(None, Vec::new())];
+        let mut rest = template;
+        let mut text = String::new();
+        loop {
+            let Some(start) = rest.find("{{").into_iter().chain(rest.find("{%")).min() else {
+                text.push_str(rest);
+                break;
+            };
+            let line = template[..template.len() - rest.len() + start].matches('\n').count() + 1;
+            let at_line = |e: String| format!("line {line}: {e}");
+            text.push_str(&rest[..start]);
+            let tag = &rest[start..];
+            let close = if tag.starts_with("{{") { "}}" } else { "%}" };
+            let end = tag.find(close).ok_or_else(|| at_line(format!("unclosed {}", &tag[..2])))?;
+            let inside = tag[2..end].trim();
+            rest = &tag[end + 2..];
+
+            if close == "}}" {
+                let expression = parse_expression(inside).map_err(at_line)?;
+                if let Node::Expression { subject: Subject::LoopName(variable) | Subject::LoopValue(variable), .. } = &expression {
+                    if !stack.iter().any(|(loop_variable, _)| loop_variable.as_ref() == Some(variable)) {
+                        return Err(at_line(format!("{variable:?} isn't a loop variable here")));
+                    }
+                }
+                let nodes = &mut stack.last_mut().expect("the root is never popped").1;
+                flush(&mut text, nodes);
+                nodes.push(expression);
+                continue;
+            }
+            let nodes = &mut stack.last_mut().expect("the root is never popped").1;
+            // a block tag alone on its line disappears along with the line
+            let line_start = text.rfind('\n').map_or(0, |at| at + 1);
+            if text[line_start..].trim().is_empty() {
+                if let Some(after) = rest.trim_start_matches([' ', '\t']).strip_prefix('\n') {
+                    text.truncate(line_start);
+                    rest = after;
+                }
+            }
+            flush(&mut text, nodes);
+            let words: Vec<&str> = inside.split_whitespace().collect();
+            match words[..] {
+                ["for", variable, "in", "fields"] => stack.push((Some(variable.to_string()), Vec::new())),
+                ["endfor"] => {
+                    let (Some(variable), body) = stack.pop().expect("the root is never popped") else {
+                        return Err(at_line("{% endfor %} without a {% for %}".to_string()));
+                    };
+                    stack.last_mut().expect("for loops are pushed onto the root").1.push(Node::For { variable, body });
+                }
+                _ => return Err(at_line(format!("unknown tag {{% {inside} %}}, only for ... in fields and endfor exist"))),
+            }
+        }
+        let (variable, mut nodes) = stack.pop().expect("the root is never popped");
+        if variable.is_some() || !stack.is_empty() {
+            return Err("{% for %} without an {% endfor %}".to_string());
+        }
+        flush(&mut text, &mut nodes);
+        Ok(Self(nodes))
+    }
+
+    /// Fills the template in. Fields that are unknown on this machine become `?`.
+    pub fn render(&self, sample: &Sample) -> String {
+        let mut out = String::new();
+        render(&self.0, sample, &[], &mut out);
+        out
+    }
+}
+
+fn flush(text: &mut String, nodes: &mut Vec<Node>) {
+    if !text.is_empty() {
+        nodes.push(Node::Text(std::mem::take(text)));
+    }
+}
+
+fn parse_expression(expression: &str) -> Result<Node, String> {
+    let mut parts = expression.split('|').map(str::trim);
+    let subject = match parts.next().unwrap_or_default() {
+        name if fields::NAMES.contains(&name) => Subject::Field(name.to_string()),
+        path => match path.split_once('.') {
+            Some((variable, "name")) => Subject::LoopName(variable.to_string()),
+            Some((variable, "value")) => Subject::LoopValue(variable.to_string()),
+            _ => return Err(format!("unknown field {path:?}")),
+        },
+    };
+    let filters = parts.map(parse_filter).collect::<Result<_, _>>()?;
+    Ok(Node::Expression { subject, filters })
+}
+
+fn parse_filter(filter: &str) -> Result<Filter, String> {
+    let (name, argument) = match filter.split_once('(') {
+        Some((name, argument)) => {
+            let argument = argument.strip_suffix(')').and_then(|n| n.trim().parse().ok()).ok_or_else(|| format!("invalid filter {filter:?}"))?;
+            (name.trim(), Some(argument))
+        }
+        None => (filter, None),
+    };
+    Ok(match (name, argument) {
+        ("human", None) => Filter::Human,
+        ("human_bytes", None) => Filter::HumanBytes,
+        ("human_duration", None) => Filter::HumanDuration,
+        ("percent", None) => Filter::Percent,
+        ("upper", None) => Filter::Upper,
+        ("round", Some(decimals)) => Filter::Round(decimals),
+        ("pad", Some(width)) => Filter::Pad(width),
+        ("rpad", Some(width)) => Filter::Rpad(width),
+        _ => return Err(format!("unknown filter {filter:?}")),
+    })
+}
+
+/// `loops` holds the loop variables in scope and the field each one is at.
+fn render(nodes: &[Node], sample: &Sample, loops: &[(&str, &str)], out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Expression { subject, filters } => {
+                let field = |variable: &str| loops.iter().rev().find(|(name, _)| *name == variable).map(|&(_, field)| field);
+                let start = match subject {
+                    Subject::Field(name) => number(sample, name),
+                    Subject::LoopName(variable) => Rendered::Text(field(variable).unwrap_or("?").to_string()),
+                    Subject::LoopValue(variable) => field(variable).map_or(Rendered::Text("?".to_string()), |name| number(sample, name)),
+                };
+                let rendered = filters.iter().fold(start, |value, filter| apply(*filter, value));
+                match rendered {
+                    Rendered::Number { value, .. } => {
+                        let _ = write!(out, "{value}");
+                    }
+                    Rendered::Text(text) => out.push_str(&text),
+                }
+            }
+            Node::For { variable, body } => {
+                for name in fields::NAMES.into_iter().filter(|name| sample.get(name).is_some()) {
+                    let mut inner = loops.to_vec();
+                    inner.push((variable.as_str(), name));
+                    render(body, sample, &inner, out);
+                }
+            }
+        }
+    }
+}
+
+fn number<'a>(sample: &Sample, field: &'a str) -> Rendered<'a> {
+    match sample.get(field) {
+        Some(value) => Rendered::Number { field, value },
+        None => Rendered::Text("?".to_string()),
+    }
+}
+
+fn apply(filter: Filter, value: Rendered<'_>) -> Rendered<'_> {
+    let text = |value: Rendered<'_>| match value {
+        Rendered::Number { value, .. } => value.to_string(),
+        Rendered::Text(text) => text,
+    };
+    match (filter, value) {
+        (Filter::Human, Rendered::Number { field, value }) => Rendered::Text(match field {
+            field if fields::is_bytes(field) => human::bytes(value as u64),
+            "uptime" => human::duration(std::time::Duration::from_secs_f64(value)),
+            field if field.ends_with("_percent") => format!("{value:.1}%"),
+            "load1" | "load5" | "load15" => format!("{value:.2}"),
+            _ => value.to_string(),
+        }),
+        (Filter::HumanBytes, Rendered::Number { value, .. }) => Rendered::Text(human::bytes(value as u64)),
+        (Filter::HumanDuration, Rendered::Number { value, .. }) => {
+            Rendered::Text(human::duration(std::time::Duration::from_secs_f64(value.max(0.0))))
+        }
+        (Filter::Percent, Rendered::Number { value, .. }) => Rendered::Text(format!("{value:.1}%")),
+        (Filter::Round(decimals), Rendered::Number { value, .. }) => Rendered::Text(format!("{value:.decimals$}")),
+        (Filter::Pad(width), value) => Rendered::Text(format!("{:<width$}", text(value))),
+        (Filter::Rpad(width), value) => Rendered::Text(format!("{:>width$}", text(value))),
+        (Filter::Upper, value) => Rendered::Text(text(value).to_uppercase()),
+        // number filters on text (`?`, names, already formatted) leave it alone
+        (_, value) => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use sysinfo_dot_h::meminfo::MemInfo;
+    use sysinfo_dot_h::SystemInfo;
+
+    fn sample() -> Sample {
+        Sample {
+            info: SystemInfo { uptime: Duration::from_secs(4 * 3600 + 120), loads: [0.1875, 0.5, 0.25], total_ram: 8 << 30, procs: 42, ..Default::default() },
+            meminfo: Some(MemInfo { available: Some(2 << 30), ..Default::default() }),
+        }
+    }
+
+    #[test]
+    fn expressions_and_filters() {
+        let template = Template::parse("up {{ uptime | human_duration }}, {{available|human}} free ({{ available_percent | round(0) }}%), \
+                                        load {{ load1 | round(2) }}, {{ procs | rpad(4) }} procs, {{ load5 | pad(4) }}|")
+            .unwrap();
+        assert_eq!(template.render(&sample()), "up 4h 2m, 2.0 GiB free (25%), load 0.19,   42 procs, 0.5 |");
+        let unknown = Sample { meminfo: None, ..sample() };
+        assert_eq!(Template::parse("{{ available | human_bytes | upper }}").unwrap().render(&unknown), "?");
+    }
+
+    #[test]
+    fn loops() {
+        let template = Template::parse("memory:\n  {% for f in fields %}\n  {{ f.name | pad(10) }} {{ f.value | human }}\n{% endfor %}\nbye\n").unwrap();
+        let rendered = template.render(&Sample { meminfo: None, ..sample() });
+        assert!(rendered.starts_with("memory:\n  uptime     4h 2m\n  load1      0.19\n"));
+        assert!(rendered.ends_with("  procs      42\nbye\n"));
+        // no meminfo, so no available
+        assert!(!rendered.contains("available"));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Template::parse("a\n{{ nope }}"), Err("line 2: unknown field \"nope\"".to_string()));
+        assert!(Template::parse("{{ load1 | shout }}").is_err());
+        assert!(Template::parse("{{ load1 | round(x) }}").is_err());
+        assert!(Template::parse("{{ load1").is_err());
+        assert!(Template::parse("{% for f in fields %}").is_err());
+        assert!(Template::parse("{% endfor %}").is_err());
+        assert!(Template::parse("{% if load1 %}").is_err());
+        assert!(Template::parse("{{ f.name }}").is_err());
+    }
+}
@@ -0,0 +1,174 @@
+//! `--top`: the derived metrics as a table refreshed in place, sorted so whatever got worse the
+//! most since the previous refresh is on top. For incidents, where the question is "what's
+//! going wrong right now" rather than "what are the numbers".
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use sysinfo_dot_h::vmstat::VmStat;
+
+use crate::fields::Sample;
+use crate::human;
+use crate::source::Source;
+
+/// Clears the screen and moves the cursor home.
+const CLEAR: &str = "\x1b[H\x1b[2J";
+
+/// What's needed for one refresh, rates are worked out between two of them.
+#[derive(Debug, Copy, Clone)]
+pub struct Snapshot {
+    pub sample: Sample,
+    pub vmstat: Option<VmStat>,
+    pub taken: Instant,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Unit {
+    Bytes,
+    Percent,
+    PerSec,
+    Load,
+    Count,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Metric {
+    name: &'static str,
+    value: f64,
+    unit: Unit,
+    /// Whether going up is bad (used memory) or good (available memory)
+    higher_is_worse: bool,
+}
+
+/// Runs until the process is killed.
+pub fn run(source: &Source, interval: Duration) -> ! {
+    let mut previous: Option<(Snapshot, Vec<Metric>)> = None;
+    let source = source.clone();
+    let _sampler = source
+        .sampler()
+        .interval(interval)
+        .on_sample(move |info| {
+            let snapshot = Snapshot { sample: source.complete(*info), vmstat: source.vmstat().ok(), taken: Instant::now() };
+            let metrics = metrics(previous.as_ref().map(|(snapshot, _)| snapshot), &snapshot);
+            let before = previous.as_ref().map_or(&[][..], |(_, metrics)| metrics);
+            print!("{CLEAR}{}", screen(&metrics, before, interval, source.scope()));
+            previous = Some((snapshot, metrics));
+        })
+        .start();
+
+    loop {
+        std::thread::park();
+    }
+}
+
+/// Everything shown, the rates only once there's a `before` to compare with. Metrics this
+/// machine doesn't have (no `/proc/meminfo` in replays, ...) are left out.
+fn metrics(before: Option<&Snapshot>, after: &Snapshot) -> Vec<Metric> {
+    let metric = |name, value: Option<f64>, unit, higher_is_worse| value.map(|value| Metric { name, value, unit, higher_is_worse });
+    let sample = &after.sample;
+    let mut metrics = vec![
+        metric("used", sample.get("available_percent").map(|available| 100.0 - available), Unit::Percent, true),
+        metric("available", sample.get("available"), Unit::Bytes, false),
+        metric("free ram", sample.get("free_ram"), Unit::Bytes, false),
+        metric("used swap", sample.get("used_swap"), Unit::Bytes, true),
+        metric("load1", sample.get("load1"), Unit::Load, true),
+        metric("procs", sample.get("procs"), Unit::Count, true),
+    ];
+    if let Some(before) = before {
+        let secs = after.taken.duration_since(before.taken).as_secs_f64();
+        let rate = |counter: fn(&VmStat) -> u64| {
+            let (Some(before), Some(after)) = (before.vmstat, after.vmstat) else { return None };
+            (secs > 0.0).then(|| counter(&after).saturating_sub(counter(&before)) as f64 / secs)
+        };
+        metrics.push(metric("swap in", rate(|vmstat| vmstat.pswpin), Unit::PerSec, true));
+        metrics.push(metric("swap out", rate(|vmstat| vmstat.pswpout), Unit::PerSec, true));
+        metrics.push(metric("majfault", rate(|vmstat| vmstat.pgmajfault), Unit::PerSec, true));
+    }
+    metrics.into_iter().flatten().collect()
+}
+
+/// How much worse `now` is than `before`, relative to `before` so that bytes and rates compare.
+fn worsening(now: &Metric, before: &Metric) -> f64 {
+    let change = if now.higher_is_worse { now.value - before.value } else { before.value - now.value };
+    change / before.value.abs().max(1.0)
+}
+
+fn screen(metrics: &[Metric], before: &[Metric], interval: Duration, scope: Option<String>) -> String {
+    let previous = |metric: &Metric| before.iter().find(|before| before.name == metric.name);
+    let mut rows: Vec<(&Metric, Option<&Metric>)> = metrics.iter().map(|metric| (metric, previous(metric))).collect();
+    rows.sort_by(|(a, a_before), (b, b_before)| {
+        let score = |metric, before: &Option<&Metric>| before.map_or(0.0, |before| worsening(metric, before));
+        score(b, b_before).total_cmp(&score(a, a_before))
+    });
+
+    let mut out = format!("every {}, what got worse first", human::duration(interval));
+    if let Some(scope) = scope {
+        let _ = write!(out, ", scope: {scope}");
+    }
+    let _ = writeln!(out, "\n  {:<12}{:>14}{:>14}", "metric", "value", "change");
+    for (metric, before) in rows {
+        let change = before.map_or_else(String::new, |before| change(before, metric));
+        let line = format!("  {:<12}{:>14}{change:>14}", metric.name, value(metric));
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+    out
+}
+
+fn value(metric: &Metric) -> String {
+    match metric.unit {
+        Unit::Bytes => human::bytes(metric.value as u64),
+        Unit::Percent => format!("{:.1}%", metric.value),
+        Unit::PerSec => format!("{:.1}/s", metric.value),
+        Unit::Load => format!("{:.2}", metric.value),
+        Unit::Count => format!("{}", metric.value as u64),
+    }
+}
+
+fn change(before: &Metric, now: &Metric) -> String {
+    let change = now.value - before.value;
+    match now.unit {
+        Unit::Bytes => human::delta_bytes(before.value as u64, now.value as u64),
+        Unit::Percent => format!("{change:+.1}%"),
+        Unit::PerSec => format!("{change:+.1}/s"),
+        Unit::Load => format!("{change:+.2}"),
+        Unit::Count => format!("{:+}", change as i64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sysinfo_dot_h::meminfo::MemInfo;
+    use sysinfo_dot_h::SystemInfo;
+
+    fn snapshot(available: u64, majfaults: u64, taken: Instant) -> Snapshot {
+        let info = SystemInfo { total_ram: 8 << 30, free_ram: 1 << 30, loads: [1.0, 0.0, 0.0], procs: 100, ..Default::default() };
+        let meminfo = MemInfo { total: 8 << 30, available: Some(available), ..Default::default() };
+        Snapshot { sample: Sample { info, meminfo: Some(meminfo) }, vmstat: Some(VmStat { pgmajfault: majfaults, ..Default::default() }), taken }
+    }
+
+    #[test]
+    fn worst_first() {
+        let start = Instant::now();
+        let first = snapshot(4 << 30, 1000, start);
+        let second = snapshot(4 << 30, 1000, start + Duration::from_secs(2));
+        let third = snapshot(3 << 30, 1100, start + Duration::from_secs(4));
+
+        let before = metrics(Some(&first), &second);
+        assert_eq!(before.iter().map(|metric| metric.name).collect::<Vec<_>>(), [
+            "used", "available", "free ram", "used swap", "load1", "procs", "swap in", "swap out", "majfault",
+        ]);
+        let after = metrics(Some(&second), &third);
+        let screen = screen(&after, &before, Duration::from_secs(2), None);
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[0], "every 0m 2s, what got worse first");
+        // majfaults went from 0/s to 50/s, used from 50% to 62.5%, available down by a quarter
+        assert_eq!(lines[2], "  majfault            50.0/s       +50.0/s");
+        assert_eq!(lines[3], "  used                 62.5%        +12.5%");
+        assert_eq!(lines[4], "  available          3.0 GiB      -1.0 GiB");
+
+        // nothing to compare with yet: no rates, no changes
+        let first_screen = super::screen(&metrics(None, &first), &[], Duration::from_secs(2), None);
+        assert!(first_screen.contains("\n  used                 50.0%\n"));
+        assert!(!first_screen.contains("majfault"));
+    }
+}
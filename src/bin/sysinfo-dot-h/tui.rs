@@ -0,0 +1,143 @@
+//! `--tui`: gauges and sparklines over the sampler's history, a tiny htop for memory.
+use std::io;
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::widgets::{Block, Gauge, Paragraph, Sparkline};
+use ratatui::Frame;
+use sysinfo_dot_h::meminfo::MemInfo;
+use sysinfo_dot_h::psi::{Pressure, Resource};
+use sysinfo_dot_h::SystemInfo;
+
+use crate::human;
+use crate::source::Source;
+
+/// Samples kept for the sparklines, more than any terminal is wide.
+const HISTORY: usize = 512;
+
+/// Draws until `q`, Esc or Ctrl-C is pressed. `pressure` adds a `/proc/pressure` panel.
+pub fn run(source: &Source, interval: Duration, pressure: bool) -> io::Result<()> {
+    let sampler = source.sampler().interval(interval).history(HISTORY).start();
+    // restores the terminal on panics too
+    ratatui::run(|terminal| loop {
+        let history = sampler.history();
+        let meminfo = source.meminfo().ok();
+        let scope = source.scope();
+        let pressure = pressure.then(|| source.pressure());
+        terminal.draw(|frame| draw(frame, &history, meminfo.as_ref(), scope.as_deref(), pressure.as_deref(), interval))?;
+
+        if event::poll(interval.min(Duration::from_millis(250)))? {
+            if let Event::Key(key) = event::read()? {
+                let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if key.kind == KeyEventKind::Press && (matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) || ctrl_c) {
+                    return Ok(());
+                }
+            }
+        }
+    })
+}
+
+fn draw(
+    frame: &mut Frame<'_>,
+    history: &[SystemInfo],
+    meminfo: Option<&MemInfo>,
+    scope: Option<&str>,
+    pressure: Option<&[(Resource, Result<Pressure, String>)]>,
+    interval: Duration,
+) {
+    let [ram, swap, free_ram, used_swap, load, pressure_area, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(3),
+        Constraint::Fill(1),
+        Constraint::Fill(1),
+        Constraint::Fill(1),
+        // a line per resource and the borders
+        Constraint::Length(if pressure.is_some() { 5 } else { 0 }),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let Some(latest) = history.last() else {
+        frame.render_widget(Paragraph::new("waiting for the first sample..."), frame.area());
+        return;
+    };
+
+    // used the way `free` counts it when meminfo is there, otherwise everything that isn't free
+    let available = meminfo.and_then(|meminfo| meminfo.available).unwrap_or(latest.free_ram + latest.buffer_ram);
+    let used_ram = latest.total_ram.saturating_sub(available);
+    frame.render_widget(usage("ram", used_ram, latest.total_ram), ram);
+    frame.render_widget(usage("swap", latest.total_swap - latest.free_swap.min(latest.total_swap), latest.total_swap), swap);
+
+    let title = format!("free ram ({})", human::bytes(latest.free_ram));
+    frame.render_widget(sparkline(&title, history, free_ram, latest.total_ram, |info| info.free_ram), free_ram);
+    let title = format!("used swap ({})", human::bytes(latest.total_swap.saturating_sub(latest.free_swap)));
+    frame.render_widget(
+        sparkline(&title, history, used_swap, latest.total_swap, |info| info.total_swap.saturating_sub(info.free_swap)),
+        used_swap,
+    );
+    // hundredths of a load, the highest one shown sets the scale
+    let [load1, load5, load15] = latest.loads;
+    let title = format!("load ({load1:.2} {load5:.2} {load15:.2})");
+    let line = sparkline(&title, history, load, 0, |info| (info.loads[0] * 100.0) as u64);
+    frame.render_widget(line, load);
+    if let Some(pressure) = pressure {
+        let lines: Vec<String> = pressure
+            .iter()
+            .map(|(resource, pressure)| match pressure {
+                Ok(Pressure { some, .. }) => format!("{resource:<8} {:>6.2}% {:>6.2}% {:>6.2}%", some.avg10, some.avg60, some.avg300),
+                Err(e) => format!("{resource:<8} unavailable ({e})"),
+            })
+            .collect();
+        let block = Block::bordered().title("pressure (avg10 avg60 avg300)");
+        frame.render_widget(Paragraph::new(lines.join("\n")).block(block), pressure_area);
+    }
+
+    let mut footer_text = format!("up {}, {} procs, every {}", human::duration(latest.uptime), latest.procs, human::duration(interval));
+    if let Some(scope) = scope {
+        footer_text += &format!(", {scope}");
+    }
+    footer_text += " - q to quit";
+    frame.render_widget(Paragraph::new(footer_text), footer);
+}
+
+fn usage(title: &str, used: u64, total: u64) -> Gauge<'static> {
+    let ratio = if total == 0 { 0.0 } else { used as f64 / total as f64 };
+    Gauge::default()
+        .block(Block::bordered().title(title.to_string()))
+        .ratio(ratio.clamp(0.0, 1.0))
+        .label(format!("{} of {} used", human::bytes(used), human::bytes(total)))
+}
+
+/// The newest samples that fit into `area`, scaled to `max` (or to the largest value if 0).
+fn sparkline(title: &str, history: &[SystemInfo], area: Rect, max: u64, value: fn(&SystemInfo) -> u64) -> Sparkline<'static> {
+    let width = usize::from(area.width.saturating_sub(2));
+    let data: Vec<u64> = history[history.len().saturating_sub(width)..].iter().map(value).collect();
+    let sparkline = Sparkline::default().block(Block::bordered().title(title.to_string())).data(data);
+    if max == 0 { sparkline } else { sparkline.max(max) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    #[test]
+    fn draws() {
+        let history: Vec<SystemInfo> = (0..100u64)
+            .map(|i| SystemInfo { total_ram: 8 << 30, free_ram: i << 24, total_swap: 1 << 30, free_swap: 1 << 29, procs: 42, ..Default::default() })
+            .collect();
+        let pressure = [(Resource::Memory, Ok(Pressure::default()))];
+        let mut terminal = Terminal::new(TestBackend::new(60, 24)).unwrap();
+        terminal.draw(|frame| draw(frame, &history, None, Some("cgroup v2 /app"), Some(&pressure), Duration::from_secs(1))).unwrap();
+
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("ram"));
+        assert!(screen.contains("512.0 MiB of 1.0 GiB used"));
+        assert!(screen.contains("free ram (1.5 GiB)"));
+        assert!(screen.contains("42 procs"));
+        assert!(screen.contains("cgroup v2 /app"));
+        assert!(screen.contains("memory     0.00%   0.00%   0.00%"));
+    }
+}
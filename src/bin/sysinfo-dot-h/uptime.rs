@@ -0,0 +1,115 @@
+//! `--uptime`: the classic `uptime` line, for images that don't ship procps.
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_long};
+
+use sysinfo_dot_h::SystemInfo;
+
+/// `uptime`'s output: ` 03:56:34 up 34 min,  1 user,  load average: 0.38, 0.58, 0.49`.
+pub struct Uptime<'a> {
+    pub info: &'a SystemInfo,
+    /// The local time as hours, minutes and seconds
+    pub now: (u8, u8, u8),
+    /// Logged in users, left out if unknown
+    pub users: Option<usize>,
+}
+
+impl fmt::Display for Uptime<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (hour, minute, second) = self.now;
+        write!(f, " {hour:02}:{minute:02}:{second:02} up ")?;
+
+        let secs = self.info.uptime.as_secs();
+        let (days, hours, minutes) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+        if days > 0 {
+            write!(f, "{days} day{}, ", if days == 1 { "" } else { "s" })?;
+        }
+        if hours > 0 {
+            write!(f, "{hours:2}:{minutes:02}, ")?;
+        } else {
+            write!(f, "{minutes} min, ")?;
+        }
+        if let Some(users) = self.users {
+            write!(f, "{users:2} {}, ", if users == 1 { "user" } else { "users" })?;
+        }
+        let [one, five, fifteen] = self.info.loads;
+        write!(f, " load average: {one:.2}, {five:.2}, {fifteen:.2}")
+    }
+}
+
+/// The line for `info`, right now.
+pub fn line(info: &SystemInfo) -> String {
+    Uptime { info, now: now(), users: users() }.to_string()
+}
+
+#[repr(C)]
+struct Tm {
+    tm_sec: c_int,
+    tm_min: c_int,
+    tm_hour: c_int,
+    tm_mday: c_int,
+    tm_mon: c_int,
+    tm_year: c_int,
+    tm_wday: c_int,
+    tm_yday: c_int,
+    tm_isdst: c_int,
+    tm_gmtoff: c_long,
+    tm_zone: *const c_char,
+}
+
+extern "C" {
+    fn time(tloc: *mut c_long) -> c_long;
+    fn localtime_r(timep: *const c_long, result: *mut Tm) -> *mut Tm;
+}
+
+/// The local time of day, from libc so `TZ` and `/etc/localtime` are honored.
+pub fn now() -> (u8, u8, u8) {
+    let mut tm = std::mem::MaybeUninit::<Tm>::uninit();
+    // SAFETY: time accepts null, and localtime_r only writes to tm, which is checked for failure
+    let tm = unsafe {
+        let now = time(std::ptr::null_mut());
+        if localtime_r(&now, tm.as_mut_ptr()).is_null() {
+            return (0, 0, 0);
+        }
+        tm.assume_init()
+    };
+    (tm.tm_hour as u8, tm.tm_min as u8, tm.tm_sec as u8)
+}
+
+/// Counts `USER_PROCESS` entries in utmp, or `None` if there's no utmp (common in containers).
+pub fn users() -> Option<usize> {
+    // glibc's struct utmp is 384 bytes on every architecture, starting with a short ut_type
+    const RECORD: usize = 384;
+    const USER_PROCESS: i16 = 7;
+
+    let utmp = std::fs::read("/run/utmp").or_else(|_| std::fs::read("/var/run/utmp")).ok()?;
+    Some(
+        utmp.chunks_exact(RECORD)
+            .filter(|record| i16::from_ne_bytes([record[0], record[1]]) == USER_PROCESS)
+            .count(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn line(uptime: u64, users: Option<usize>) -> String {
+        let info = SystemInfo { uptime: Duration::from_secs(uptime), loads: [0.38, 0.58, 0.49], ..Default::default() };
+        Uptime { info: &info, now: (3, 56, 34), users }.to_string()
+    }
+
+    #[test]
+    fn like_procps() {
+        assert_eq!(line(34 * 60, Some(0)), " 03:56:34 up 34 min,  0 users,  load average: 0.38, 0.58, 0.49");
+        assert_eq!(line(86400 + 3 * 3600 + 5 * 60, Some(2)), " 03:56:34 up 1 day,  3:05,  2 users,  load average: 0.38, 0.58, 0.49");
+        assert_eq!(line(60, Some(1)), " 03:56:34 up 1 min,  1 user,  load average: 0.38, 0.58, 0.49");
+        assert_eq!(line(3 * 86400 + 60, None), " 03:56:34 up 3 days, 1 min,  load average: 0.38, 0.58, 0.49");
+    }
+
+    #[test]
+    fn local_time() {
+        let (hour, minute, second) = now();
+        assert!(hour < 24 && minute < 60 && second < 61);
+    }
+}
@@ -0,0 +1,113 @@
+//! `--watch`: a sampler driven display refreshing in place, like `watch free`.
+use std::fmt::Write;
+use std::time::Duration;
+
+use sysinfo_dot_h::psi::{Pressure, Resource};
+use sysinfo_dot_h::SystemInfo;
+
+use crate::args::Output;
+use crate::source::Source;
+use crate::{free, human, json, uptime};
+
+/// Clears the screen and moves the cursor home.
+const CLEAR: &str = "\x1b[H\x1b[2J";
+
+/// Runs until the process is killed. `pressure` adds `/proc/pressure` to the human readable
+/// screen.
+pub fn run(source: &Source, interval: Duration, output: Output, pressure: bool) -> ! {
+    let mut previous: Option<SystemInfo> = None;
+    let source = source.clone();
+    let _sampler = source
+        .sampler()
+        .interval(interval)
+        .on_sample(move |info| {
+            match &output {
+                Output::Human => {
+                    let pressure = pressure.then(|| source.pressure());
+                    print!("{CLEAR}{}", screen(info, previous.as_ref(), interval, source.scope(), pressure.as_deref()));
+                }
+                // one snapshot per line, so the output can be piped into `jq` or a file
+                Output::Json => println!("{}", info.to_json()),
+                Output::PrettyJson => println!("{}", json::pretty(&info.to_json())),
+                Output::Free => match source.meminfo() {
+                    Ok(meminfo) => match source.scope() {
+                        Some(scope) => print!("{CLEAR}scope: {scope}\n{}", free::Free(&meminfo)),
+                        None => print!("{CLEAR}{}", free::Free(&meminfo)),
+                    },
+                    Err(e) => eprintln!("sysinfo-dot-h: {e}"),
+                },
+                Output::Uptime => println!("{CLEAR}{}", uptime::line(info)),
+                Output::Line => println!("{}", info.summary(human::units())),
+                // a stream of lines, which is what status bars read
+                Output::Format(format) => println!("{}", format.render(&source.complete(*info))),
+                Output::Template(template) => print!("{CLEAR}{}", template.render(&source.complete(*info))),
+            }
+            previous = Some(*info);
+        })
+        .start();
+
+    loop {
+        std::thread::park();
+    }
+}
+
+fn screen(
+    info: &SystemInfo,
+    previous: Option<&SystemInfo>,
+    interval: Duration,
+    scope: Option<String>,
+    pressure: Option<&[(Resource, Result<Pressure, String>)]>,
+) -> String {
+    let mut out = format!("every {}", human::duration(interval));
+    if let Some(scope) = scope {
+        let _ = write!(out, ", scope: {scope}");
+    }
+    out.push('\n');
+    let mut derived = human::Derived(info).to_string();
+    if let Some(pressure) = pressure {
+        // right below the load averages, which it explains
+        let after_load = derived.find("\n  ram ").map_or(derived.len(), |at| at + 1);
+        derived.insert_str(after_load, &human::Pressure(pressure).to_string());
+    }
+    out.push_str(&derived);
+    if let Some(previous) = previous {
+        let _ = write!(out, "{}", changes(previous, info));
+    }
+    out
+}
+
+/// What changed between two refreshes.
+fn changes(before: &SystemInfo, after: &SystemInfo) -> String {
+    let mut out = String::from("since last refresh:\n");
+    let _ = writeln!(out, "  free ram   {}", human::delta_bytes(before.free_ram, after.free_ram));
+    let _ = writeln!(out, "  free swap  {}", human::delta_bytes(before.free_swap, after.free_swap));
+    let _ = writeln!(out, "  buffers    {}", human::delta_bytes(before.buffer_ram, after.buffer_ram));
+    let _ = writeln!(out, "  load1      {:+.2}", after.loads[0] - before.loads[0]);
+    let _ = writeln!(out, "  procs      {:+}", i32::from(after.procs) - i32::from(before.procs));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_changes() {
+        let before = SystemInfo { free_ram: 4096, procs: 10, loads: [1.0, 0.0, 0.0], ..Default::default() };
+        let after = SystemInfo { free_ram: 2048, procs: 12, loads: [0.5, 0.0, 0.0], ..Default::default() };
+
+        let screen = screen(&after, Some(&before), Duration::from_secs(2), None, None);
+        assert!(screen.starts_with("every 0m 2s\n  uptime "));
+        assert!(screen.contains("\n  free ram   -2.0 KiB\n"));
+        assert!(screen.contains("\n  load1      -0.50\n"));
+        assert!(screen.contains("\n  procs      +2\n"));
+        assert!(!super::screen(&after, None, Duration::from_secs(2), None, None).contains("since last refresh"));
+        let scoped = super::screen(&after, None, Duration::from_secs(2), Some("cgroup v2 /app.slice, limit 1.0 GiB".to_string()), None);
+        assert!(scoped.starts_with("every 0m 2s, scope: cgroup v2 /app.slice, limit 1.0 GiB\n"));
+
+        let pressure = [(Resource::Memory, Ok(Pressure::default()))];
+        let screen = super::screen(&after, None, Duration::from_secs(2), None, Some(&pressure));
+        assert!(screen.contains("\n  load       0.50 0.00 0.00\n  pressure     avg10   avg60  avg300\n    memory     0.00%"));
+        assert!(screen.contains("0.00%\n  ram "));
+    }
+}
@@ -0,0 +1,237 @@
+//! Reusing a recent snapshot instead of asking the kernel again, for callers that want one per
+//! request or per frame but don't need it fresher than some TTL.
+//!
+//! [`LocalCollector`] is the single threaded one: no locks or atomics at all, so it can't be
+//! shared between threads, but one per thread is cheap. That's what thread-per-core runtimes
+//! want on their hot paths:
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use sysinfo_dot_h::cache::LocalCollector;
+//!
+//! thread_local! {
+//!     static SNAPSHOTS: LocalCollector = LocalCollector::new(Duration::from_millis(100));
+//! }
+//!
+//! // at most one sysinfo(2) per thread every 100ms
+//! let free = SNAPSHOTS.with(|snapshots| snapshots.collect().map(|info| info.free_ram));
+//! ```
+//!
+//! [`CachedCollector`] is the shared one, behind an `RwLock`: readers of a fresh snapshot only
+//! take the read lock, and when it's stale one caller collects while the others wait for its
+//! result instead of all asking the kernel. For the common case there's a process-wide one,
+//! [`collect_cached`]:
+//!
+//! ```rust
+//! // per HTTP request, at most one sysinfo(2) a second for the whole process
+//! let procs = sysinfo_dot_h::cache::collect_cached().unwrap().procs;
+//! ```
+use std::cell::Cell;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use crate::backend::Backend;
+use crate::clock::{self, Clock};
+use crate::provider::SysinfoProvider;
+use crate::sampler::Sample;
+use crate::{SysinfoError, SystemInfo};
+
+/// A snapshot cache for one thread, see the [module docs](self).
+#[derive(Debug)]
+pub struct LocalCollector<P = Backend> {
+    provider: P,
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+    last: Cell<Option<Sample>>,
+}
+
+impl LocalCollector {
+    /// Collects with [`Backend::Libc`], at most once per `ttl`.
+    #[must_use] pub fn new(ttl: Duration) -> Self {
+        Self::with_provider(Backend::Libc, ttl)
+    }
+}
+
+impl<P: SysinfoProvider> LocalCollector<P> {
+    /// Collects from `provider`, at most once per `ttl`.
+    #[must_use] pub fn with_provider(provider: P, ttl: Duration) -> Self {
+        Self { provider, ttl, clock: clock::system(), last: Cell::new(None) }
+    }
+
+    /// Where the age of the cached snapshot is measured, the
+    /// [`SystemClock`](clock::SystemClock) by default.
+    #[must_use] pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// The cached snapshot if it's younger than the TTL, a fresh one otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns the provider's error if a fresh snapshot was needed and it failed. Nothing is
+    /// cached then, the next call tries again.
+    pub fn collect(&self) -> Result<SystemInfo, SysinfoError> {
+        let now = self.clock.now();
+        if let Some(sample) = self.last.get().filter(|sample| sample.age_at(now) < self.ttl) {
+            return Ok(sample.info);
+        }
+        let info = self.provider.collect()?;
+        self.last.set(Some(Sample { info, collected_at: self.clock.now() }));
+        Ok(info)
+    }
+
+    /// The cached snapshot (fresh or not) and when it was collected.
+    #[must_use] pub fn cached(&self) -> Option<Sample> {
+        self.last.get()
+    }
+
+    /// Forgets the cached snapshot, so the next [`collect`](Self::collect) asks the provider.
+    pub fn invalidate(&self) {
+        self.last.set(None);
+    }
+}
+
+impl<P: SysinfoProvider> SysinfoProvider for LocalCollector<P> {
+    fn collect(&self) -> Result<SystemInfo, SysinfoError> {
+        LocalCollector::collect(self)
+    }
+}
+
+/// A snapshot cache shared between threads, see the [module docs](self).
+#[derive(Debug)]
+pub struct CachedCollector<P = Backend> {
+    provider: P,
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+    last: RwLock<Option<Sample>>,
+}
+
+impl CachedCollector {
+    /// Collects with [`Backend::Libc`], at most once per `ttl`.
+    #[must_use] pub fn new(ttl: Duration) -> Self {
+        Self::with_provider(Backend::Libc, ttl)
+    }
+}
+
+impl<P: SysinfoProvider> CachedCollector<P> {
+    /// Collects from `provider`, at most once per `ttl`.
+    #[must_use] pub fn with_provider(provider: P, ttl: Duration) -> Self {
+        Self { provider, ttl, clock: clock::system(), last: RwLock::new(None) }
+    }
+
+    /// Where the age of the cached snapshot is measured, the
+    /// [`SystemClock`](clock::SystemClock) by default.
+    #[must_use] pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// The cached snapshot if it's younger than the TTL, a fresh one otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns the provider's error if a fresh snapshot was needed and it failed. Nothing is
+    /// cached then, the next call tries again.
+    pub fn collect(&self) -> Result<SystemInfo, SysinfoError> {
+        if let Some(info) = self.fresh(&self.last.read().unwrap_or_else(|e| e.into_inner())) {
+            return Ok(info);
+        }
+        let mut last = self.last.write().unwrap_or_else(|e| e.into_inner());
+        // someone else may have collected while we waited for the lock
+        if let Some(info) = self.fresh(&last) {
+            return Ok(info);
+        }
+        let info = self.provider.collect()?;
+        *last = Some(Sample { info, collected_at: self.clock.now() });
+        Ok(info)
+    }
+
+    fn fresh(&self, last: &Option<Sample>) -> Option<SystemInfo> {
+        let now = self.clock.now();
+        last.filter(|sample| sample.age_at(now) < self.ttl).map(|sample| sample.info)
+    }
+
+    /// The cached snapshot (fresh or not) and when it was collected.
+    #[must_use] pub fn cached(&self) -> Option<Sample> {
+        *self.last.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Forgets the cached snapshot, so the next [`collect`](Self::collect) asks the provider.
+    pub fn invalidate(&self) {
+        *self.last.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+impl<P: SysinfoProvider> SysinfoProvider for CachedCollector<P> {
+    fn collect(&self) -> Result<SystemInfo, SysinfoError> {
+        CachedCollector::collect(self)
+    }
+}
+
+/// How old [`collect_cached`]'s snapshot gets.
+pub const GLOBAL_TTL: Duration = Duration::from_secs(1);
+
+/// A snapshot from the process-wide [`CachedCollector`], at most [`GLOBAL_TTL`] old.
+///
+/// # Errors
+///
+/// Returns an error if a fresh snapshot was needed and `sysinfo(2)` failed.
+pub fn collect_cached() -> Result<SystemInfo, SysinfoError> {
+    static GLOBAL: OnceLock<CachedCollector> = OnceLock::new();
+    GLOBAL.get_or_init(|| CachedCollector::new(GLOBAL_TTL)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MockClock, MockProvider};
+
+    #[test]
+    fn caches_for_the_ttl() {
+        let clock = MockClock::new();
+        let procs = |procs| SystemInfo { procs, ..Default::default() };
+        let provider = MockProvider::results([Ok(procs(1)), Err("down".into()), Ok(procs(2))]);
+        let cache = LocalCollector::with_provider(&provider, Duration::from_secs(1)).clock(clock.clone());
+
+        assert_eq!(cache.collect().unwrap().procs, 1);
+        clock.advance(Duration::from_millis(999));
+        assert_eq!(cache.collect().unwrap().procs, 1);
+        assert_eq!(provider.calls(), 1);
+
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(cache.collect().unwrap_err().to_string(), "down");
+        assert_eq!(cache.cached().map(|sample| sample.info.procs), Some(1));
+        assert_eq!(cache.collect().unwrap().procs, 2);
+
+        cache.invalidate();
+        assert!(cache.cached().is_none());
+        assert_eq!(provider.calls(), 3);
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let clock = MockClock::new();
+        let calls = std::sync::atomic::AtomicU32::new(0);
+        let provider = crate::provider::from_fn(|| {
+            calls.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Ok(SystemInfo { procs: 7, ..Default::default() })
+        });
+        let cache = CachedCollector::with_provider(provider, Duration::from_secs(1)).clock(clock.clone());
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| assert_eq!(cache.collect().unwrap().procs, 7));
+            }
+        });
+        assert_eq!(calls.load(std::sync::atomic::Ordering::Relaxed), 1);
+
+        clock.advance(Duration::from_secs(1));
+        cache.collect().unwrap();
+        cache.invalidate();
+        assert!(cache.cached().is_none());
+        cache.collect().unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::Relaxed), 3);
+
+        assert!(collect_cached().unwrap().total_ram > 0);
+    }
+}
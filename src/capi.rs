@@ -0,0 +1,170 @@
+//! A C ABI for using this crate from C/C++, described by `include/sysinfo_dot_h.h`.
+//!
+//! Build it as a shared library with
+//!
+//! ```sh
+//! cargo rustc --release --features capi --crate-type cdylib
+//! ```
+//!
+//! and regenerate the header with
+//! `cbindgen --config cbindgen.toml --output include/sysinfo_dot_h.h src/capi.rs` whenever this
+//! module changes. The layout of [`Snapshot`] only ever changes together with
+//! [`SYSINFO_DOT_H_ABI_VERSION`].
+use std::os::raw::c_int;
+
+use crate::{sysinfo, SystemInfo};
+
+/// Bumped whenever the snapshot struct or a function signature changes incompatibly.
+pub const SYSINFO_DOT_H_ABI_VERSION: u32 = 1;
+
+/// The normalized snapshot: memory in bytes (already multiplied by `mem_unit`), load averages as
+/// doubles and the uptime in seconds.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Snapshot {
+    /// Seconds since boot
+    pub uptime: u64,
+    /// 1, 5, and 15 minute load averages
+    pub loads: [f64; 3],
+    /// Total usable main memory size in bytes
+    pub total_ram: u64,
+    /// Free memory in bytes. (note: freeram != available memory)
+    pub free_ram: u64,
+    /// Amount of shared memory in bytes
+    pub shared_ram: u64,
+    /// Memory used by buffers in bytes
+    pub buffer_ram: u64,
+    /// Total swap space size in bytes
+    pub total_swap: u64,
+    /// Swap space still available in bytes
+    pub free_swap: u64,
+    /// Total high memory size in bytes
+    pub total_high: u64,
+    /// Available high memory size in bytes
+    pub free_high: u64,
+    /// Number of current processes
+    pub procs: u16,
+}
+
+impl From<SystemInfo> for Snapshot {
+    fn from(info: SystemInfo) -> Self {
+        Self {
+            uptime: info.uptime.as_secs(),
+            loads: info.loads,
+            total_ram: info.total_ram,
+            free_ram: info.free_ram,
+            shared_ram: info.shared_ram,
+            buffer_ram: info.buffer_ram,
+            total_swap: info.total_swap,
+            free_swap: info.free_swap,
+            total_high: info.total_high,
+            free_high: info.free_high,
+            procs: info.procs,
+        }
+    }
+}
+
+/// Returns the `SYSINFO_DOT_H_ABI_VERSION` of the loaded library, so callers can check it against
+/// the header they were compiled with.
+#[no_mangle]
+pub extern "C" fn sysinfo_dot_h_abi_version() -> u32 {
+    SYSINFO_DOT_H_ABI_VERSION
+}
+
+/// Fills `out` with a fresh snapshot. Returns 0 on success and -1 on failure, in which case `out`
+/// is untouched and `errno` is set: by `sysinfo(2)` when it fails, `EINVAL` when `out` is null or
+/// the crate couldn't use what the kernel returned (e.g. a malformed `SYSINFO_DOT_H_FAKE`).
+///
+/// # Safety
+///
+/// `out` must be null (which fails) or point to memory valid for writing a snapshot.
+#[no_mangle]
+pub unsafe extern "C" fn sysinfo_dot_h_collect(out: *mut Snapshot) -> c_int {
+    if out.is_null() {
+        *errno() = EINVAL;
+        return -1;
+    }
+    let mut raw: sysinfo = std::mem::zeroed();
+    if crate::sysinfo(&mut raw) != 0 {
+        // sysinfo(2) already set errno
+        return -1;
+    }
+    #[cfg(feature = "fake")]
+    if crate::fake::apply(&mut raw).is_err() {
+        *errno() = EINVAL;
+        return -1;
+    }
+    out.write(Snapshot::from(SystemInfo::from(raw)));
+    0
+}
+
+/// 22 with every libc below.
+const EINVAL: c_int = 22;
+
+// what `errno` expands to, which every libc names differently
+extern "C" {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[link_name = "__errno_location"]
+    fn errno() -> *mut c_int;
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "dragonfly"))]
+    #[link_name = "__error"]
+    fn errno() -> *mut c_int;
+    #[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+    #[link_name = "__errno"]
+    fn errno() -> *mut c_int;
+    #[cfg(windows)]
+    #[link_name = "_errno"]
+    fn errno() -> *mut c_int;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_through_the_c_abi() {
+        let mut snapshot = Snapshot::default();
+        assert_eq!(unsafe { sysinfo_dot_h_collect(&mut snapshot) }, 0);
+        assert!(snapshot.total_ram > 0);
+        assert_eq!(unsafe { sysinfo_dot_h_collect(std::ptr::null_mut()) }, -1);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(EINVAL));
+    }
+
+    #[test]
+    fn layout_matches_the_header() {
+        use std::mem::{align_of, offset_of, size_of};
+        // sysinfo_dot_h_snapshot as any C compiler lays it out on a 64 bit target: eight byte
+        // fields in order, then the uint16_t and the tail padding
+        assert_eq!((size_of::<Snapshot>(), align_of::<Snapshot>()), (104, 8));
+        let offsets = [
+            offset_of!(Snapshot, uptime),
+            offset_of!(Snapshot, loads),
+            offset_of!(Snapshot, total_ram),
+            offset_of!(Snapshot, free_ram),
+            offset_of!(Snapshot, shared_ram),
+            offset_of!(Snapshot, buffer_ram),
+            offset_of!(Snapshot, total_swap),
+            offset_of!(Snapshot, free_swap),
+            offset_of!(Snapshot, total_high),
+            offset_of!(Snapshot, free_high),
+            offset_of!(Snapshot, procs),
+        ];
+        assert_eq!(offsets, [0, 8, 32, 40, 48, 56, 64, 72, 80, 88, 96]);
+    }
+
+    #[test]
+    fn header_is_up_to_date() {
+        let header = include_str!("../include/sysinfo_dot_h.h");
+        assert!(header.contains(&format!("#define SYSINFO_DOT_H_ABI_VERSION {SYSINFO_DOT_H_ABI_VERSION}")));
+        for symbol in ["sysinfo_dot_h_abi_version(void)", "sysinfo_dot_h_collect(struct sysinfo_dot_h_snapshot *out)"] {
+            assert!(header.contains(symbol), "{symbol} is missing, regenerate the header with cbindgen");
+        }
+        // the fields `layout_matches_the_header` checks, in the same order
+        let fields = [
+            "uint64_t uptime;", "double loads[3];", "uint64_t total_ram;", "uint64_t free_ram;", "uint64_t shared_ram;", "uint64_t buffer_ram;",
+            "uint64_t total_swap;", "uint64_t free_swap;", "uint64_t total_high;", "uint64_t free_high;", "uint16_t procs;",
+        ];
+        let declared: Vec<&str> = header.lines().map(str::trim).filter(|line| fields.contains(line)).collect();
+        assert_eq!(declared, fields, "the snapshot struct changed, regenerate the header with cbindgen");
+    }
+}
@@ -0,0 +1,246 @@
+//! The memory limits of the cgroup this process runs in, for seeing a container the way its
+//! processes do. `sysinfo(2)` and `/proc/meminfo` always describe the whole host.
+//!
+//! Both cgroup v2 (`memory.max`, `memory.current`, ...) and the v1 memory controller
+//! (`memory.limit_in_bytes`, ...) are understood.
+//!
+//! ```rust
+//! use sysinfo_dot_h::cgroup::Cgroup;
+//!
+//! let info = sysinfo_dot_h::collect_info();
+//! if let Ok(cgroup) = Cgroup::current() {
+//!     let scoped = cgroup.apply(&info);
+//!     assert!(scoped.total_ram <= info.total_ram);
+//! }
+//! ```
+use std::path::{Path, PathBuf};
+
+use crate::meminfo::MemInfo;
+use crate::{SysinfoError, SystemInfo};
+
+/// Where cgroup filesystems are mounted on every distribution that matters.
+const ROOT: &str = "/sys/fs/cgroup";
+
+/// v1 reports "no limit" as the largest page aligned `i64`, anything this big means unlimited.
+const UNLIMITED: u64 = 1 << 62;
+
+/// A cgroup's memory accounting, in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cgroup {
+    /// The path from `/proc/self/cgroup`, `/` inside most containers
+    pub path: String,
+    /// 1 or 2
+    pub version: u8,
+    /// The memory limit, `None` if there's none
+    pub memory_max: Option<u64>,
+    /// Memory charged to the cgroup, page cache included
+    pub memory_usage: u64,
+    /// Page cache that hasn't been touched lately, which the kernel reclaims first
+    pub inactive_file: u64,
+    /// The swap limit, `None` if there's none (or swap accounting is off)
+    pub swap_max: Option<u64>,
+    /// Swap charged to the cgroup
+    pub swap_usage: u64,
+}
+
+impl Cgroup {
+    /// Reads the cgroup of the calling process.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SysinfoError::Proc`] if `/proc/self/cgroup` (or a file of the cgroup) can't be
+    /// read, and [`SysinfoError::Missing`] if there's no memory controller for it.
+    pub fn current() -> Result<Self, SysinfoError> {
+        let cgroups = crate::read_proc("/proc/self/cgroup")?;
+        Self::read(&cgroups, Path::new(ROOT))
+    }
+
+    /// Reads the cgroup described by `cgroups` (the contents of `/proc/<pid>/cgroup`) from the
+    /// hierarchies mounted under `root`. v2 wins if it has the memory controller.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SysinfoError::Missing`] if no hierarchy has a memory controller for the cgroup.
+    pub fn read(cgroups: &str, root: &Path) -> Result<Self, SysinfoError> {
+        for line in cgroups.lines() {
+            let mut parts = line.splitn(3, ':');
+            let (Some(_), Some(""), Some(path)) = (parts.next(), parts.next(), parts.next()) else { continue };
+            // the unified hierarchy is either the whole mount or, in hybrid setups, a subdirectory
+            for mount in [root.to_path_buf(), root.join("unified")] {
+                if let Some(dir) = find_dir(&mount, path, "memory.max") {
+                    return Self::read_v2(path, &dir);
+                }
+            }
+        }
+        for line in cgroups.lines() {
+            let mut parts = line.splitn(3, ':');
+            let (Some(_), Some(controllers), Some(path)) = (parts.next(), parts.next(), parts.next()) else { continue };
+            if controllers.split(',').any(|controller| controller == "memory") {
+                if let Some(dir) = find_dir(&root.join("memory"), path, "memory.limit_in_bytes") {
+                    return Self::read_v1(path, &dir);
+                }
+            }
+        }
+        Err(SysinfoError::missing("/proc/self/cgroup", "memory controller"))
+    }
+
+    fn read_v2(path: &str, dir: &Path) -> Result<Self, SysinfoError> {
+        let stat = read(dir, "memory.stat")?;
+        Ok(Self {
+            path: path.to_string(),
+            version: 2,
+            memory_max: parsed(dir, "memory.max", limit)?,
+            memory_usage: parsed(dir, "memory.current", number)?,
+            inactive_file: stat_value(&stat, "inactive_file").unwrap_or(0),
+            // memory.swap.* only exists with swap accounting
+            swap_max: read(dir, "memory.swap.max").ok().and_then(|max| limit(&max)).flatten(),
+            swap_usage: read(dir, "memory.swap.current").ok().and_then(|usage| number(&usage)).unwrap_or(0),
+        })
+    }
+
+    fn read_v1(path: &str, dir: &Path) -> Result<Self, SysinfoError> {
+        let stat = read(dir, "memory.stat")?;
+        let memory_max = parsed(dir, "memory.limit_in_bytes", limit)?;
+        let memory_usage = parsed(dir, "memory.usage_in_bytes", number)?;
+        // memsw is memory and swap together
+        let memsw_max = read(dir, "memory.memsw.limit_in_bytes").ok().and_then(|max| limit(&max)).flatten();
+        let memsw_usage = read(dir, "memory.memsw.usage_in_bytes").ok().and_then(|usage| number(&usage));
+        Ok(Self {
+            path: path.to_string(),
+            version: 1,
+            memory_max,
+            memory_usage,
+            inactive_file: stat_value(&stat, "total_inactive_file").unwrap_or(0),
+            swap_max: memsw_max.map(|max| max.saturating_sub(memory_max.unwrap_or(0))),
+            swap_usage: memsw_usage.map_or(0, |usage| usage.saturating_sub(memory_usage)),
+        })
+    }
+
+    /// Memory in use that can't just be dropped, what the OOM killer goes by. Usage minus
+    /// inactive page cache, like `kubectl top` shows.
+    #[must_use] pub fn working_set(&self) -> u64 {
+        self.memory_usage.saturating_sub(self.inactive_file)
+    }
+
+    /// `info` as seen from inside this cgroup: totals capped at the limits and the free
+    /// amounts worked out from the cgroup's usage. Values without a limit are the host's.
+    #[must_use] pub fn apply(&self, info: &SystemInfo) -> SystemInfo {
+        let mut scoped = *info;
+        if let Some(max) = self.memory_max {
+            scoped.total_ram = info.total_ram.min(max);
+            scoped.free_ram = info.free_ram.min(scoped.total_ram.saturating_sub(self.memory_usage));
+        }
+        if let Some(max) = self.swap_max {
+            scoped.total_swap = info.total_swap.min(max);
+            scoped.free_swap = info.free_swap.min(scoped.total_swap.saturating_sub(self.swap_usage));
+        }
+        scoped
+    }
+
+    /// Like [`apply`](Self::apply), for `/proc/meminfo`. `available` becomes what's left
+    /// before the limit once inactive page cache is dropped, if that's less than the host has.
+    #[must_use] pub fn apply_meminfo(&self, meminfo: &MemInfo) -> MemInfo {
+        let mut scoped = *meminfo;
+        if let Some(max) = self.memory_max {
+            scoped.total = meminfo.total.min(max);
+            scoped.free = meminfo.free.min(scoped.total.saturating_sub(self.memory_usage));
+            let available = scoped.total.saturating_sub(self.working_set());
+            scoped.available = Some(meminfo.available.map_or(available, |host| host.min(available)));
+        }
+        if let Some(max) = self.swap_max {
+            scoped.swap_total = meminfo.swap_total.min(max);
+            scoped.swap_free = meminfo.swap_free.min(scoped.swap_total.saturating_sub(self.swap_usage));
+        }
+        scoped
+    }
+}
+
+/// The directory for `path` under `mount`, or the mount itself when `path` isn't visible
+/// there (containers without a cgroup namespace see the host's path but get their own cgroup
+/// mounted at the root). `file` tells whether the controller is there at all.
+fn find_dir(mount: &Path, path: &str, file: &str) -> Option<PathBuf> {
+    let dir = mount.join(path.trim_start_matches('/'));
+    [dir, mount.to_path_buf()].into_iter().find(|dir| dir.join(file).is_file())
+}
+
+fn read(dir: &Path, file: &str) -> Result<String, SysinfoError> {
+    let path = dir.join(file);
+    std::fs::read_to_string(&path).map_err(|e| SysinfoError::proc(path.display().to_string(), e))
+}
+
+/// `file` read and turned into a value by `parse`, which returns `None` for garbage.
+fn parsed<T>(dir: &Path, file: &str, parse: fn(&str) -> Option<T>) -> Result<T, SysinfoError> {
+    let contents = read(dir, file)?;
+    parse(&contents).ok_or_else(|| SysinfoError::parse(dir.join(file).display().to_string(), contents.trim()))
+}
+
+fn number(s: &str) -> Option<u64> {
+    s.trim().parse().ok()
+}
+
+/// `max` or a huge v1 value is `Some(None)`, garbage is `None`.
+fn limit(s: &str) -> Option<Option<u64>> {
+    if s.trim() == "max" {
+        return Some(None);
+    }
+    number(s).map(|limit| (limit < UNLIMITED).then_some(limit))
+}
+
+/// A `key value` line of `memory.stat`.
+fn stat_value(stat: &str, key: &str) -> Option<u64> {
+    stat.lines().find_map(|line| number(line.strip_prefix(key)?.strip_prefix(' ')?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_root(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("sysinfo_dot_h-cgroup-{name}-{}", std::process::id()));
+        for (file, contents) in files {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn v2() {
+        let root = fake_root("v2", &[
+            ("app.slice/memory.max", "1073741824\n"),
+            ("app.slice/memory.current", "805306368\n"),
+            ("app.slice/memory.stat", "anon 536870912\ninactive_file 268435456\n"),
+            ("app.slice/memory.swap.max", "max\n"),
+        ]);
+        let cgroup = Cgroup::read("0::/app.slice\n", &root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!((cgroup.version, cgroup.memory_max, cgroup.swap_max), (2, Some(1 << 30), None));
+        assert_eq!(cgroup.working_set(), 512 << 20);
+
+        let host = SystemInfo { total_ram: 8 << 30, free_ram: 4 << 30, total_swap: 2 << 30, free_swap: 2 << 30, ..Default::default() };
+        let scoped = cgroup.apply(&host);
+        assert_eq!((scoped.total_ram, scoped.free_ram), (1 << 30, 256 << 20));
+        assert_eq!((scoped.total_swap, scoped.free_swap), (2 << 30, 2 << 30));
+
+        let meminfo = MemInfo { total: 8 << 30, free: 4 << 30, available: Some(6 << 30), ..Default::default() };
+        assert_eq!(cgroup.apply_meminfo(&meminfo).available, Some(512 << 20));
+    }
+
+    #[test]
+    fn v1_in_a_container() {
+        // no cgroup namespace: the host's path, but the container's cgroup at the mount root
+        let root = fake_root("v1", &[
+            ("memory/memory.limit_in_bytes", "9223372036854771712\n"),
+            ("memory/memory.usage_in_bytes", "1048576\n"),
+            ("memory/memory.stat", "cache 0\ntotal_inactive_file 4096\n"),
+            ("unified/cgroup.procs", ""),
+        ]);
+        let cgroup = Cgroup::read("4:memory:/docker/8d1f\n0::/\n", &root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!((cgroup.version, cgroup.memory_max, cgroup.inactive_file), (1, None, 4096));
+        let host = SystemInfo { total_ram: 8 << 30, free_ram: 4 << 30, ..Default::default() };
+        assert_eq!(cgroup.apply(&host), host);
+        assert!(Cgroup::read("0::/\n", Path::new("/nonexistent")).is_err());
+    }
+}
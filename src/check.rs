@@ -0,0 +1,393 @@
+//! Nagios/Icinga style checks, so this crate can stand in for `check_mem`/`check_load`.
+//!
+//! A [`Check`] evaluates a set of [`Rule`]s against a snapshot and produces a [`Report`], which
+//! prints as standard plugin output (status, message and perfdata) and maps to the standard exit
+//! codes.
+//!
+//! Thresholds use the usual [plugin range syntax](https://nagios-plugins.org/doc/guidelines.html#THRESHOLDFORMAT):
+//! `10` alerts outside of `0..=10`, `10:` below 10, `~:10` above 10, `10:20` outside of
+//! `10..=20`, `@10:20` inside of it and `~:` never (no limits).
+//!
+//! ```rust
+//! use sysinfo_dot_h::check::{Check, Metric};
+//!
+//! let report = Check::new("MEM")
+//!     .rule(Metric::FreeRamPercent, "10:", "5:").unwrap()
+//!     .rule(Metric::Load1, "8", "16").unwrap()
+//!     .evaluate(&sysinfo_dot_h::collect_info());
+//!
+//! println!("{report}"); // MEM OK - free_ram_percent is 61.84%, load1 is 0.52 | ...
+//! // std::process::exit(report.status().exit_code());
+//! ```
+use std::fmt;
+use std::str::FromStr;
+
+use crate::provider::SysinfoProvider;
+use crate::{SysinfoError, SystemInfo};
+
+/// A value a [`Rule`] can look at.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Metric {
+    /// 1 minute load average
+    Load1,
+    /// 5 minute load average
+    Load5,
+    /// 15 minute load average
+    Load15,
+    /// Free RAM in bytes (note: freeram != available memory)
+    FreeRam,
+    /// Free RAM as a percentage of the total
+    FreeRamPercent,
+    /// Free swap in bytes
+    FreeSwap,
+    /// Used swap as a percentage of the total, 0 without swap
+    SwapUsedPercent,
+    /// Number of current processes
+    Procs,
+    /// Seconds since boot
+    Uptime,
+}
+
+impl Metric {
+    /// Every metric, in declaration order.
+    pub const ALL: [Metric; 9] = [
+        Metric::Load1, Metric::Load5, Metric::Load15, Metric::FreeRam, Metric::FreeRamPercent,
+        Metric::FreeSwap, Metric::SwapUsedPercent, Metric::Procs, Metric::Uptime,
+    ];
+
+    /// The name used in messages, perfdata and by [`FromStr`].
+    #[must_use] pub fn name(self) -> &'static str {
+        match self {
+            Metric::Load1 => "load1",
+            Metric::Load5 => "load5",
+            Metric::Load15 => "load15",
+            Metric::FreeRam => "free_ram",
+            Metric::FreeRamPercent => "free_ram_percent",
+            Metric::FreeSwap => "free_swap",
+            Metric::SwapUsedPercent => "swap_used_percent",
+            Metric::Procs => "procs",
+            Metric::Uptime => "uptime",
+        }
+    }
+
+    /// Reads this metric out of `info`.
+    #[must_use] pub fn value(self, info: &SystemInfo) -> f64 {
+        let percent = |part: u64, total: u64| if total == 0 { 0.0 } else { part as f64 * 100.0 / total as f64 };
+        match self {
+            Metric::Load1 => info.loads[0],
+            Metric::Load5 => info.loads[1],
+            Metric::Load15 => info.loads[2],
+            Metric::FreeRam => info.free_ram as f64,
+            Metric::FreeRamPercent => percent(info.free_ram, info.total_ram),
+            Metric::FreeSwap => info.free_swap as f64,
+            Metric::SwapUsedPercent => percent(info.total_swap.saturating_sub(info.free_swap), info.total_swap),
+            Metric::Procs => f64::from(info.procs),
+            Metric::Uptime => info.uptime.as_secs() as f64,
+        }
+    }
+
+    /// The perfdata unit of measurement.
+    fn uom(self) -> &'static str {
+        match self {
+            Metric::FreeRam | Metric::FreeSwap => "B",
+            Metric::FreeRamPercent | Metric::SwapUsedPercent => "%",
+            Metric::Uptime => "s",
+            Metric::Load1 | Metric::Load5 | Metric::Load15 | Metric::Procs => "",
+        }
+    }
+
+    /// The perfdata `min;max` fields.
+    fn bounds(self, info: &SystemInfo) -> (String, String) {
+        match self {
+            Metric::FreeRam => ("0".to_string(), info.total_ram.to_string()),
+            Metric::FreeSwap => ("0".to_string(), info.total_swap.to_string()),
+            Metric::FreeRamPercent | Metric::SwapUsedPercent => ("0".to_string(), "100".to_string()),
+            _ => ("0".to_string(), String::new()),
+        }
+    }
+
+    fn format(self, value: f64) -> String {
+        match self {
+            Metric::FreeRam | Metric::FreeSwap | Metric::Procs | Metric::Uptime => format!("{value:.0}"),
+            _ => format!("{value:.2}"),
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Metric {
+    type Err = SysinfoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Metric::ALL.into_iter().find(|metric| metric.name() == s).ok_or_else(|| SysinfoError::Invalid(format!("unknown metric {s:?}")))
+    }
+}
+
+/// A plugin threshold range, see the [module docs](self) for the syntax.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Range {
+    start: f64,
+    end: f64,
+    inside: bool,
+}
+
+impl Range {
+    /// Whether `value` should raise an alert.
+    #[must_use] pub fn alerts(&self, value: f64) -> bool {
+        let within = self.start <= value && value <= self.end;
+        within == self.inside
+    }
+}
+
+impl FromStr for Range {
+    type Err = SysinfoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || SysinfoError::Invalid(format!("invalid threshold range {s:?}"));
+        let (inside, range) = match s.strip_prefix('@') {
+            Some(range) => (true, range),
+            None => (false, s),
+        };
+        let number = |n: &str| n.parse::<f64>().map_err(|_| invalid());
+        let (start, end) = match range.split_once(':') {
+            None => (0.0, number(range)?),
+            Some(("~", "")) => (f64::NEG_INFINITY, f64::INFINITY),
+            Some(("~", end)) => (f64::NEG_INFINITY, number(end)?),
+            Some((start, "")) => (number(start)?, f64::INFINITY),
+            Some((start, end)) => (number(start)?, number(end)?),
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        Ok(Self { start, end, inside })
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.inside {
+            f.write_str("@")?;
+        }
+        // open ends are left empty (or `~` at the start), as in the plugin guidelines
+        match (self.start, self.end) {
+            (start, end) if start == f64::NEG_INFINITY && end == f64::INFINITY => f.write_str("~:"),
+            (start, end) if start == f64::NEG_INFINITY => write!(f, "~:{end}"),
+            (start, end) if end == f64::INFINITY => write!(f, "{start}:"),
+            (start, end) if start == 0.0 && !self.inside => write!(f, "{end}"),
+            (start, end) => write!(f, "{start}:{end}"),
+        }
+    }
+}
+
+/// A metric with its warning and critical ranges.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rule {
+    /// What to look at
+    pub metric: Metric,
+    /// Raises [`Status::Warning`]
+    pub warning: Option<Range>,
+    /// Raises [`Status::Critical`]
+    pub critical: Option<Range>,
+}
+
+impl Rule {
+    /// What `value` means for this rule.
+    #[must_use] pub fn status(&self, value: f64) -> Status {
+        if self.critical.is_some_and(|range| range.alerts(value)) {
+            Status::Critical
+        } else if self.warning.is_some_and(|range| range.alerts(value)) {
+            Status::Warning
+        } else {
+            Status::Ok
+        }
+    }
+}
+
+/// A plugin state. Ordered from best to worst, except that `Unknown` sorts last.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Status {
+    /// Everything's fine
+    Ok,
+    /// A warning threshold was crossed
+    Warning,
+    /// A critical threshold was crossed
+    Critical,
+    /// The check couldn't be done at all
+    Unknown,
+}
+
+impl Status {
+    /// The exit code plugins use for this state.
+    #[must_use] pub fn exit_code(self) -> i32 {
+        match self {
+            Status::Ok => 0,
+            Status::Warning => 1,
+            Status::Critical => 2,
+            Status::Unknown => 3,
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Ok => "OK",
+            Status::Warning => "WARNING",
+            Status::Critical => "CRITICAL",
+            Status::Unknown => "UNKNOWN",
+        })
+    }
+}
+
+/// A set of rules checked together, producing one line of plugin output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    label: String,
+    rules: Vec<Rule>,
+}
+
+impl Check {
+    /// A check without any rules. `label` is the first word of the output, like `MEM` or `LOAD`.
+    #[must_use] pub fn new(label: &str) -> Self {
+        Self { label: label.to_string(), rules: Vec::new() }
+    }
+
+    /// Adds a rule. Empty ranges mean "never".
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SysinfoError::Invalid`] if a range is malformed.
+    pub fn rule(mut self, metric: Metric, warning: &str, critical: &str) -> Result<Self, SysinfoError> {
+        let range = |s: &str| if s.is_empty() { Ok(None) } else { s.parse().map(Some) };
+        self.rules.push(Rule { metric, warning: range(warning)?, critical: range(critical)? });
+        Ok(self)
+    }
+
+    /// Evaluates every rule against `info`.
+    #[must_use] pub fn evaluate(&self, info: &SystemInfo) -> Report {
+        let mut status = Status::Ok;
+        let mut messages = Vec::new();
+        let mut perfdata = Vec::new();
+        for rule in &self.rules {
+            let value = rule.metric.value(info);
+            status = status.max(rule.status(value));
+
+            let formatted = rule.metric.format(value);
+            messages.push(format!("{} is {formatted}{}", rule.metric, rule.metric.uom()));
+            let (min, max) = rule.metric.bounds(info);
+            let range = |range: Option<Range>| range.map(|range| range.to_string()).unwrap_or_default();
+            perfdata.push(format!(
+                "{}={formatted}{};{};{};{min};{max}",
+                rule.metric, rule.metric.uom(), range(rule.warning), range(rule.critical),
+            ));
+        }
+
+        Report { label: self.label.clone(), status, message: messages.join(", "), perfdata: perfdata.join(" ") }
+    }
+
+    /// Takes a snapshot from `provider` and evaluates it, [`unknown`](Self::unknown) if that
+    /// fails.
+    #[must_use] pub fn run<P: SysinfoProvider + ?Sized>(&self, provider: &P) -> Report {
+        match provider.collect() {
+            Ok(info) => self.evaluate(&info),
+            Err(e) => self.unknown(&e.to_string()),
+        }
+    }
+
+    /// A report for when the snapshot couldn't even be collected.
+    #[must_use] pub fn unknown(&self, error: &str) -> Report {
+        Report { label: self.label.clone(), status: Status::Unknown, message: error.to_string(), perfdata: String::new() }
+    }
+}
+
+/// The outcome of a [`Check`]. `Display` gives the plugin output line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    label: String,
+    status: Status,
+    message: String,
+    perfdata: String,
+}
+
+impl Report {
+    /// The worst status of every rule.
+    #[must_use] pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// The perfdata part of the output (after the `|`).
+    #[must_use] pub fn perfdata(&self) -> &str {
+        &self.perfdata
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} - {}", self.label, self.status, self.message)?;
+        if !self.perfdata.is_empty() {
+            write!(f, " | {}", self.perfdata)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> SystemInfo {
+        SystemInfo { loads: [9.5, 4.0, 1.0], total_ram: 1000, free_ram: 80, ..Default::default() }
+    }
+
+    #[test]
+    fn range_syntax() {
+        let range = |s: &str| s.parse::<Range>().unwrap();
+        assert!(range("10").alerts(11.0) && range("10").alerts(-1.0) && !range("10").alerts(10.0));
+        assert!(range("10:").alerts(9.0) && !range("10:").alerts(1e9));
+        assert!(range("~:10").alerts(11.0) && !range("~:10").alerts(-1e9));
+        assert!(range("10:20").alerts(21.0) && !range("10:20").alerts(15.0));
+        assert!(range("@10:20").alerts(15.0) && !range("@10:20").alerts(21.0));
+        assert!(!range("~:").alerts(-1e9) && !range("~:").alerts(1e9));
+        assert!("20:10".parse::<Range>().is_err());
+        assert!("ten".parse::<Range>().is_err());
+    }
+
+    #[test]
+    fn range_round_trips() {
+        for s in ["10", "10:", "0:", "~:", "~:10", "10:20", "@10:20", "@0:5", "@10:"] {
+            assert_eq!(s.parse::<Range>().unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn worst_status_wins() {
+        let check = Check::new("MEM")
+            .rule(Metric::FreeRamPercent, "10:", "5:").unwrap()
+            .rule(Metric::Load1, "8", "16").unwrap();
+        let report = check.evaluate(&info());
+        assert_eq!(report.status(), Status::Warning);
+        assert_eq!(
+            report.to_string(),
+            "MEM WARNING - free_ram_percent is 8.00%, load1 is 9.50 | free_ram_percent=8.00%;10:;5:;0;100 load1=9.50;8;16;0;"
+        );
+    }
+
+    #[test]
+    fn unknown_report() {
+        let report = Check::new("LOAD").unknown("sysinfo() failed");
+        assert_eq!(report.status().exit_code(), 3);
+        assert_eq!(report.to_string(), "LOAD UNKNOWN - sysinfo() failed");
+    }
+
+    #[test]
+    fn metric_names() {
+        for metric in Metric::ALL {
+            assert_eq!(metric.name().parse::<Metric>().unwrap(), metric);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! Where time comes from, so that everything timed (the [sampler](crate::sampler), the
+//! [recording](crate::recording) timestamps, replays) can be tested with
+//! [`MockClock`](crate::testing::MockClock) instead of sleeping.
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// A source of [`Instant`]s and [`SystemTime`]s.
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// The monotonic time, for intervals.
+    fn now(&self) -> Instant;
+
+    /// The wall clock time, for timestamps.
+    fn system_time(&self) -> SystemTime;
+
+    /// How long to really wait when something is due `remaining` from [`now`](Self::now),
+    /// before looking at the clock again. The whole of it for real clocks; fake ones only wait a
+    /// moment, their time moves when the test says so.
+    fn wait_for(&self, remaining: Duration) -> Duration {
+        remaining
+    }
+}
+
+/// The real clocks, [`Instant::now`] and [`SystemTime::now`]. What everything uses by default.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        (**self).system_time()
+    }
+
+    fn wait_for(&self, remaining: Duration) -> Duration {
+        (**self).wait_for(remaining)
+    }
+}
+
+/// The default for the clock fields of the sampler and recordings.
+pub(crate) fn system() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
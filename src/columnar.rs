@@ -0,0 +1,140 @@
+//! Columnar export of sample history: Arrow [`RecordBatch`]es, and Parquet files with the
+//! `parquet` feature, for analysis in DataFusion, pandas, polars, ...
+//!
+//! Every [`SystemInfo`] becomes one row, with the same column names as
+//! [`SystemInfo::to_json`] except that `loads` is split into `load1`, `load5` and `load15`.
+//! `uptime` (in seconds) doubles as the time axis.
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use sysinfo_dot_h::{columnar, sampler::Sampler};
+//!
+//! let sampler = Sampler::builder().interval(Duration::from_millis(1)).history(16).start();
+//! std::thread::sleep(Duration::from_millis(20));
+//!
+//! let batch = columnar::to_record_batch(&sampler.history()).unwrap();
+//! assert!(batch.num_rows() > 0);
+//! ```
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, UInt16Array, UInt64Array};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+
+use crate::SystemInfo;
+
+/// The schema of [`to_record_batch`]'s output.
+#[must_use] pub fn schema() -> SchemaRef {
+    let u64_column = |name: &str| Field::new(name, DataType::UInt64, false);
+    let f64_column = |name: &str| Field::new(name, DataType::Float64, false);
+    Arc::new(Schema::new(vec![
+        u64_column("uptime"),
+        f64_column("load1"),
+        f64_column("load5"),
+        f64_column("load15"),
+        u64_column("total_ram"),
+        u64_column("free_ram"),
+        u64_column("shared_ram"),
+        u64_column("buffer_ram"),
+        u64_column("total_swap"),
+        u64_column("free_swap"),
+        u64_column("total_high"),
+        u64_column("free_high"),
+        Field::new("procs", DataType::UInt16, false),
+    ]))
+}
+
+/// Converts `samples` (e.g. [`Sampler::history`](crate::sampler::Sampler::history)) into a
+/// single record batch, one row per sample.
+///
+/// # Errors
+///
+/// Never fails in practice, the error is passed through from Arrow.
+pub fn to_record_batch(samples: &[SystemInfo]) -> Result<RecordBatch, ArrowError> {
+    let u64_column = |field: fn(&SystemInfo) -> u64| -> ArrayRef {
+        Arc::new(samples.iter().map(field).collect::<UInt64Array>())
+    };
+    let load_column = |minutes: usize| -> ArrayRef {
+        Arc::new(samples.iter().map(|info| info.loads[minutes]).collect::<Float64Array>())
+    };
+
+    RecordBatch::try_new(schema(), vec![
+        u64_column(|info| info.uptime.as_secs()),
+        load_column(0),
+        load_column(1),
+        load_column(2),
+        u64_column(|info| info.total_ram),
+        u64_column(|info| info.free_ram),
+        u64_column(|info| info.shared_ram),
+        u64_column(|info| info.buffer_ram),
+        u64_column(|info| info.total_swap),
+        u64_column(|info| info.free_swap),
+        u64_column(|info| info.total_high),
+        u64_column(|info| info.free_high),
+        Arc::new(samples.iter().map(|info| info.procs).collect::<UInt16Array>()),
+    ])
+}
+
+/// Writes `samples` into `writer` as a Parquet file.
+///
+/// The file is uncompressed unless you enable one of `parquet`'s compression features yourself
+/// and pass matching `properties`.
+///
+/// # Errors
+///
+/// Returns an error if writing fails.
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: std::io::Write + Send>(
+    samples: &[SystemInfo],
+    writer: W,
+    properties: Option<parquet::file::properties::WriterProperties>,
+) -> Result<(), parquet::errors::ParquetError> {
+    let batch = to_record_batch(samples)?;
+    let mut writer = parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), properties)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn samples() -> Vec<SystemInfo> {
+        (1..=3)
+            .map(|i| SystemInfo {
+                uptime: Duration::from_secs(i),
+                loads: [i as f64, 0.5, 0.25],
+                free_ram: i * 1024,
+                procs: i as u16,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn one_row_per_sample() {
+        let batch = to_record_batch(&samples()).unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.num_columns(), schema().fields().len());
+
+        let free_ram = batch.column_by_name("free_ram").unwrap().as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(free_ram.values(), &[1024, 2048, 3072]);
+        let load1 = batch.column_by_name("load1").unwrap().as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(load1.values(), &[1.0, 2.0, 3.0]);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_round_trip() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let path = std::env::temp_dir().join(format!("sysinfo_dot_h-{}.parquet", std::process::id()));
+        write_parquet(&samples(), std::fs::File::create(&path).unwrap(), None).unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap()).unwrap().build().unwrap();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(batches, vec![to_record_batch(&samples()).unwrap()]);
+    }
+}
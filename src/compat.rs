@@ -0,0 +1,71 @@
+//! Today's names and layouts, kept as they are across the coming v1 renames: the C-like
+//! [`sysinfo`](struct@sysinfo) struct (with the same `#[repr(C)]` layout as `<sys/sysinfo.h>`), the raw
+//! `sysinfo()` binding, [`collect`], [`try_collect_into`] and [`try_collect`] (with its `String`
+//! error, the crate root's returns a [`SysinfoError`](crate::SysinfoError) now).
+//!
+//! Code that was written against them only has to change its imports once the crate root moves
+//! on:
+//!
+//! ```rust
+//! use sysinfo_dot_h::compat::{try_collect, sysinfo};
+//!
+//! let info: sysinfo = try_collect().unwrap();
+//! dbg!(info.totalram * u64::from(info.mem_unit));
+//! ```
+pub use crate::sysinfo;
+
+/// [`crate::collect`], which v1 moves here.
+#[must_use] pub fn collect() -> sysinfo {
+    let mut info: sysinfo = unsafe { std::mem::zeroed() };
+    let _ = crate::syscall_into(&mut info);
+    info
+}
+
+/// [`crate::try_collect_into`], which v1 moves here.
+///
+/// # Errors
+///
+/// Same as [`crate::try_collect_into`].
+pub fn try_collect_into(info: &mut sysinfo) -> Result<(), crate::SysinfoError> {
+    crate::syscall_into(info)
+}
+
+/// [`crate::try_collect`] with the error as a `String`, like it used to be.
+///
+/// # Errors
+///
+/// Same as [`crate::try_collect`].
+pub fn try_collect() -> Result<sysinfo, String> {
+    crate::syscall().map_err(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{align_of, offset_of, size_of};
+    use std::os::raw::{c_int, c_ulong};
+
+    /// The layout glibc and musl use, the one the kernel writes.
+    #[test]
+    fn layout_matches_the_c_struct() {
+        let long = size_of::<c_ulong>();
+        assert_eq!(align_of::<sysinfo>(), align_of::<c_ulong>());
+        assert_eq!(offset_of!(sysinfo, uptime), 0);
+        assert_eq!(offset_of!(sysinfo, loads), long);
+        assert_eq!(offset_of!(sysinfo, totalram), 4 * long);
+        assert_eq!(offset_of!(sysinfo, freeswap), 9 * long);
+        assert_eq!(offset_of!(sysinfo, procs), 10 * long);
+        assert_eq!(offset_of!(sysinfo, pad), 10 * long + 2);
+        assert_eq!(offset_of!(sysinfo, totalhigh), 11 * long);
+        assert_eq!(offset_of!(sysinfo, mem_unit), 13 * long);
+        // mem_unit and the tail padding
+        if long == 8 {
+            assert_eq!(size_of::<sysinfo>(), 112);
+        }
+
+        let _: unsafe extern "C" fn(*mut sysinfo) -> c_int = sysinfo;
+        let _: fn() -> sysinfo = collect;
+        let _: fn() -> Result<sysinfo, String> = try_collect;
+        let _: fn(&mut sysinfo) -> Result<(), crate::SysinfoError> = try_collect_into;
+    }
+}
@@ -0,0 +1,215 @@
+//! `/proc/stat`'s CPU times, for the one thing `sysinfo(2)` doesn't say anything about: how busy
+//! the CPUs are. The times only ever go up, so utilization is always between two reads.
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use sysinfo_dot_h::cpu::CpuStat;
+//!
+//! let before = CpuStat::read().unwrap();
+//! std::thread::sleep(Duration::from_millis(100));
+//! let usage = CpuStat::read().unwrap().utilization_since(&before);
+//! println!("{:.1}% busy, {:.1}% waiting for I/O", usage.total.busy(), usage.total.iowait);
+//! ```
+use std::str::FromStr;
+
+use crate::SysinfoError;
+
+/// Time spent in each state since boot, in jiffies (`USER_HZ`, 1/100 s practically everywhere).
+/// `guest` and `guest_nice` aren't separate, the kernel already counts them in `user` and `nice`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    /// Idle with I/O outstanding. Not reliable per core, the task waiting may move
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    /// Taken by the hypervisor for other guests
+    pub steal: u64,
+}
+
+/// One `cpuN` line. Offline cores have none, so the ids can have holes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Core {
+    pub id: usize,
+    pub times: CpuTimes,
+}
+
+/// The `cpu` lines of `/proc/stat`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CpuStat {
+    /// All cores together
+    pub total: CpuTimes,
+    pub cores: Vec<Core>,
+}
+
+/// The share of the time between two reads spent in each state, in percent. They add up to 100
+/// (give or take rounding), or are all 0 if no time passed.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Utilization {
+    pub user: f64,
+    pub nice: f64,
+    pub system: f64,
+    pub idle: f64,
+    pub iowait: f64,
+    pub irq: f64,
+    pub softirq: f64,
+    pub steal: f64,
+}
+
+/// [`CpuStat::utilization_since`]: all cores together, and each core that was in both reads.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CpuUsage {
+    pub total: Utilization,
+    pub cores: Vec<(usize, Utilization)>,
+}
+
+impl CpuTimes {
+    /// Everything added up.
+    #[must_use] pub fn total(&self) -> u64 {
+        self.user + self.nice + self.system + self.idle + self.iowait + self.irq + self.softirq + self.steal
+    }
+
+    /// What changed since `earlier`, in percent of the time that passed. A counter that went
+    /// backwards (a core that went offline and came back) counts as 0.
+    #[must_use] pub fn utilization_since(&self, earlier: &CpuTimes) -> Utilization {
+        let fields = |times: &CpuTimes| [times.user, times.nice, times.system, times.idle, times.iowait, times.irq, times.softirq, times.steal];
+        let deltas: Vec<u64> = fields(self).iter().zip(fields(earlier)).map(|(now, then)| now.saturating_sub(then)).collect();
+        let elapsed: u64 = deltas.iter().sum();
+        let percent = |i: usize| if elapsed == 0 { 0.0 } else { deltas[i] as f64 * 100.0 / elapsed as f64 };
+        Utilization {
+            user: percent(0),
+            nice: percent(1),
+            system: percent(2),
+            idle: percent(3),
+            iowait: percent(4),
+            irq: percent(5),
+            softirq: percent(6),
+            steal: percent(7),
+        }
+    }
+}
+
+impl Utilization {
+    /// Neither idle nor waiting for I/O, what `top` calls busy.
+    #[must_use] pub fn busy(&self) -> f64 {
+        self.user + self.nice + self.system + self.irq + self.softirq + self.steal
+    }
+}
+
+impl CpuStat {
+    /// Reads `/proc/stat`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or has no `cpu` line.
+    pub fn read() -> Result<Self, SysinfoError> {
+        crate::read_proc("/proc/stat")?.parse()
+    }
+
+    /// The utilization between `earlier` and this read, overall and per core.
+    #[must_use] pub fn utilization_since(&self, earlier: &CpuStat) -> CpuUsage {
+        CpuUsage {
+            total: self.total.utilization_since(&earlier.total),
+            cores: self
+                .cores
+                .iter()
+                .filter_map(|core| {
+                    let then = earlier.cores.iter().find(|then| then.id == core.id)?;
+                    Some((core.id, core.times.utilization_since(&then.times)))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for CpuStat {
+    type Err = SysinfoError;
+
+    /// Parses the contents of `/proc/stat`. The other lines (`intr`, `ctxt`, ...) are ignored, and
+    /// so are the columns old kernels don't have (`steal` came in 2.6.11).
+    fn from_str(s: &str) -> Result<Self, SysinfoError> {
+        let mut stat = CpuStat::default();
+        let mut has_total = false;
+        for line in s.lines() {
+            let mut columns = line.split_whitespace();
+            let Some(name) = columns.next().and_then(|name| name.strip_prefix("cpu")) else { continue };
+            let invalid = || SysinfoError::parse("/proc/stat", line);
+            let numbers: Vec<u64> = columns.map(str::parse).collect::<Result<_, _>>().map_err(|_| invalid())?;
+            if numbers.len() < 4 {
+                return Err(invalid());
+            }
+            let column = |i: usize| numbers.get(i).copied().unwrap_or(0);
+            let times = CpuTimes {
+                user: column(0),
+                nice: column(1),
+                system: column(2),
+                idle: column(3),
+                iowait: column(4),
+                irq: column(5),
+                softirq: column(6),
+                steal: column(7),
+            };
+            if name.is_empty() {
+                stat.total = times;
+                has_total = true;
+            } else {
+                stat.cores.push(Core { id: name.parse().map_err(|_| invalid())?, times });
+            }
+        }
+        if !has_total {
+            return Err(SysinfoError::missing("/proc/stat", "cpu line"));
+        }
+        Ok(stat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAT: &str = "\
+cpu  10132153 290696 3084719 46828483 16683 0 25195 0 175628 0
+cpu0 1393280 32966 572056 13343292 6130 0 17875 0 23933 0
+cpu2 1335924 27302 507475 13350224 3133 0 2954 0 24196 0
+intr 199292407 57 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+ctxt 392530767
+btime 1700000000
+";
+
+    #[test]
+    fn parses() {
+        let stat: CpuStat = STAT.parse().unwrap();
+        assert_eq!(stat.total.user, 10132153);
+        assert_eq!(stat.total.idle, 46828483);
+        assert_eq!(stat.cores.iter().map(|core| core.id).collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(stat.cores[1].times.softirq, 2954);
+
+        let old: CpuStat = "cpu 1 2 3 4\n".parse().unwrap();
+        assert_eq!((old.total.idle, old.total.steal), (4, 0));
+        assert!("intr 1\n".parse::<CpuStat>().is_err());
+        assert!("cpu 1 2\n".parse::<CpuStat>().is_err());
+        assert!("cpu 1 2 3 x\n".parse::<CpuStat>().is_err());
+        assert!(CpuStat::read().unwrap().total.total() > 0);
+    }
+
+    #[test]
+    fn utilization() {
+        let earlier = CpuTimes { user: 100, idle: 100, ..Default::default() };
+        let now = CpuTimes { user: 130, system: 10, idle: 150, iowait: 10, ..Default::default() };
+        let usage = now.utilization_since(&earlier);
+        assert_eq!((usage.user, usage.system, usage.idle, usage.iowait), (30.0, 10.0, 50.0, 10.0));
+        assert_eq!(usage.busy(), 40.0);
+        assert_eq!(now.utilization_since(&now), Utilization::default());
+        // a counter going backwards doesn't make anything negative
+        assert_eq!(earlier.utilization_since(&now).user, 0.0);
+
+        let before = CpuStat { total: earlier, cores: vec![Core { id: 0, times: earlier }, Core { id: 1, times: earlier }] };
+        let after = CpuStat { total: now, cores: vec![Core { id: 1, times: now }, Core { id: 3, times: now }] };
+        let usage = after.utilization_since(&before);
+        assert_eq!(usage.total.busy(), 40.0);
+        assert_eq!(usage.cores.iter().map(|(id, _)| *id).collect::<Vec<_>>(), [1]);
+    }
+}
@@ -0,0 +1,235 @@
+//! Exports snapshots as a D-Bus object, using [zbus](https://docs.rs/zbus).
+//!
+//! The object lives at [`OBJECT_PATH`] and implements the [`INTERFACE`] interface. Every property
+//! is read-only and a single `org.freedesktop.DBus.Properties.PropertiesChanged` signal is emitted
+//! per sampler tick, so widgets can just subscribe instead of polling.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! let export = sysinfo_dot_h::dbus::export_session(Duration::from_secs(2)).unwrap();
+//! // `busctl --user introspect io.github.WilliamAnimate.SysinfoDotH /io/github/WilliamAnimate/SysinfoDotH`
+//! std::thread::park();
+//! # drop(export);
+//! ```
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use zbus::blocking::connection::Builder;
+use zbus::blocking::Connection;
+use zbus::object_server::{Interface, SignalEmitter};
+use zbus::zvariant::Value;
+
+use crate::sampler::Sampler;
+use crate::SystemInfo;
+
+/// The well-known name requested by [`export_session`] and [`export_system`].
+pub const BUS_NAME: &str = "io.github.WilliamAnimate.SysinfoDotH";
+/// Where the object is served.
+pub const OBJECT_PATH: &str = "/io/github/WilliamAnimate/SysinfoDotH";
+/// The name of the exported interface.
+pub const INTERFACE: &str = "io.github.WilliamAnimate.SysinfoDotH1";
+
+/// The exported object. Memory properties are in bytes and `Uptime` is in seconds.
+///
+/// The snapshot lives behind its own (std) lock rather than being updated through
+/// `InterfaceRef::get_mut`, so the sampler thread never waits on zbus' async interface lock.
+#[derive(Debug, Default)]
+struct Stats {
+    info: Arc<Mutex<SystemInfo>>,
+}
+
+#[zbus::interface(name = "io.github.WilliamAnimate.SysinfoDotH1")]
+impl Stats {
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn uptime(&self) -> u64 {
+        self.info().uptime.as_secs()
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn loads(&self) -> (f64, f64, f64) {
+        let [one, five, fifteen] = self.info().loads;
+        (one, five, fifteen)
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn total_ram(&self) -> u64 {
+        self.info().total_ram
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn free_ram(&self) -> u64 {
+        self.info().free_ram
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn shared_ram(&self) -> u64 {
+        self.info().shared_ram
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn buffer_ram(&self) -> u64 {
+        self.info().buffer_ram
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn total_swap(&self) -> u64 {
+        self.info().total_swap
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn free_swap(&self) -> u64 {
+        self.info().free_swap
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn total_high(&self) -> u64 {
+        self.info().total_high
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn free_high(&self) -> u64 {
+        self.info().free_high
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn procs(&self) -> u16 {
+        self.info().procs
+    }
+}
+
+impl Stats {
+    fn info(&self) -> SystemInfo {
+        *self.info.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Every property, keyed by its D-Bus name.
+fn properties(info: &SystemInfo) -> HashMap<&'static str, Value<'static>> {
+    let [one, five, fifteen] = info.loads;
+    HashMap::from([
+        ("Uptime", Value::from(info.uptime.as_secs())),
+        ("Loads", Value::from((one, five, fifteen))),
+        ("TotalRam", Value::from(info.total_ram)),
+        ("FreeRam", Value::from(info.free_ram)),
+        ("SharedRam", Value::from(info.shared_ram)),
+        ("BufferRam", Value::from(info.buffer_ram)),
+        ("TotalSwap", Value::from(info.total_swap)),
+        ("FreeSwap", Value::from(info.free_swap)),
+        ("TotalHigh", Value::from(info.total_high)),
+        ("FreeHigh", Value::from(info.free_high)),
+        ("Procs", Value::from(info.procs)),
+    ])
+}
+
+/// A running D-Bus export. The object is removed and the sampler stopped when this is dropped.
+#[derive(Debug)]
+pub struct DbusExport {
+    sampler: Option<Sampler>,
+    connection: Connection,
+}
+
+impl DbusExport {
+    /// The connection the object is served on.
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+}
+
+impl Drop for DbusExport {
+    fn drop(&mut self) {
+        // stop ticking first, so no signal is emitted for an object that's gone
+        drop(self.sampler.take());
+        let _ = self.connection.object_server().remove::<Stats, _>(OBJECT_PATH);
+    }
+}
+
+/// Builds a connection from `builder` serving the object, refreshing it every `interval`.
+///
+/// Pass a builder with a [`name`](Builder::name) to also request a well-known name, or use
+/// [`export_session`]/[`export_system`]. The object is registered before the connection is
+/// built, so no early call can miss it.
+///
+/// # Errors
+///
+/// Returns an error if the connection can't be established.
+pub fn export(builder: Builder<'_>, interval: Duration) -> zbus::Result<DbusExport> {
+    let stats = Stats::default();
+    let shared = Arc::clone(&stats.info);
+    let connection = builder.serve_at(OBJECT_PATH, stats)?.build()?;
+    let iface = connection.object_server().interface::<_, Stats>(OBJECT_PATH)?;
+
+    let sampler = Sampler::builder()
+        .interval(interval)
+        .on_sample(move |info| {
+            *shared.lock().unwrap_or_else(PoisonError::into_inner) = *info;
+            let _ = zbus::block_on(properties_changed(iface.signal_emitter(), properties(info)));
+        })
+        .start();
+
+    Ok(DbusExport { sampler: Some(sampler), connection })
+}
+
+async fn properties_changed(
+    emitter: &SignalEmitter<'_>,
+    changed: HashMap<&str, Value<'_>>,
+) -> zbus::Result<()> {
+    zbus::fdo::Properties::properties_changed(emitter, Stats::name(), changed, Cow::Borrowed(&[])).await
+}
+
+/// Connects to the session bus, requests [`BUS_NAME`] and [`export`]s the object.
+///
+/// # Errors
+///
+/// Returns an error if there is no session bus or the name is already taken.
+pub fn export_session(interval: Duration) -> zbus::Result<DbusExport> {
+    export(Builder::session()?.name(BUS_NAME)?, interval)
+}
+
+/// Same as [`export_session`], but on the system bus. This usually needs a bus policy allowing
+/// you to own [`BUS_NAME`].
+///
+/// # Errors
+///
+/// Returns an error if there is no system bus or the name can't be owned.
+pub fn export_system(interval: Duration) -> zbus::Result<DbusExport> {
+    export(Builder::system()?.name(BUS_NAME)?, interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixStream;
+
+    #[test]
+    fn properties_over_a_p2p_connection() {
+        let (server, client) = UnixStream::pair().unwrap();
+        let guid = zbus::Guid::generate();
+        let server = std::thread::spawn(move || {
+            let builder = Builder::async_io_unix_stream(server).server(guid).unwrap().p2p();
+            export(builder, Duration::from_millis(10)).unwrap()
+        });
+        let client = Builder::async_io_unix_stream(client).p2p().build().unwrap();
+        let export = server.join().unwrap();
+
+        let proxy = zbus::blocking::fdo::PropertiesProxy::builder(&client)
+            .destination(BUS_NAME).unwrap()
+            .path(OBJECT_PATH).unwrap()
+            .build()
+            .unwrap();
+        let interface = zbus::names::InterfaceName::try_from(INTERFACE).unwrap();
+        let total: u64 = loop {
+            let total = proxy.get(interface.clone(), "TotalRam").unwrap().try_into().unwrap();
+            if total > 0 {
+                break total;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        };
+        assert_eq!(total, crate::collect_info().total_ram);
+        let high: u64 = proxy.get(interface.clone(), "TotalHigh").unwrap().try_into().unwrap();
+        assert_eq!(high, crate::collect_info().total_high);
+        drop(export);
+    }
+}
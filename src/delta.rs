@@ -0,0 +1,116 @@
+//! What changed between two snapshots, and how fast, for monitoring loops that poll and want
+//! drift (memory consumed per second, processes spawned, ...) without doing the subtraction
+//! themselves.
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use sysinfo_dot_h::SystemInfo;
+//!
+//! let before = SystemInfo { uptime: Duration::from_secs(100), free_ram: 3 << 20, ..Default::default() };
+//! let after = SystemInfo { uptime: Duration::from_secs(110), free_ram: 2 << 20, ..Default::default() };
+//! let delta = before.diff(&after);
+//! assert_eq!(delta.free_ram, -(1 << 20));
+//! assert_eq!(delta.rate(delta.free_ram), Some(-104857.6)); // bytes per second
+//! ```
+use core::time::Duration;
+
+use crate::{sysinfo, SystemInfo};
+
+/// `after - before` for every field, amounts in bytes. Totals are in there too, they move with
+/// memory hotplug and `swapon`.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct SysinfoDelta {
+    /// The time between the two, `None` if it isn't known: the uptime went backwards (a
+    /// reboot) or didn't move. See [`with_elapsed`](Self::with_elapsed).
+    pub elapsed: Option<Duration>,
+    pub loads: [f64; 3],
+    pub total_ram: i64,
+    pub free_ram: i64,
+    pub shared_ram: i64,
+    pub buffer_ram: i64,
+    pub total_swap: i64,
+    pub free_swap: i64,
+    pub total_high: i64,
+    pub free_high: i64,
+    pub procs: i64,
+}
+
+impl SysinfoDelta {
+    /// Replaces the elapsed time guessed from the uptimes, which are in whole seconds, with one
+    /// measured by the caller (e.g. between the `Instant`s of two samples).
+    #[must_use] pub fn with_elapsed(mut self, elapsed: Duration) -> Self {
+        self.elapsed = known(elapsed);
+        self
+    }
+
+    /// `delta` (one of the fields) per second, `None` without an elapsed time.
+    #[must_use] pub fn rate(&self, delta: i64) -> Option<f64> {
+        per_second(delta as f64, self.elapsed)
+    }
+}
+
+/// `elapsed`, unless it's 0, which no rate can be worked out over. For every delta type's
+/// `with_elapsed`.
+pub(crate) fn known(elapsed: Duration) -> Option<Duration> {
+    Some(elapsed).filter(|elapsed| !elapsed.is_zero())
+}
+
+/// `delta` per second of `elapsed`, `None` without one. For every delta type's `rate`.
+pub(crate) fn per_second(delta: f64, elapsed: Option<Duration>) -> Option<f64> {
+    elapsed.map(|elapsed| delta / elapsed.as_secs_f64())
+}
+
+/// `after - before`, saturated to an `i64`.
+fn change(before: u64, after: u64) -> i64 {
+    let magnitude = i64::try_from(after.abs_diff(before)).unwrap_or(i64::MAX);
+    if after >= before { magnitude } else { -magnitude }
+}
+
+impl SystemInfo {
+    /// What changed from `self` to `later`. The elapsed time is the difference of the uptimes.
+    #[must_use] pub fn diff(&self, later: &SystemInfo) -> SysinfoDelta {
+        SysinfoDelta {
+            elapsed: later.uptime.checked_sub(self.uptime).and_then(known),
+            loads: [0, 1, 2].map(|i| later.loads[i] - self.loads[i]),
+            total_ram: change(self.total_ram, later.total_ram),
+            free_ram: change(self.free_ram, later.free_ram),
+            shared_ram: change(self.shared_ram, later.shared_ram),
+            buffer_ram: change(self.buffer_ram, later.buffer_ram),
+            total_swap: change(self.total_swap, later.total_swap),
+            free_swap: change(self.free_swap, later.free_swap),
+            total_high: change(self.total_high, later.total_high),
+            free_high: change(self.free_high, later.free_high),
+            procs: i64::from(later.procs) - i64::from(self.procs),
+        }
+    }
+}
+
+impl sysinfo {
+    /// [`SystemInfo::diff`] for the raw struct, in bytes whatever the `mem_unit`s.
+    #[must_use] pub fn diff(&self, later: &sysinfo) -> SysinfoDelta {
+        SystemInfo::from(*self).diff(&SystemInfo::from(*later))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deltas_and_rates() {
+        let before = SystemInfo { uptime: Duration::from_secs(100), free_ram: 3 << 20, procs: 10, loads: [1.0, 0.5, 0.25], ..Default::default() };
+        let after = SystemInfo { uptime: Duration::from_secs(110), free_ram: 2 << 20, procs: 15, loads: [1.5, 0.5, 0.25], ..Default::default() };
+        let delta = before.diff(&after);
+        assert_eq!(delta.elapsed, Some(Duration::from_secs(10)));
+        assert_eq!((delta.free_ram, delta.procs, delta.loads), (-(1 << 20), 5, [0.5, 0.0, 0.0]));
+        assert_eq!(delta.rate(delta.procs), Some(0.5));
+        assert_eq!(delta.with_elapsed(Duration::from_millis(500)).rate(delta.procs), Some(10.0));
+
+        let rebooted = after.diff(&before);
+        assert_eq!((rebooted.elapsed, rebooted.rate(rebooted.procs)), (None, None));
+        assert_eq!(SystemInfo { free_ram: u64::MAX, ..before }.diff(&before).free_ram, -i64::MAX);
+
+        let raw = crate::compat::collect();
+        assert_eq!(raw.diff(&raw).free_ram, 0);
+    }
+}
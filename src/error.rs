@@ -0,0 +1,133 @@
+//! Why collecting (or reading, or parsing) failed, for callers that want to tell the causes
+//! apart or chain them with `anyhow`/`thiserror`.
+//!
+//! [`SysinfoError`] converts into a `String`, so `?` works in functions returning
+//! `Result<_, String>` too, and a `String` converts into [`SysinfoError::Other`], for providers
+//! and sampler sources that fail their own way.
+use std::fmt;
+use std::io;
+
+/// What [`try_collect`](crate::try_collect) and every other fallible function of the crate
+/// return when they fail.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SysinfoError {
+    /// `sysinfo(2)` (or `sysconf`, `uname`, `poll`, ...) failed, with the `errno` it set
+    Syscall(io::Error),
+    /// `SYSINFO_DOT_H_FAKE` couldn't be parsed (only with the `fake` feature)
+    Fake(String),
+    /// `getloadavg(3)` returned fewer than the 3 averages asked for, which isn't an `errno`
+    /// failure
+    ShortLoadAvg { got: usize },
+    /// A file under `/proc` or `/sys` (or a recording) couldn't be opened or read
+    Proc { path: String, source: io::Error },
+    /// A line of `file` (or a value, for JSON) didn't parse
+    Parse { file: String, line: String },
+    /// `file` doesn't have `what`, which it needs
+    Missing { file: String, what: String },
+    /// An argument that can't work: a PSI window out of range, a label name that isn't one, a
+    /// threshold that isn't a number, ...
+    Invalid(String),
+    /// Two sources that should agree don't, see `numa::NumaMemory::cross_check`
+    Mismatch(String),
+    /// A caller's provider, source or writer failed, with its message
+    Other(String),
+}
+
+impl SysinfoError {
+    /// The `errno` of the failed call or read, if that's what failed.
+    #[must_use] pub fn errno(&self) -> Option<i32> {
+        match self {
+            SysinfoError::Syscall(e) | SysinfoError::Proc { source: e, .. } => e.raw_os_error(),
+            _ => None,
+        }
+    }
+
+    /// Captures `errno` right after a failed call.
+    pub(crate) fn last_os_error() -> Self {
+        SysinfoError::Syscall(io::Error::last_os_error())
+    }
+
+    /// [`SysinfoError::Proc`], for reading `path` failing with `source`.
+    #[cfg(feature = "procfs")]
+    pub(crate) fn proc(path: impl Into<String>, source: io::Error) -> Self {
+        SysinfoError::Proc { path: path.into(), source }
+    }
+
+    /// [`SysinfoError::Parse`], for `line` of `file`.
+    pub(crate) fn parse(file: impl Into<String>, line: impl Into<String>) -> Self {
+        SysinfoError::Parse { file: file.into(), line: line.into() }
+    }
+
+    /// [`SysinfoError::Missing`], for `what` missing from `file`.
+    pub(crate) fn missing(file: impl Into<String>, what: impl Into<String>) -> Self {
+        SysinfoError::Missing { file: file.into(), what: what.into() }
+    }
+}
+
+impl fmt::Display for SysinfoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SysinfoError::Syscall(e) => write!(f, "Failed to get the sysinfo struct: {e}"),
+            SysinfoError::ShortLoadAvg { got } => write!(f, "getloadavg() returned {got} of the 3 load averages"),
+            SysinfoError::Proc { path, source } => write!(f, "can't read {path}: {source}"),
+            SysinfoError::Parse { file, line } => write!(f, "invalid {file}: {line:?}"),
+            SysinfoError::Missing { file, what } => write!(f, "no {what} in {file}"),
+            SysinfoError::Fake(e) | SysinfoError::Invalid(e) | SysinfoError::Mismatch(e) | SysinfoError::Other(e) => f.write_str(e),
+        }
+    }
+}
+
+impl std::error::Error for SysinfoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SysinfoError::Syscall(e) | SysinfoError::Proc { source: e, .. } => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<SysinfoError> for String {
+    fn from(e: SysinfoError) -> Self {
+        e.to_string()
+    }
+}
+
+impl From<String> for SysinfoError {
+    fn from(e: String) -> Self {
+        SysinfoError::Other(e)
+    }
+}
+
+impl From<&str> for SysinfoError {
+    fn from(e: &str) -> Self {
+        SysinfoError::Other(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_errno() {
+        let e = SysinfoError::Syscall(io::Error::from_raw_os_error(14));
+        assert_eq!(e.errno(), Some(14));
+        assert!(std::error::Error::source(&e).is_some());
+        assert!(String::from(e).starts_with("Failed to get the sysinfo struct: "));
+
+        let e = SysinfoError::Fake("invalid SYSINFO_DOT_H_FAKE: unknown key \"nope\"".to_string());
+        assert_eq!(e.errno(), None);
+        assert_eq!(e.to_string(), "invalid SYSINFO_DOT_H_FAKE: unknown key \"nope\"");
+
+        let e = SysinfoError::Proc { path: "/proc/meminfo".to_string(), source: io::Error::from_raw_os_error(2) };
+        assert_eq!(e.errno(), Some(2));
+        assert!(e.to_string().starts_with("can't read /proc/meminfo: "));
+        assert_eq!(SysinfoError::parse("/proc/vmstat", "pswpin lots").to_string(), "invalid /proc/vmstat: \"pswpin lots\"");
+        assert_eq!(SysinfoError::missing("/proc/meminfo", "MemTotal").to_string(), "no MemTotal in /proc/meminfo");
+        assert!(matches!(SysinfoError::from("boom"), SysinfoError::Other(e) if e == "boom"));
+
+        let e = SysinfoError::ShortLoadAvg { got: 1 };
+        assert_eq!((e.errno(), e.to_string().as_str()), (None, "getloadavg() returned 1 of the 3 load averages"));
+    }
+}
@@ -0,0 +1,198 @@
+//! Synthetic values out of the environment, for integration tests and demos of programs using
+//! this crate without touching their code.
+//!
+//! With the `fake` feature on, [`try_collect`](crate::try_collect), [`collect`](crate::collect),
+//! every [backend](crate::backend) (procfs included) and everything built on them (the sampler,
+//! the exporters...) replace the fields listed in [`VAR`] after asking the kernel:
+//!
+//! ```sh
+//! SYSINFO_DOT_H_FAKE=totalram=8G,freeram=512M,load1=12.5 ./my-app
+//! ```
+//!
+//! The keys are `struct sysinfo`'s: `uptime` (seconds), `load1`, `load5`, `load15`, `procs` and
+//! the amounts `totalram`, `freeram`, `sharedram`, `bufferram`, `totalswap`, `freeswap`,
+//! `totalhigh` and `freehigh`, in bytes or with a `K`, `M`, `G` or `T` suffix (powers of 1024).
+//! Fields that aren't listed keep their real value. A malformed variable makes `try_collect`
+//! fail, so a typo doesn't go unnoticed as real values.
+use std::os::raw::{c_long, c_ulong};
+
+use crate::sysinfo;
+#[cfg(feature = "procfs")] use crate::SystemInfo;
+
+/// The environment variable holding the fake values.
+pub const VAR: &str = "SYSINFO_DOT_H_FAKE";
+
+/// Applies [`VAR`] to `info`, if it's set.
+pub(crate) fn apply(info: &mut sysinfo) -> Result<(), String> {
+    for fake in from_env()? {
+        fake.apply(info)?;
+    }
+    Ok(())
+}
+
+/// [`apply`] for the backends that don't go through `struct sysinfo` (procfs).
+#[cfg(feature = "procfs")]
+pub(crate) fn apply_info(info: &mut SystemInfo) -> Result<(), String> {
+    for fake in from_env()? {
+        fake.apply_info(info);
+    }
+    Ok(())
+}
+
+fn from_env() -> Result<Vec<Fake>, String> {
+    match std::env::var(VAR) {
+        Ok(fakes) => parse(&fakes).map_err(|e| format!("invalid {VAR}: {e}")),
+        Err(std::env::VarError::NotPresent) => Ok(Vec::new()),
+        Err(std::env::VarError::NotUnicode(_)) => Err(format!("invalid {VAR}: not UTF-8")),
+    }
+}
+
+/// One `key=value`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Fake {
+    Uptime(u64),
+    Load(usize, f64),
+    Procs(u16),
+    Amount(Amount, u64),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Amount {
+    TotalRam,
+    FreeRam,
+    SharedRam,
+    BufferRam,
+    TotalSwap,
+    FreeSwap,
+    TotalHigh,
+    FreeHigh,
+}
+
+fn parse(fakes: &str) -> Result<Vec<Fake>, String> {
+    fakes.split(',').map(str::trim).filter(|fake| !fake.is_empty()).map(|fake| {
+        let (key, value) = fake.split_once('=').ok_or_else(|| format!("{fake:?} isn't key=value"))?;
+        let (key, value) = (key.trim(), value.trim());
+        let invalid = || format!("invalid value for {key}: {value:?}");
+        let load = |index| Ok(Fake::Load(index, value.parse().ok().filter(|load: &f64| *load >= 0.0).ok_or_else(invalid)?));
+        let amount = |amount| Ok(Fake::Amount(amount, size(value).ok_or_else(invalid)?));
+        match key {
+            // struct sysinfo's uptime is a signed long
+            "uptime" => Ok(Fake::Uptime(value.parse().ok().filter(|secs| c_long::try_from(*secs).is_ok()).ok_or_else(invalid)?)),
+            "load1" => load(0),
+            "load5" => load(1),
+            "load15" => load(2),
+            "procs" => Ok(Fake::Procs(value.parse().map_err(|_| invalid())?)),
+            "totalram" => amount(Amount::TotalRam),
+            "freeram" => amount(Amount::FreeRam),
+            "sharedram" => amount(Amount::SharedRam),
+            "bufferram" => amount(Amount::BufferRam),
+            "totalswap" => amount(Amount::TotalSwap),
+            "freeswap" => amount(Amount::FreeSwap),
+            "totalhigh" => amount(Amount::TotalHigh),
+            "freehigh" => amount(Amount::FreeHigh),
+            _ => Err(format!("unknown key {key:?}")),
+        }
+    }).collect()
+}
+
+impl Fake {
+    fn apply(self, info: &mut sysinfo) -> Result<(), String> {
+        match self {
+            Fake::Uptime(secs) => info.uptime = secs as c_long,
+            // the kernel's fixed point, see `SystemInfo::loads`
+            Fake::Load(index, load) => info.loads[index] = (load * 65536.0).round() as c_ulong,
+            Fake::Procs(procs) => info.procs = procs,
+            Fake::Amount(amount, bytes) => {
+                let slot = match amount {
+                    Amount::TotalRam => &mut info.totalram,
+                    Amount::FreeRam => &mut info.freeram,
+                    Amount::SharedRam => &mut info.sharedram,
+                    Amount::BufferRam => &mut info.bufferram,
+                    Amount::TotalSwap => &mut info.totalswap,
+                    Amount::FreeSwap => &mut info.freeswap,
+                    Amount::TotalHigh => &mut info.totalhigh,
+                    Amount::FreeHigh => &mut info.freehigh,
+                };
+                // the amounts are in units of mem_unit bytes
+                *slot = c_ulong::try_from(bytes / u64::from(info.mem_unit.max(1))).map_err(|_| format!("{bytes} bytes don't fit in struct sysinfo"))?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "procfs")]
+    fn apply_info(self, info: &mut SystemInfo) {
+        match self {
+            Fake::Uptime(secs) => info.uptime = std::time::Duration::from_secs(secs),
+            Fake::Load(index, load) => info.loads[index] = load,
+            Fake::Procs(procs) => info.procs = procs,
+            Fake::Amount(amount, bytes) => *match amount {
+                Amount::TotalRam => &mut info.total_ram,
+                Amount::FreeRam => &mut info.free_ram,
+                Amount::SharedRam => &mut info.shared_ram,
+                Amount::BufferRam => &mut info.buffer_ram,
+                Amount::TotalSwap => &mut info.total_swap,
+                Amount::FreeSwap => &mut info.free_swap,
+                Amount::TotalHigh => &mut info.total_high,
+                Amount::FreeHigh => &mut info.free_high,
+            } = bytes,
+        }
+    }
+}
+
+/// `512M`, `8G`, `4096`. Also takes `MiB`-style and lowercase suffixes.
+fn size(value: &str) -> Option<u64> {
+    let digits = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, suffix) = value.split_at(digits);
+    let shift = match suffix.trim().to_ascii_uppercase().trim_end_matches("IB").trim_end_matches('B') {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return None,
+    };
+    number.parse::<u64>().ok()?.checked_mul(1 << shift)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SystemInfo;
+
+    fn apply_str(info: &mut sysinfo, fakes: &str) -> Result<(), String> {
+        parse(fakes)?.into_iter().try_for_each(|fake| fake.apply(info))
+    }
+
+    #[test]
+    fn overrides_fields() {
+        let mut raw: sysinfo = unsafe { std::mem::zeroed() };
+        raw.uptime = 10;
+        raw.procs = 5;
+        raw.mem_unit = 4;
+        apply_str(&mut raw, "totalram=8G, freeram=512M,load1=1.5,procs=300,").unwrap();
+        let info = SystemInfo::from(raw);
+        assert_eq!((info.total_ram, info.free_ram, info.procs), (8 << 30, 512 << 20, 300));
+        assert_eq!(info.loads[0], 1.5);
+        assert_eq!(info.uptime.as_secs(), 10);
+
+        assert_eq!(size("4096"), Some(4096));
+        assert_eq!(size("2KiB"), Some(2048));
+        assert_eq!(size("2X"), None);
+        assert!(apply_str(&mut raw, "totalram").is_err());
+        assert!(apply_str(&mut raw, "swap=1G").is_err());
+        assert!(apply_str(&mut raw, "load1=-1").is_err());
+        assert!(apply_str(&mut raw, "uptime=-1").is_err());
+    }
+
+    #[cfg(feature = "procfs")]
+    #[test]
+    fn overrides_system_info() {
+        use std::time::Duration;
+        let mut info = SystemInfo { uptime: Duration::from_secs(10), free_ram: 1, ..Default::default() };
+        for fake in parse("totalram=8G,load15=0.25,uptime=60").unwrap() {
+            fake.apply_info(&mut info);
+        }
+        assert_eq!(info, SystemInfo { uptime: Duration::from_secs(60), loads: [0.0, 0.0, 0.25], total_ram: 8 << 30, free_ram: 1, ..Default::default() });
+    }
+}
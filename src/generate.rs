@@ -0,0 +1,197 @@
+//! Random but consistent snapshots for property tests, with the `arbitrary` and `proptest`
+//! features.
+//!
+//! Both go through [`Draw`]: plain numbers anything can generate, turned into a snapshot that
+//! keeps the kernel's invariants. Every amount is a share of what's still left, so free memory
+//! never exceeds the total, buffers and shared memory fit in what isn't free, free swap fits in
+//! the swap, and `/proc/meminfo`'s available memory sits between free memory and the total.
+use std::time::Duration;
+
+use crate::meminfo::MemInfo;
+use crate::SystemInfo;
+
+/// The largest machine generated, 16 TiB.
+const MAX_RAM: u64 = 16 << 40;
+
+/// A share of something, out of `u16::MAX`.
+fn share(of: u64, share: u16) -> u64 {
+    (u128::from(of) * u128::from(share) / u128::from(u16::MAX)) as u64
+}
+
+#[derive(Debug)]
+struct Draw {
+    uptime: u32,
+    /// In hundredths
+    loads: [u16; 3],
+    total_ram: u64,
+    total_swap: u64,
+    shares: [u16; 6],
+    procs: u16,
+    available: bool,
+}
+
+impl Draw {
+    fn system_info(&self) -> SystemInfo {
+        let [free, buffer, shared, free_swap, total_high, free_high] = self.shares;
+        let total_ram = self.total_ram % MAX_RAM;
+        let total_swap = self.total_swap % MAX_RAM;
+        let free_ram = share(total_ram, free);
+        let buffer_ram = share(total_ram - free_ram, buffer);
+        let total_high = share(total_ram, total_high);
+        SystemInfo {
+            uptime: Duration::from_secs(u64::from(self.uptime)),
+            loads: self.loads.map(|load| f64::from(load) / 100.0),
+            total_ram,
+            free_ram,
+            buffer_ram,
+            shared_ram: share(total_ram - free_ram - buffer_ram, shared),
+            total_swap,
+            free_swap: share(total_swap, free_swap),
+            total_high,
+            free_high: share(total_high, free_high),
+            // there's always at least the one asking
+            procs: self.procs.max(1),
+        }
+    }
+
+    fn meminfo(&self) -> MemInfo {
+        let [free, available, buffers, cached, shmem, s_reclaimable] = self.shares;
+        let info = self.system_info();
+        let total = info.total_ram;
+        let free = share(total, free);
+        let buffers = share(total - free, buffers);
+        let cached = share(total - free - buffers, cached);
+        let shmem = share(cached, shmem);
+        // the file LRUs hold the buffers and the page cache, except shared memory
+        let file = buffers + cached - shmem;
+        MemInfo {
+            total,
+            free,
+            available: self.available.then(|| free + share(total - free, available)),
+            buffers,
+            cached,
+            // tmpfs and shared memory are part of the page cache
+            shmem,
+            s_reclaimable: share(total - free - buffers - cached, s_reclaimable),
+            active_file: file / 2,
+            inactive_file: file - file / 2,
+            swap_total: info.total_swap,
+            swap_free: info.free_swap,
+            high_total: info.total_high,
+            high_free: info.free_high,
+            // the default overcommit_ratio of 50
+            commit_limit: total / 2 + info.total_swap,
+            committed_as: share(total + info.total_swap, available),
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impls {
+    use arbitrary::{Arbitrary, Result, Unstructured};
+
+    use super::*;
+
+    impl<'a> Arbitrary<'a> for Draw {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(Self {
+                uptime: u.arbitrary()?,
+                loads: u.arbitrary()?,
+                total_ram: u.arbitrary()?,
+                total_swap: u.arbitrary()?,
+                shares: u.arbitrary()?,
+                procs: u.arbitrary()?,
+                available: u.arbitrary()?,
+            })
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for SystemInfo {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(Draw::arbitrary(u)?.system_info())
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for MemInfo {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(Draw::arbitrary(u)?.meminfo())
+        }
+    }
+}
+
+#[cfg(feature = "proptest")]
+mod proptest_impls {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn draw() -> impl Strategy<Value = Draw> {
+        (any::<u32>(), any::<[u16; 3]>(), any::<u64>(), any::<u64>(), any::<[u16; 6]>(), any::<u16>(), any::<bool>()).prop_map(
+            |(uptime, loads, total_ram, total_swap, shares, procs, available)| Draw { uptime, loads, total_ram, total_swap, shares, procs, available },
+        )
+    }
+
+    impl Arbitrary for SystemInfo {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with((): ()) -> Self::Strategy {
+            draw().prop_map(|draw| draw.system_info()).boxed()
+        }
+    }
+
+    impl Arbitrary for MemInfo {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with((): ()) -> Self::Strategy {
+            draw().prop_map(|draw| draw.meminfo()).boxed()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_consistent(info: &SystemInfo, meminfo: &MemInfo) {
+        assert!(info.total_ram <= MAX_RAM);
+        assert!(info.free_ram + info.buffer_ram + info.shared_ram <= info.total_ram);
+        assert!(info.free_swap <= info.total_swap);
+        assert!(info.free_high <= info.total_high && info.total_high <= info.total_ram);
+        assert!(info.loads.iter().all(|load| load.is_finite() && *load >= 0.0));
+        assert!(info.procs > 0);
+        assert!(meminfo.available.is_none_or(|available| meminfo.free <= available && available <= meminfo.total));
+        assert!(meminfo.free + meminfo.buffers + meminfo.cached + meminfo.s_reclaimable <= meminfo.total);
+        assert!(meminfo.shmem <= meminfo.cached);
+    }
+
+    #[test]
+    fn extremes_are_consistent() {
+        for share in [0, 1, u16::MAX / 2, u16::MAX] {
+            for total in [0, 1, u64::MAX] {
+                let draw = Draw { uptime: u32::MAX, loads: [share; 3], total_ram: total, total_swap: total, shares: [share; 6], procs: 0, available: true };
+                assert_consistent(&draw.system_info(), &draw.meminfo());
+            }
+        }
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary() {
+        use arbitrary::{Arbitrary, Unstructured};
+        let bytes: Vec<u8> = (0..4096u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+        let mut u = Unstructured::new(&bytes);
+        for _ in 0..50 {
+            assert_consistent(&SystemInfo::arbitrary(&mut u).unwrap(), &MemInfo::arbitrary(&mut u).unwrap());
+        }
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn proptest(info: SystemInfo, meminfo: MemInfo) {
+            assert_consistent(&info, &meminfo);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! A tonic based gRPC service exposing snapshots, see `proto/sysinfo_dot_h.proto` for the schema.
+//!
+//! ```rust,no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use sysinfo_dot_h::grpc;
+//!
+//! tonic::transport::Server::builder()
+//!     .add_service(grpc::server())
+//!     .serve("[::1]:50051".parse()?)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::pin::Pin;
+use std::time::Duration;
+
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+
+use crate::{try_collect_info, SystemInfo};
+
+/// The generated protobuf messages, server and client.
+#[allow(missing_docs, clippy::all)]
+pub mod proto {
+    tonic::include_proto!("sysinfo_dot_h.v1");
+}
+
+use proto::sysinfo_server::{Sysinfo, SysinfoServer};
+
+/// `StreamSnapshots` requests asking for a shorter interval than this get this instead.
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The `sysinfo_dot_h.v1.Sysinfo` service.
+#[derive(Debug, Clone)]
+pub struct SysinfoService {
+    min_interval: Duration,
+}
+
+impl Default for SysinfoService {
+    fn default() -> Self {
+        Self { min_interval: DEFAULT_MIN_INTERVAL }
+    }
+}
+
+impl SysinfoService {
+    /// Sets the shortest interval `StreamSnapshots` clients may ask for, so a misbehaving client
+    /// can't make the server spin on the syscall.
+    #[must_use] pub fn min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+}
+
+/// Shorthand for wrapping a default [`SysinfoService`] into a tonic server.
+#[must_use] pub fn server() -> SysinfoServer<SysinfoService> {
+    SysinfoServer::new(SysinfoService::default())
+}
+
+impl From<SystemInfo> for proto::Snapshot {
+    fn from(info: SystemInfo) -> Self {
+        Self {
+            uptime: info.uptime.as_secs(),
+            load1: info.loads[0],
+            load5: info.loads[1],
+            load15: info.loads[2],
+            total_ram: info.total_ram,
+            free_ram: info.free_ram,
+            shared_ram: info.shared_ram,
+            buffer_ram: info.buffer_ram,
+            total_swap: info.total_swap,
+            free_swap: info.free_swap,
+            total_high: info.total_high,
+            free_high: info.free_high,
+            procs: u32::from(info.procs),
+        }
+    }
+}
+
+fn snapshot() -> Result<proto::Snapshot, Status> {
+    try_collect_info().map(proto::Snapshot::from).map_err(Status::internal)
+}
+
+#[tonic::async_trait]
+impl Sysinfo for SysinfoService {
+    async fn get_snapshot(
+        &self,
+        _request: Request<proto::GetSnapshotRequest>,
+    ) -> Result<Response<proto::Snapshot>, Status> {
+        snapshot().map(Response::new)
+    }
+
+    type StreamSnapshotsStream = Pin<Box<dyn Stream<Item = Result<proto::Snapshot, Status>> + Send>>;
+
+    async fn stream_snapshots(
+        &self,
+        request: Request<proto::StreamSnapshotsRequest>,
+    ) -> Result<Response<Self::StreamSnapshotsStream>, Status> {
+        let interval = Duration::from_millis(request.into_inner().interval_ms).max(self.min_interval);
+        let ticks = tokio_stream::wrappers::IntervalStream::new(tokio::time::interval(interval));
+        Ok(Response::new(Box::pin(ticks.map(|_| snapshot()))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap().block_on(future)
+    }
+
+    #[test]
+    fn get_snapshot() {
+        let service = SysinfoService::default();
+        let response = block_on(service.get_snapshot(Request::new(proto::GetSnapshotRequest {}))).unwrap();
+        assert!(response.into_inner().total_ram > 0);
+    }
+
+    #[test]
+    fn stream_snapshots() {
+        let service = SysinfoService::default().min_interval(Duration::from_millis(1));
+        let snapshots = block_on(async {
+            let request = Request::new(proto::StreamSnapshotsRequest { interval_ms: 0 });
+            let stream = service.stream_snapshots(request).await.unwrap().into_inner();
+            stream.take(3).collect::<Vec<_>>().await
+        });
+        assert_eq!(snapshots.len(), 3);
+        assert!(snapshots.into_iter().all(|snapshot| snapshot.is_ok()));
+    }
+}
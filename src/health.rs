@@ -0,0 +1,167 @@
+//! Readiness probes driven by [`check`](crate::check) rules, so a service can report "not
+//! ready" while the host is under memory pressure.
+//!
+//! With the `tower` feature, [`Readiness`] is also a `tower::Service` answering any HTTP request
+//! with `200 OK` or `503 Service Unavailable`, ready to be mounted on a `/readyz` route.
+//!
+//! ```rust
+//! use sysinfo_dot_h::check::{Check, Metric};
+//! use sysinfo_dot_h::health::Readiness;
+//!
+//! let rules = Check::new("READINESS").rule(Metric::FreeRamPercent, "", "2:").unwrap();
+//! let readiness = Readiness::new(rules);
+//!
+//! if !readiness.probe().is_ready() {
+//!     // shed load
+//! }
+//! ```
+use std::fmt;
+use std::sync::Arc;
+
+use crate::backend::Backend;
+use crate::check::{Check, Report, Status};
+use crate::provider::SysinfoProvider;
+
+/// The result of a [`Readiness::probe`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Health {
+    /// No rule is at or above the failure status
+    Ready(Report),
+    /// Too many resources in use, or the snapshot couldn't be collected
+    NotReady(Report),
+}
+
+impl Health {
+    /// Whether this is [`Health::Ready`].
+    #[must_use] pub fn is_ready(&self) -> bool {
+        matches!(self, Health::Ready(_))
+    }
+
+    /// The report the decision was based on.
+    #[must_use] pub fn report(&self) -> &Report {
+        match self {
+            Health::Ready(report) | Health::NotReady(report) => report,
+        }
+    }
+}
+
+/// A readiness probe, taking its snapshots from a [`SysinfoProvider`]. Cheap to clone.
+pub struct Readiness<P = Backend> {
+    check: Arc<Check>,
+    fail_at: Status,
+    provider: Arc<P>,
+}
+
+impl<P> Clone for Readiness<P> {
+    fn clone(&self) -> Self {
+        Self { check: Arc::clone(&self.check), fail_at: self.fail_at, provider: Arc::clone(&self.provider) }
+    }
+}
+
+impl<P> fmt::Debug for Readiness<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Readiness").field("check", &self.check).field("fail_at", &self.fail_at).finish_non_exhaustive()
+    }
+}
+
+impl Readiness {
+    /// A probe that fails once any rule of `check` goes critical.
+    #[must_use] pub fn new(check: Check) -> Self {
+        Self::with_provider(check, Backend::Libc)
+    }
+}
+
+impl<P: SysinfoProvider> Readiness<P> {
+    /// Like [`new`](Readiness::new), with the snapshots coming from `provider`.
+    #[must_use] pub fn with_provider(check: Check, provider: P) -> Self {
+        Self { check: Arc::new(check), fail_at: Status::Critical, provider: Arc::new(provider) }
+    }
+
+    /// Fails on warnings too, not just on critical rules.
+    #[must_use] pub fn strict(mut self) -> Self {
+        self.fail_at = Status::Warning;
+        self
+    }
+
+    /// Collects a snapshot and evaluates the rules.
+    #[must_use] pub fn probe(&self) -> Health {
+        let report = self.check.run(&*self.provider);
+        if report.status() >= self.fail_at {
+            Health::NotReady(report)
+        } else {
+            Health::Ready(report)
+        }
+    }
+}
+
+#[cfg(feature = "tower")]
+mod service {
+    use std::convert::Infallible;
+    use std::future::{ready, Ready};
+    use std::task::{Context, Poll};
+
+    use super::{Health, Readiness};
+    use crate::provider::SysinfoProvider;
+
+    /// Answers every request with the probe's [`Report`](crate::check::Report) as a
+    /// `text/plain` body: `200 OK` when ready, `503 Service Unavailable` otherwise.
+    impl<B, P: SysinfoProvider> tower_service::Service<http::Request<B>> for Readiness<P> {
+        type Response = http::Response<String>;
+        type Error = Infallible;
+        type Future = Ready<Result<Self::Response, Infallible>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _request: http::Request<B>) -> Self::Future {
+            let health = self.probe();
+            let status = match health {
+                Health::Ready(_) => http::StatusCode::OK,
+                Health::NotReady(_) => http::StatusCode::SERVICE_UNAVAILABLE,
+            };
+            let mut response = http::Response::new(format!("{}\n", health.report()));
+            *response.status_mut() = status;
+            response.headers_mut().insert(http::header::CONTENT_TYPE, http::HeaderValue::from_static("text/plain"));
+            ready(Ok(response))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::Metric;
+
+    #[test]
+    fn probes() {
+        let never = Check::new("READINESS").rule(Metric::Procs, "", "@100000:").unwrap();
+        assert!(Readiness::new(never).probe().is_ready());
+
+        let always = Check::new("READINESS").rule(Metric::Procs, "@0:", "").unwrap();
+        assert!(Readiness::new(always.clone()).probe().is_ready());
+        assert!(!Readiness::new(always).strict().probe().is_ready());
+
+        let low = Check::new("READINESS").rule(Metric::FreeRamPercent, "", "5:").unwrap();
+        let fake = crate::provider::from_fn(|| Ok(crate::SystemInfo { total_ram: 100, free_ram: 1, ..Default::default() }));
+        assert!(!Readiness::with_provider(low.clone(), fake).probe().is_ready());
+        let broken = crate::provider::from_fn(|| Err("no".into()));
+        assert_eq!(Readiness::with_provider(low, broken).probe().report().status(), Status::Unknown);
+    }
+
+    #[cfg(feature = "tower")]
+    #[test]
+    fn tower_service() {
+        use std::future::Future;
+        use tower_service::Service;
+
+        let always = Check::new("READINESS").rule(Metric::Procs, "", "@0:").unwrap();
+        let mut future = Readiness::new(always).call(http::Request::new(()));
+        let waker = std::task::Waker::noop();
+        let std::task::Poll::Ready(Ok(response)) = std::pin::Pin::new(&mut future).poll(&mut std::task::Context::from_waker(waker)) else {
+            panic!("the readiness service is synchronous");
+        };
+        assert_eq!(response.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.body().starts_with("READINESS CRITICAL - procs is "));
+    }
+}
@@ -0,0 +1,443 @@
+//! A normalized, easier to consume view of the sysinfo struct.
+//!
+//! The raw struct reports memory in multiples of `mem_unit` and load averages as fixed-point
+//! numbers. [`SystemInfo`] does that math once so the values can be shipped around (over a
+//! socket, into a metrics system, ...) without every consumer having to remember it.
+use core::ffi::{c_long, c_ulong};
+use core::time::Duration;
+
+use crate::sysinfo;
+#[cfg(feature = "std")] use crate::SysinfoError;
+
+/// The raw load averages are fixed-point numbers scaled by `1 << SI_LOAD_SHIFT` (16), see
+/// `include/uapi/linux/sysinfo.h`.
+pub(crate) const SI_LOAD_SCALE: f64 = (1u32 << 16) as f64;
+
+/// The sysinfo struct, normalized.
+///
+/// Memory fields are in bytes (already multiplied by `mem_unit`), load averages are plain `f64`s
+/// and the uptime is a [`Duration`].
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SystemInfo {
+    /// Time since boot (in whole seconds when serialized)
+    #[cfg_attr(feature = "schemars", schemars(with = "u64"))]
+    #[cfg_attr(feature = "serde", serde(with = "whole_seconds"))]
+    pub uptime: Duration,
+    /// 1, 5, and 15 minute load averages
+    pub loads: [f64; 3],
+    /// Total usable main memory size in bytes
+    pub total_ram: u64,
+    /// Free memory in bytes. (note: freeram != available memory)
+    pub free_ram: u64,
+    /// Amount of shared memory in bytes
+    pub shared_ram: u64,
+    /// Memory used by buffers in bytes
+    pub buffer_ram: u64,
+    /// Total swap space size in bytes
+    pub total_swap: u64,
+    /// Swap space still available in bytes
+    pub free_swap: u64,
+    /// Total high memory size in bytes
+    pub total_high: u64,
+    /// Available high memory size in bytes
+    pub free_high: u64,
+    /// Number of current processes
+    pub procs: u16,
+}
+
+/// The byte accessors of the raw struct, two per field: `<field>_bytes` saturates,
+/// `checked_<field>_bytes` says when the product doesn't fit.
+macro_rules! byte_accessors {
+    ($ty:ident { $($field:ident: $bytes:ident, $checked:ident, $what:literal;)* }) => {
+        impl $ty {
+            $(
+                #[doc = concat!("`", stringify!($field), "` in bytes: ", $what, ", times `mem_unit`, saturated to `u64::MAX`.")]
+                #[must_use] pub fn $bytes(&self) -> u64 {
+                    self.$checked().unwrap_or(u64::MAX)
+                }
+
+                #[doc = concat!("[`", stringify!($bytes), "`](Self::", stringify!($bytes), "), `None` if it doesn't fit a `u64`.")]
+                #[must_use] pub fn $checked(&self) -> Option<u64> {
+                    #[allow(clippy::unnecessary_cast)] // c_ulong is only 32 bits on 32-bit targets
+                    (self.$field as u64).checked_mul(self.unit())
+                }
+            )*
+        }
+    };
+}
+
+// for `Sysinfo` too
+pub(crate) use byte_accessors;
+
+byte_accessors! {
+    sysinfo {
+        totalram: total_ram_bytes, checked_total_ram_bytes, "total usable main memory";
+        freeram: free_ram_bytes, checked_free_ram_bytes, "free memory";
+        sharedram: shared_ram_bytes, checked_shared_ram_bytes, "shared memory";
+        bufferram: buffer_ram_bytes, checked_buffer_ram_bytes, "memory used by buffers";
+        totalswap: total_swap_bytes, checked_total_swap_bytes, "total swap space";
+        freeswap: free_swap_bytes, checked_free_swap_bytes, "free swap space";
+        totalhigh: total_high_bytes, checked_total_high_bytes, "total high memory";
+        freehigh: free_high_bytes, checked_free_high_bytes, "free high memory";
+    }
+}
+
+impl sysinfo {
+    /// `mem_unit`, with the 0 of kernels before 2.3.23 meaning bytes.
+    fn unit(&self) -> u64 {
+        u64::from(self.mem_unit.max(1))
+    }
+}
+
+impl From<sysinfo> for SystemInfo {
+    fn from(raw: sysinfo) -> Self {
+        Self {
+            uptime: Duration::from_secs(raw.uptime.max(0) as u64),
+            loads: raw.loads().into(),
+            total_ram: raw.total_ram_bytes(),
+            free_ram: raw.free_ram_bytes(),
+            shared_ram: raw.shared_ram_bytes(),
+            buffer_ram: raw.buffer_ram_bytes(),
+            total_swap: raw.total_swap_bytes(),
+            free_swap: raw.free_swap_bytes(),
+            total_high: raw.total_high_bytes(),
+            free_high: raw.free_high_bytes(),
+            procs: raw.procs,
+        }
+    }
+}
+
+/// Serializes a [`Duration`] as whole seconds, like [`SystemInfo::to_json`].
+#[cfg(feature = "serde")]
+pub(crate) mod whole_seconds {
+    use std::time::Duration;
+
+    pub(crate) fn serialize<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_secs())
+    }
+
+    pub(crate) fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        <u64 as serde::Deserialize>::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
+
+/// The raw struct is serialized as its [`SystemInfo`], in bytes whatever `mem_unit` was and
+/// without the padding, so the snapshot reads the same on 32 and 64-bit hosts. Deserializing
+/// goes through [`From<SystemInfo>`](#impl-From<SystemInfo>-for-sysinfo), which picks a
+/// `mem_unit` that fits the host.
+#[cfg(feature = "serde")]
+impl serde::Serialize for sysinfo {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SystemInfo::from(*self).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for sysinfo {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SystemInfo::deserialize(deserializer).map(sysinfo::from)
+    }
+}
+
+/// The other way around, for handing a snapshot to code that wants the raw struct.
+///
+/// `mem_unit` is 1 unless the amounts don't fit a `c_ulong` (on 32-bit targets), then it's the
+/// smallest power of two that makes them fit, like the kernel does. Amounts that aren't a
+/// multiple of it are rounded down.
+impl From<SystemInfo> for sysinfo {
+    #[allow(clippy::unnecessary_cast)] // c_ulong is only 32 bits on 32-bit targets
+    fn from(info: SystemInfo) -> Self {
+        let largest = [info.total_ram, info.total_swap, info.total_high].into_iter().max().unwrap_or(0);
+        let mut unit: u64 = 1;
+        while largest / unit > c_ulong::MAX as u64 && unit < 1 << 31 {
+            unit <<= 1;
+        }
+        let amount = |bytes: u64| (bytes / unit).min(c_ulong::MAX as u64) as c_ulong;
+
+        let mut raw: sysinfo = unsafe { core::mem::zeroed() };
+        raw.uptime = c_long::try_from(info.uptime.as_secs()).unwrap_or(c_long::MAX);
+        // rounded, `as` saturates (and makes NaN 0)
+        raw.loads = info.loads.map(|load| ((load * SI_LOAD_SCALE).max(0.0) + 0.5) as c_ulong);
+        raw.totalram = amount(info.total_ram);
+        raw.freeram = amount(info.free_ram);
+        raw.sharedram = amount(info.shared_ram);
+        raw.bufferram = amount(info.buffer_ram);
+        raw.totalswap = amount(info.total_swap);
+        raw.freeswap = amount(info.free_swap);
+        raw.totalhigh = amount(info.total_high);
+        raw.freehigh = amount(info.free_high);
+        raw.procs = info.procs;
+        raw.mem_unit = unit as u32;
+        raw
+    }
+}
+
+#[cfg(feature = "std")]
+impl sysinfo {
+    /// [`SystemInfo::to_json`], in bytes whatever the `mem_unit`.
+    #[must_use] pub fn to_json(&self) -> String {
+        SystemInfo::from(*self).to_json()
+    }
+}
+
+#[cfg(feature = "std")]
+impl SystemInfo {
+    /// When the machine booted, the clock now minus the uptime. Only as precise as the uptime,
+    /// a whole second, see [`boot_time`](crate::boot_time) for a steadier one.
+    ///
+    /// ```rust
+    /// let info = sysinfo_dot_h::collect_info();
+    /// assert!(info.boot_time() < std::time::SystemTime::now());
+    /// ```
+    #[must_use] pub fn boot_time(&self) -> std::time::SystemTime {
+        let now = std::time::SystemTime::now();
+        now.checked_sub(self.uptime).unwrap_or(now)
+    }
+
+    /// Serializes the snapshot into a single line of JSON, without serde.
+    ///
+    /// The uptime is given in whole seconds and every memory field is in bytes:
+    ///
+    /// ```text
+    /// {"uptime":1234,"loads":[0.5,0.25,0.125],"total_ram":16654577664,...,"procs":1024}
+    /// ```
+    ///
+    /// The schema is stable: new members may be added at the end, existing ones keep their name,
+    /// order and meaning. Every member is always there:
+    ///
+    /// | member | type | |
+    /// |---|---|---|
+    /// | `uptime` | integer | seconds since boot |
+    /// | `loads` | 3 numbers | 1, 5 and 15 minute load averages |
+    /// | `total_ram`, `free_ram`, `shared_ram`, `buffer_ram` | integer | bytes |
+    /// | `total_swap`, `free_swap` | integer | bytes |
+    /// | `total_high`, `free_high` | integer | bytes, 0 on 64-bit |
+    /// | `procs` | integer | current processes (threads, really) |
+    ///
+    /// With the `schemars` feature, `snapshot_schema()` has it as a JSON Schema.
+    #[must_use] pub fn to_json(&self) -> String {
+        format!(
+            concat!(
+                "{{\"uptime\":{},\"loads\":[{},{},{}],",
+                "\"total_ram\":{},\"free_ram\":{},\"shared_ram\":{},\"buffer_ram\":{},",
+                "\"total_swap\":{},\"free_swap\":{},\"total_high\":{},\"free_high\":{},",
+                "\"procs\":{}}}"
+            ),
+            self.uptime.as_secs(),
+            self.loads[0], self.loads[1], self.loads[2],
+            self.total_ram, self.free_ram, self.shared_ram, self.buffer_ram,
+            self.total_swap, self.free_swap, self.total_high, self.free_high,
+            self.procs,
+        )
+    }
+
+    /// Parses what [`to_json`](Self::to_json) wrote, pretty printed or not, so saved snapshots
+    /// can be loaded back in.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SysinfoError::Missing`] if a field is missing and [`SysinfoError::Parse`] if
+    /// it isn't a number.
+    pub fn from_json(json: &str) -> Result<Self, SysinfoError> {
+        let invalid = |name: &str, value: &str| SysinfoError::parse(format!("snapshot {name}"), value);
+        let number = |name: &str| -> Result<u64, SysinfoError> {
+            let value = json_value(json, name)?;
+            value.parse().map_err(|_| invalid(name, value))
+        };
+        let text = json_value(json, "loads")?;
+        let loads: Vec<f64> = text
+            .strip_prefix('[')
+            .and_then(|loads| loads.strip_suffix(']'))
+            .map(|loads| loads.split(',').map(|load| load.trim().parse()).collect::<Result<_, _>>())
+            .and_then(Result::ok)
+            .ok_or_else(|| invalid("loads", text))?;
+        let loads: [f64; 3] = loads.try_into().map_err(|_| invalid("loads", text))?;
+        let procs = number("procs")?;
+
+        Ok(Self {
+            uptime: Duration::from_secs(number("uptime")?),
+            loads,
+            total_ram: number("total_ram")?,
+            free_ram: number("free_ram")?,
+            shared_ram: number("shared_ram")?,
+            buffer_ram: number("buffer_ram")?,
+            total_swap: number("total_swap")?,
+            free_swap: number("free_swap")?,
+            total_high: number("total_high")?,
+            free_high: number("free_high")?,
+            procs: procs.try_into().map_err(|_| invalid("procs", &procs.to_string()))?,
+        })
+    }
+}
+
+/// The raw text of the member called `name`: a number, or an array of numbers. Nothing else
+/// appears in a snapshot.
+#[cfg(feature = "std")]
+fn json_value<'a>(json: &'a str, name: &str) -> Result<&'a str, SysinfoError> {
+    let missing = || SysinfoError::missing("the snapshot", format!("{name:?}"));
+    let start = json.find(&format!("\"{name}\"")).ok_or_else(missing)? + name.len() + 2;
+    let rest = json[start..].trim_start().strip_prefix(':').ok_or_else(missing)?.trim_start();
+    let end = if rest.starts_with('[') {
+        rest.find(']').map(|end| end + 1)
+    } else {
+        rest.find(|c: char| c == ',' || c == '}' || c.is_whitespace())
+    };
+    Ok(&rest[..end.unwrap_or(rest.len())])
+}
+
+/// The JSON Schema of [`SystemInfo::to_json`]'s output, for validating it mechanically.
+///
+/// ```rust
+/// let schema = sysinfo_dot_h::snapshot_schema();
+/// assert_eq!(schema.get("title").unwrap(), "SystemInfo");
+/// ```
+#[cfg(feature = "schemars")]
+#[must_use] pub fn snapshot_schema() -> schemars::Schema {
+    schemars::schema_for!(SystemInfo)
+}
+
+/// Like [`try_collect`](crate::try_collect), but returns the normalized [`SystemInfo`].
+///
+/// # Examples
+///
+/// ```rust
+/// use sysinfo_dot_h::try_collect_info;
+///
+/// let info = try_collect_info().unwrap();
+/// dbg!(info.uptime); // a Duration
+/// dbg!(info.total_ram); // in bytes, no mem_unit math needed
+/// ```
+///
+/// # Errors
+///
+/// Same as [`try_collect`](crate::try_collect).
+#[cfg(feature = "std")]
+pub fn try_collect_info() -> Result<SystemInfo, SysinfoError> {
+    crate::syscall().map(SystemInfo::from)
+}
+
+/// Like [`collect`](crate::collect), but returns the normalized [`SystemInfo`].
+///
+/// This has the same caveats as `collect()`: if the call to `sysinfo()` fails the values will be
+/// garbage (zeroes, in practice).
+#[cfg(feature = "std")]
+#[must_use] pub fn collect_info() -> SystemInfo {
+    SystemInfo::from(crate::compat::collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw() -> sysinfo {
+        let mut raw: sysinfo = unsafe { core::mem::zeroed() };
+        raw.uptime = 90;
+        raw.loads = [1 << 16, 1 << 15, 3 << 14];
+        raw.totalram = 1024;
+        raw.freeram = 512;
+        raw.totalswap = 2048;
+        raw.procs = 42;
+        raw.mem_unit = 4096;
+        raw
+    }
+
+    #[test]
+    fn normalizes_units() {
+        let info = SystemInfo::from(raw());
+        assert_eq!(info.uptime, Duration::from_secs(90));
+        assert_eq!(info.loads, [1.0, 0.5, 0.75]);
+        assert_eq!(info.total_ram, 1024 * 4096);
+        assert_eq!(info.free_ram, 512 * 4096);
+        assert_eq!(info.total_swap, 2048 * 4096);
+        assert_eq!(info.procs, 42);
+    }
+
+    #[test]
+    fn zero_mem_unit_means_bytes() {
+        let mut raw = raw();
+        raw.mem_unit = 0;
+        assert_eq!(SystemInfo::from(raw).total_ram, 1024);
+    }
+
+    #[test]
+    fn raw_json_is_normalized() {
+        let json = raw().to_json();
+        assert!(json.starts_with("{\"uptime\":90,\"loads\":[1,0.5,0.75],\"total_ram\":4194304,"), "{json}");
+        assert_eq!(SystemInfo::from_json(&json).unwrap(), SystemInfo::from(raw()));
+    }
+
+    #[test]
+    fn byte_accessors() {
+        let mut raw = raw();
+        assert_eq!((raw.total_ram_bytes(), raw.checked_free_ram_bytes()), (1024 * 4096, Some(512 * 4096)));
+        // 32-bit with more than 4 GiB: the kernel counts in pages
+        raw.totalram = 0x0010_0000;
+        assert_eq!(raw.total_ram_bytes(), 4 << 30);
+        #[cfg(target_pointer_width = "64")]
+        {
+            raw.totalram = c_ulong::MAX;
+            assert_eq!((raw.checked_total_ram_bytes(), raw.total_ram_bytes()), (None, u64::MAX));
+        }
+    }
+
+    #[test]
+    fn back_to_raw() {
+        let info = SystemInfo::from(raw());
+        let back = sysinfo::from(info);
+        assert_eq!((back.uptime, back.loads, back.procs), (90, [1 << 16, 1 << 15, 3 << 14], 42));
+        assert_eq!(back.mem_unit, 1);
+        assert_eq!(SystemInfo::from(back), info);
+
+        let big = SystemInfo { total_ram: u64::MAX, free_ram: 1 << 40, ..info };
+        let back = sysinfo::from(big);
+        assert!(back.mem_unit >= 1 && back.totalram > 0);
+        assert_eq!(SystemInfo::from(back).free_ram, 1 << 40);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_matches_json() {
+        let info = SystemInfo::from(raw());
+        let json = serde_json::to_string(&info).unwrap();
+        assert_eq!(json.replace(".0,", ",").replace(".0]", "]"), info.to_json());
+        assert_eq!(serde_json::from_str::<SystemInfo>(&json).unwrap(), info);
+
+        // the raw struct is normalized, mem_unit and the padding don't show
+        assert_eq!(serde_json::to_string(&raw()).unwrap(), json);
+        let back: sysinfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(SystemInfo::from(back), info);
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn schema_matches_json() {
+        let schema = snapshot_schema();
+        let mut properties: Vec<&String> = schema.get("properties").unwrap().as_object().unwrap().keys().collect();
+        properties.sort();
+
+        let json = SystemInfo::from(raw()).to_json();
+        let mut keys: Vec<String> = json.split('"').skip(1).step_by(2).map(str::to_string).collect();
+        keys.sort();
+        assert_eq!(properties, keys.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let info = SystemInfo::from(raw());
+        assert_eq!(SystemInfo::from_json(&info.to_json()).unwrap(), info);
+
+        let pretty = info.to_json().replace(',', ",\n  ").replace(':', ": ").replace('[', "[ ");
+        assert_eq!(SystemInfo::from_json(&pretty).unwrap(), info);
+        assert!(SystemInfo::from_json("{\"uptime\":1}").is_err());
+        assert!(SystemInfo::from_json(&info.to_json().replace("\"procs\":42", "\"procs\":-1")).is_err());
+    }
+
+    #[test]
+    fn json_shape() {
+        let json = SystemInfo::from(raw()).to_json();
+        assert!(json.starts_with("{\"uptime\":90,\"loads\":[1,0.5,0.75],"));
+        assert!(json.ends_with("\"procs\":42}"));
+    }
+}
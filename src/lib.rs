@@ -3,8 +3,96 @@
 //!
 //! Internally, this crate calls `unsafe {}` because of FFI. You, the programmer, are ultimately
 //! responsible for any downtime in prod or similar
-#[cfg(not(target_os = "linux"))] compile_error!("The <sys/sysinfo.h> calls are only present in Linux.");
-use std::os::raw::{c_long, c_ulong, c_ushort, c_uint, c_int, c_char};
+//!
+//! `sysinfo(2)` only exists on Linux. On macOS and FreeBSD [`sysinfo()`] is filled from
+//! `sysctlbyname(3)` instead, with the fields that have no sysctl left at 0 (see the table in
+//! `src/sysctl.rs`). Elsewhere (Windows, the other BSDs, ...) the crate builds against a stub
+//! kernel that always reports the same made up machine, so the examples in these docs still run.
+//! Don't ship that. The `/proc` readers only see real data on Linux.
+//!
+//! By default only the FFI core is built: [`sysinfo`](struct@sysinfo), [`collect`], [`try_collect`], the
+//! normalized [`SystemInfo`] and a few helpers without dependencies. Everything else is opt in,
+//! see the features in `Cargo.toml`: `procfs` for the `/proc` readers and the backends, `sampler`
+//! for the sampler and what's built around it (checks, caches, watchers, recordings, test helpers),
+//! `export-prometheus`, `cli` for the `sysinfo-dot-h` binary, and the integrations.
+//!
+//! The default `std` feature is the only one that isn't about an API: without it the crate is
+//! `#![no_std]`, for static binaries that don't link a libc, and collects with the `raw-syscall`
+//! feature's `raw` module. See there for what's left.
+#![cfg_attr(not(feature = "std"), no_std)]
+use core::ffi::{c_long, c_ulong, c_ushort, c_uint, c_char};
+#[cfg(all(feature = "std", target_os = "linux"))] use core::ffi::c_int;
+#[cfg(feature = "std")] use std::time::{Duration, Instant, SystemTime};
+
+#[cfg(feature = "std")] mod error;
+#[cfg(feature = "std")] pub use error::SysinfoError;
+mod info;
+pub use info::SystemInfo;
+mod repr;
+pub use repr::Sysinfo;
+#[cfg(feature = "std")] pub use repr::{try_collect_sysinfo, try_collect_sysinfo_into};
+#[cfg(all(feature = "libc", target_os = "linux"))] mod libc_compat;
+#[cfg(feature = "std")] pub use info::{try_collect_info, collect_info};
+#[cfg(feature = "schemars")] pub use info::snapshot_schema;
+
+pub mod delta;
+pub use delta::SysinfoDelta;
+pub mod loadavg;
+pub use loadavg::LoadAvg;
+#[cfg(any(feature = "raw-syscall", all(feature = "procfs", target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+pub mod raw;
+#[cfg(feature = "std")] pub mod compat;
+#[cfg(feature = "std")] pub mod stable;
+#[cfg(all(feature = "std", any(target_os = "linux", target_os = "macos", target_os = "freebsd")))] pub mod sysconf;
+#[cfg(feature = "std")] pub mod units;
+#[cfg(all(feature = "std", any(target_os = "linux", target_os = "macos", target_os = "freebsd")))] pub mod utsname;
+#[cfg(feature = "procfs")] pub mod backend;
+#[cfg(feature = "procfs")] pub use backend::try_collect_with_fallback;
+#[cfg(all(feature = "std", any(target_os = "linux", target_os = "macos", target_os = "freebsd")))] pub use utsname::try_collect_uname;
+#[cfg(feature = "procfs")] pub mod cgroup;
+#[cfg(feature = "procfs")] pub mod cpu;
+#[cfg(feature = "procfs")] pub mod meminfo;
+#[cfg(feature = "procfs")] pub mod numa;
+#[cfg(feature = "procfs")] pub mod process;
+#[cfg(feature = "procfs")] pub mod psi;
+/// [`psi`] under the name of its files, `/proc/pressure`.
+#[cfg(feature = "procfs")] pub use psi as pressure;
+#[cfg(feature = "procfs")] pub mod vmstat;
+#[cfg(feature = "sampler")] pub mod cache;
+#[cfg(feature = "sampler")] pub mod check;
+#[cfg(feature = "sampler")] pub mod clock;
+#[cfg(feature = "sampler")] pub mod health;
+#[cfg(feature = "sampler")] pub mod provider;
+#[cfg(feature = "sampler")] pub mod recording;
+#[cfg(feature = "sampler")] pub mod refresh;
+#[cfg(feature = "sampler")] pub mod sampler;
+#[cfg(feature = "sampler")] pub mod seqlock;
+#[cfg(feature = "sampler")] pub mod testing;
+#[cfg(feature = "sampler")] pub mod watch;
+#[cfg(feature = "export-prometheus")] pub mod prometheus;
+#[cfg(feature = "fake")] pub mod fake;
+#[cfg(any(feature = "arbitrary", feature = "proptest", all(test, feature = "procfs")))] mod generate;
+// macOS and FreeBSD only need it for the /proc files
+#[cfg(any(
+    all(feature = "std", not(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))),
+    all(not(target_os = "linux"), feature = "procfs"),
+    all(test, feature = "procfs"),
+))]
+mod stub;
+#[cfg(all(feature = "std", any(target_os = "macos", target_os = "freebsd")))] mod sysctl;
+
+#[cfg(feature = "async")] pub mod nonblocking;
+#[cfg(feature = "uds")] pub mod uds;
+#[cfg(feature = "grpc")] pub mod grpc;
+#[cfg(feature = "zbus")] pub mod dbus;
+#[cfg(feature = "systemd")] pub mod systemd;
+#[cfg(feature = "zabbix")] pub mod zabbix;
+#[cfg(feature = "capi")] pub mod capi;
+#[cfg(feature = "arrow")] pub mod columnar;
+
+/// The size of the tail padding, `20 - 2 * sizeof(long) - sizeof(int)` in the kernel's header:
+/// nothing on 64-bit, 8 bytes on 32-bit.
+pub(crate) const TAIL: usize = 20 - 2 * core::mem::size_of::<c_ulong>() - core::mem::size_of::<c_uint>();
 
 // https://stackoverflow.com/questions/349889/how-do-you-determine-the-amount-of-linux-system-ram-in-c
 /// The sysinfo struct. Should be the same as it is in C.
@@ -24,6 +112,9 @@ use std::os::raw::{c_long, c_ulong, c_ushort, c_uint, c_int, c_char};
 /// - `totalhigh`: Total high memory size
 /// - `freehigh`: Available high memory size
 /// - `mem_unit`: Memory unit size in bytes
+///
+/// v1 renames it [`Sysinfo`], see there for moving over. This one stays, as the `sysinfo()`
+/// binding's argument and in [`compat`].
 #[repr(C)]
 #[allow(non_camel_case_types)] // if uppercase, this may be a breaking change. fix in v1.
 #[derive(Debug, Copy, Clone)]
@@ -55,12 +146,25 @@ pub struct sysinfo {
     /// Memory unit size in bytes
     pub mem_unit: c_uint,
     /// Padding (you cant access this)
-    _f: [c_char; 0],
+    _f: [c_char; TAIL],
 }
 
+#[cfg(all(feature = "std", target_os = "linux"))]
 extern "C" {
     pub fn sysinfo(info: *mut sysinfo) -> c_int;
 }
+#[cfg(all(feature = "std", any(target_os = "macos", target_os = "freebsd")))] pub use sysctl::sysinfo;
+#[cfg(all(feature = "std", not(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))))] pub use stub::sysinfo;
+
+/// Reads a `/proc` file, or the stub's version of it where there's no `/proc`.
+#[cfg(feature = "procfs")]
+pub(crate) fn read_proc(path: &str) -> Result<String, SysinfoError> {
+    #[cfg(not(target_os = "linux"))]
+    if let Some(contents) = stub::proc_file(path) {
+        return Ok(contents);
+    }
+    std::fs::read_to_string(path).map_err(|e| SysinfoError::proc(path, e))
+}
 
 /// A wrapper to C to get the sysinfo struct.
 ///
@@ -78,22 +182,87 @@ extern "C" {
 ///
 /// # Errors
 ///
-/// If the FFI call to `sysinfo()` fails, this function will return an `Err` type. This is
-/// unlikely to occur but heee's a heads up.
+/// If the FFI call to `sysinfo()` fails, this function will return a [`SysinfoError::Syscall`]
+/// with the `errno` it set. This is unlikely to occur but heee's a heads up.
 ///
 /// # Safety
 ///
 /// Although this function uses `unsafe{}` internally, it shouldn't cause any memory corruption bugs. The data returned by this function is usuable outside of `unsafe{}`.
-pub fn try_collect() -> Result<sysinfo, String> {
-    unsafe {
-        let mut info: sysinfo = std::mem::zeroed();
-        let result = sysinfo(&mut info);
-        if result == 0 {
-            Ok(info)
-        } else {
-            Err("Failed to get the sysinfo struct".to_string())
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "deprecate-v0", deprecated(note = "replaced by `try_collect_sysinfo` in v1, or the same in `compat`"))]
+pub fn try_collect() -> Result<sysinfo, SysinfoError> {
+    syscall()
+}
+
+/// [`try_collect`] into a struct the caller owns, for hot loops that reuse one. Nothing is
+/// allocated, not even on failure (the error is just the `errno`), unless the `fake` feature is
+/// on.
+///
+/// ```rust
+/// let mut info = sysinfo_dot_h::collect();
+/// for _ in 0..1000 {
+///     sysinfo_dot_h::try_collect_into(&mut info).unwrap();
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Same as [`try_collect`]. `info` may have been partly written then.
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "deprecate-v0", deprecated(note = "replaced by `try_collect_sysinfo_into` in v1, or the same in `compat`"))]
+pub fn try_collect_into(info: &mut sysinfo) -> Result<(), SysinfoError> {
+    syscall_into(info)
+}
+
+/// What [`try_collect`] and friends do, under a name that isn't going to be deprecated, for the
+/// crate's own use.
+#[cfg(feature = "std")]
+pub(crate) fn syscall() -> Result<sysinfo, SysinfoError> {
+    let mut info: sysinfo = unsafe { std::mem::zeroed() };
+    syscall_into(&mut info)?;
+    Ok(info)
+}
+
+/// [`try_collect_into`], see [`syscall`].
+#[cfg(feature = "std")]
+pub(crate) fn syscall_into(info: &mut sysinfo) -> Result<(), SysinfoError> {
+    if unsafe { sysinfo(info) } != 0 {
+        return Err(SysinfoError::last_os_error());
+    }
+    #[cfg(feature = "fake")] fake::apply(info).map_err(SysinfoError::Fake)?;
+    Ok(())
+}
+
+/// Fills `samples` with snapshots taken `interval` apart (back to back with [`Duration::ZERO`]),
+/// for capturing a short high resolution trace around some suspect code. Doesn't allocate.
+///
+/// The samples are spaced from the first one rather than from each other, so a late one doesn't
+/// push back the rest.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// let mut trace = [sysinfo_dot_h::collect(); 16];
+/// sysinfo_dot_h::collect_many_into(&mut trace, Duration::from_millis(1)).unwrap();
+/// ```
+///
+/// # Errors
+///
+/// Fails like [`try_collect`] on the first sample that can't be collected. The ones before it are
+/// filled in, the others left alone.
+#[cfg(feature = "std")]
+pub fn collect_many_into(samples: &mut [sysinfo], interval: Duration) -> Result<(), SysinfoError> {
+    let start = Instant::now();
+    for (i, sample) in samples.iter_mut().enumerate() {
+        if i > 0 && !interval.is_zero() {
+            let due = start + interval.saturating_mul(u32::try_from(i).unwrap_or(u32::MAX));
+            if let Some(remaining) = due.checked_duration_since(Instant::now()) {
+                std::thread::sleep(remaining);
+            }
         }
+        syscall_into(sample)?;
     }
+    Ok(())
 }
 
 /// A wrapper to C to get the sysinfo struct.
@@ -114,12 +283,52 @@ pub fn try_collect() -> Result<sysinfo, String> {
 /// # Safety
 ///
 /// Although this function uses `unsafe{}` internally, it shouldn't cause any memory corruption bugs. The data returned by this function is usuable outside of `unsafe{}`.
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "deprecate-v0", deprecated(note = "replaced by `try_collect_sysinfo` or `collect_info` in v1, or the same in `compat`"))]
 #[must_use] pub fn collect() -> sysinfo {
-    unsafe {
-        let mut info: sysinfo = std::mem::zeroed();
-        sysinfo(&mut info);
-        info
+    compat::collect()
+}
+
+/// Time since boot, to the second.
+///
+/// ```rust
+/// println!("up {:?}", sysinfo_dot_h::uptime().unwrap());
+/// ```
+///
+/// # Errors
+///
+/// Same as [`try_collect`].
+#[cfg(feature = "std")]
+pub fn uptime() -> Result<Duration, SysinfoError> {
+    Ok(Duration::from_secs(u64::try_from(syscall()?.uptime).unwrap_or(0)))
+}
+
+/// When the machine booted. With the `procfs` feature that's the kernel's own `btime` in
+/// `/proc/stat`, the same on every call, with [`SystemInfo::boot_time`] as the fallback, which can
+/// be a second off either way from one call to the next (the clock and the uptime don't tick
+/// together) and moves when the clock is set.
+///
+/// ```rust
+/// let booted = sysinfo_dot_h::boot_time().unwrap();
+/// println!("booted {:?} ago", booted.elapsed().unwrap());
+/// ```
+///
+/// # Errors
+///
+/// Same as [`try_collect`], if it gets to it.
+#[cfg(feature = "std")]
+pub fn boot_time() -> Result<SystemTime, SysinfoError> {
+    #[cfg(feature = "procfs")]
+    if let Some(btime) = read_proc("/proc/stat").ok().as_deref().and_then(parse_btime) {
+        return Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(btime));
     }
+    Ok(try_collect_info()?.boot_time())
+}
+
+/// The `btime` line of `/proc/stat`, seconds since the epoch.
+#[cfg(feature = "procfs")]
+fn parse_btime(stat: &str) -> Option<u64> {
+    stat.lines().find_map(|line| line.strip_prefix("btime "))?.trim().parse().ok()
 }
 
 #[cfg(test)]
@@ -127,12 +336,28 @@ mod tests {
     use super::*;
 
     #[test]
+    fn uptime_and_boot_time() {
+        let uptime = uptime().unwrap();
+        assert!(uptime > Duration::ZERO);
+        let booted = boot_time().unwrap();
+        // btime is truncated, the uptime rounded
+        assert!(booted.elapsed().unwrap() + Duration::from_secs(2) >= uptime);
+        #[cfg(feature = "procfs")]
+        {
+            assert_eq!(parse_btime("cpu  1 2 3 4\nbtime 1700000000\nprocesses 42\n"), Some(1_700_000_000));
+            assert_eq!(parse_btime("cpu  1 2 3 4\n"), None);
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)] // the v0 names are still tested, until v1 removes them
     fn try_collect_sysinfo() {
         let result = try_collect();
         assert!(result.is_ok());
     }
 
     #[test]
+    #[allow(deprecated)] // the v0 names are still tested, until v1 removes them
     fn try_fetch_uptime() {
         let result = try_collect();
         debug_assert!(result.is_ok()); // essentally the collect_sysinfo test
@@ -141,6 +366,26 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)] // the v0 names are still tested, until v1 removes them
+    fn collects_into() {
+        let mut info: sysinfo = unsafe { std::mem::zeroed() };
+        try_collect_into(&mut info).unwrap();
+        assert!(info.totalram > 0 && info.mem_unit > 0);
+    }
+
+    #[test]
+    fn collects_many() {
+        let mut samples = [unsafe { std::mem::zeroed::<sysinfo>() }; 4];
+        collect_many_into(&mut samples, Duration::ZERO).unwrap();
+        assert!(samples.iter().all(|sample| sample.totalram > 0));
+
+        let start = Instant::now();
+        collect_many_into(&mut samples, Duration::from_millis(5)).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(15));
+    }
+
+    #[test]
+    #[allow(deprecated)] // the v0 names are still tested, until v1 removes them
     fn fetch_uptime() {
         let result = collect();
         println!("fetch_uptime(): {}", result.uptime);
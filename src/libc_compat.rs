@@ -0,0 +1,105 @@
+//! Conversions to and from the `libc` crate's `sysinfo`, behind the `libc` feature, for code
+//! that already has one. Field by field rather than a cast: glibc's and musl's don't have the
+//! same size (musl reserves 256 bytes at the end) and musl's `uptime` is unsigned.
+use crate::{sysinfo, Sysinfo};
+
+/// Every field, `uptime` saturated if musl's unsigned one doesn't fit.
+impl From<libc::sysinfo> for sysinfo {
+    fn from(raw: libc::sysinfo) -> Self {
+        // SAFETY: all zeroes is a valid struct sysinfo
+        let mut info: sysinfo = unsafe { core::mem::zeroed() };
+        #[allow(clippy::useless_conversion, clippy::unnecessary_fallible_conversions)] // musl's uptime isn't a c_long
+        {
+            info.uptime = raw.uptime.try_into().unwrap_or(core::ffi::c_long::MAX);
+        }
+        info.loads = raw.loads;
+        info.totalram = raw.totalram;
+        info.freeram = raw.freeram;
+        info.sharedram = raw.sharedram;
+        info.bufferram = raw.bufferram;
+        info.totalswap = raw.totalswap;
+        info.freeswap = raw.freeswap;
+        info.procs = raw.procs;
+        info.totalhigh = raw.totalhigh;
+        info.freehigh = raw.freehigh;
+        info.mem_unit = raw.mem_unit;
+        info
+    }
+}
+
+/// Every field, a negative `uptime` (which the kernel never reports) becoming 0 on musl.
+impl From<sysinfo> for libc::sysinfo {
+    fn from(info: sysinfo) -> Self {
+        // SAFETY: all zeroes is a valid struct sysinfo, padding and reserved bytes included
+        let mut raw: libc::sysinfo = unsafe { core::mem::zeroed() };
+        #[allow(clippy::useless_conversion, clippy::unnecessary_fallible_conversions)] // musl's uptime isn't a c_long
+        {
+            raw.uptime = info.uptime.try_into().unwrap_or_default();
+        }
+        raw.loads = info.loads;
+        raw.totalram = info.totalram;
+        raw.freeram = info.freeram;
+        raw.sharedram = info.sharedram;
+        raw.bufferram = info.bufferram;
+        raw.totalswap = info.totalswap;
+        raw.freeswap = info.freeswap;
+        raw.procs = info.procs;
+        raw.totalhigh = info.totalhigh;
+        raw.freehigh = info.freehigh;
+        raw.mem_unit = info.mem_unit;
+        raw
+    }
+}
+
+/// Through [`sysinfo`](struct@sysinfo), see there.
+impl From<libc::sysinfo> for Sysinfo {
+    fn from(raw: libc::sysinfo) -> Self {
+        Sysinfo::from(sysinfo::from(raw))
+    }
+}
+
+/// Through [`sysinfo`](struct@sysinfo), see there.
+impl From<Sysinfo> for libc::sysinfo {
+    fn from(info: Sysinfo) -> Self {
+        libc::sysinfo::from(sysinfo::from(info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::{align_of, offset_of, size_of};
+
+    /// The fields sit where libc has them, so both describe the struct the kernel writes.
+    #[test]
+    fn layout_matches_libc() {
+        assert_eq!(align_of::<sysinfo>(), align_of::<libc::sysinfo>());
+        // musl's is bigger, it reserves room for the future
+        assert!(size_of::<sysinfo>() <= size_of::<libc::sysinfo>());
+        #[cfg(target_env = "gnu")]
+        assert_eq!(size_of::<sysinfo>(), size_of::<libc::sysinfo>());
+        assert_eq!(offset_of!(sysinfo, uptime), offset_of!(libc::sysinfo, uptime));
+        assert_eq!(offset_of!(sysinfo, loads), offset_of!(libc::sysinfo, loads));
+        assert_eq!(offset_of!(sysinfo, totalram), offset_of!(libc::sysinfo, totalram));
+        assert_eq!(offset_of!(sysinfo, freeram), offset_of!(libc::sysinfo, freeram));
+        assert_eq!(offset_of!(sysinfo, sharedram), offset_of!(libc::sysinfo, sharedram));
+        assert_eq!(offset_of!(sysinfo, bufferram), offset_of!(libc::sysinfo, bufferram));
+        assert_eq!(offset_of!(sysinfo, totalswap), offset_of!(libc::sysinfo, totalswap));
+        assert_eq!(offset_of!(sysinfo, freeswap), offset_of!(libc::sysinfo, freeswap));
+        assert_eq!(offset_of!(sysinfo, procs), offset_of!(libc::sysinfo, procs));
+        assert_eq!(offset_of!(sysinfo, totalhigh), offset_of!(libc::sysinfo, totalhigh));
+        assert_eq!(offset_of!(sysinfo, freehigh), offset_of!(libc::sysinfo, freehigh));
+        assert_eq!(offset_of!(sysinfo, mem_unit), offset_of!(libc::sysinfo, mem_unit));
+    }
+
+    #[test]
+    fn lossless_both_ways() {
+        let mut raw: libc::sysinfo = unsafe { core::mem::zeroed() };
+        assert_eq!(unsafe { libc::sysinfo(&mut raw) }, 0);
+        let info = sysinfo::from(raw);
+        assert_eq!((info.totalram, info.freeswap, info.procs, info.mem_unit), (raw.totalram, raw.freeswap, raw.procs, raw.mem_unit));
+        let back = libc::sysinfo::from(info);
+        assert_eq!((back.uptime, back.loads, back.totalhigh, back.mem_unit), (raw.uptime, raw.loads, raw.totalhigh, raw.mem_unit));
+        assert_eq!(Sysinfo::from(raw), Sysinfo::from(info));
+    }
+}
@@ -0,0 +1,119 @@
+//! The 1, 5 and 15 minute load averages as plain numbers, with names instead of indices.
+//!
+//! The raw struct keeps them as fixed-point numbers scaled by `1 << SI_LOAD_SHIFT`; [`LoadAvg`]
+//! comes out of it (or out of a [`SystemInfo`]) already divided. On Unix, `getloadavg` reads
+//! them without the rest of the snapshot.
+//!
+//! ```rust
+//! let loads = sysinfo_dot_h::collect().loads();
+//! println!("load average: {loads}"); // "load average: 0.52 0.40 0.33"
+//! assert!(loads.one >= 0.0);
+//! ```
+use core::fmt;
+
+use crate::info::SI_LOAD_SCALE;
+use crate::{sysinfo, SystemInfo};
+
+/// The load averages: runnable (and uninterruptible) tasks, averaged over 1, 5 and 15 minutes.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct LoadAvg {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+}
+
+impl From<[f64; 3]> for LoadAvg {
+    fn from([one, five, fifteen]: [f64; 3]) -> Self {
+        Self { one, five, fifteen }
+    }
+}
+
+impl From<LoadAvg> for [f64; 3] {
+    fn from(loads: LoadAvg) -> Self {
+        [loads.one, loads.five, loads.fifteen]
+    }
+}
+
+impl From<sysinfo> for LoadAvg {
+    fn from(raw: sysinfo) -> Self {
+        raw.loads()
+    }
+}
+
+impl From<&SystemInfo> for LoadAvg {
+    fn from(info: &SystemInfo) -> Self {
+        info.load_avg()
+    }
+}
+
+impl sysinfo {
+    /// The load averages out of the fixed-point `loads`.
+    #[must_use] pub fn loads(&self) -> LoadAvg {
+        LoadAvg::from(self.loads.map(|load| load as f64 / SI_LOAD_SCALE))
+    }
+}
+
+impl SystemInfo {
+    /// `loads` with names.
+    #[must_use] pub fn load_avg(&self) -> LoadAvg {
+        LoadAvg::from(self.loads)
+    }
+}
+
+/// Two decimals each, like `uptime(1)`: `0.52 0.40 0.33`.
+impl fmt::Display for LoadAvg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2} {:.2} {:.2}", self.one, self.five, self.fifteen)
+    }
+}
+
+/// The load averages from `getloadavg(3)` instead of `sysinfo(2)`, for where only they are
+/// needed. glibc and musl read `/proc/loadavg` for it, the BSDs and macOS ask the kernel.
+///
+/// ```rust
+/// let loads = sysinfo_dot_h::loadavg::getloadavg().unwrap();
+/// assert!(loads.fifteen >= 0.0);
+/// ```
+///
+/// # Errors
+///
+/// Returns [`SysinfoError::Syscall`](crate::SysinfoError::Syscall) if `getloadavg()` fails,
+/// and [`SysinfoError::ShortLoadAvg`](crate::SysinfoError::ShortLoadAvg) if it only got some of
+/// the three.
+#[cfg(all(feature = "std", unix))]
+pub fn getloadavg() -> Result<LoadAvg, crate::SysinfoError> {
+    extern "C" {
+        fn getloadavg(loadavg: *mut f64, nelem: std::os::raw::c_int) -> std::os::raw::c_int;
+    }
+    let mut loads = [0.0; 3];
+    match unsafe { getloadavg(loads.as_mut_ptr(), 3) } {
+        3 => Ok(LoadAvg::from(loads)),
+        -1 => Err(crate::SysinfoError::last_os_error()),
+        got => Err(crate::SysinfoError::ShortLoadAvg { got: got.max(0) as usize }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_loads() {
+        let mut raw: sysinfo = unsafe { std::mem::zeroed() };
+        raw.loads = [1 << 16, 1 << 15, 3 << 14];
+        let loads = raw.loads();
+        assert_eq!(loads, LoadAvg { one: 1.0, five: 0.5, fifteen: 0.75 });
+        assert_eq!(LoadAvg::from(&SystemInfo::from(raw)), loads);
+        assert_eq!(<[f64; 3]>::from(loads), [1.0, 0.5, 0.75]);
+        assert_eq!(loads.to_string(), "1.00 0.50 0.75");
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+    #[test]
+    fn getloadavg_agrees() {
+        let libc = getloadavg().unwrap();
+        let syscall = crate::compat::collect().loads();
+        // both round, differently, and the averages may have moved in between
+        assert!((libc.fifteen - syscall.fifteen).abs() < 0.5, "{libc} vs {syscall}");
+    }
+}
@@ -0,0 +1,395 @@
+//! `/proc/meminfo`, for the numbers `sysinfo(2)` doesn't have. Most importantly `MemAvailable`,
+//! the kernel's estimate of how much memory can be allocated without swapping, which is what
+//! people usually mean by "free memory".
+//!
+//! ```rust
+//! let meminfo = sysinfo_dot_h::meminfo::MemInfo::read().unwrap();
+//! assert!(meminfo.total > 0);
+//! ```
+use std::str::FromStr;
+
+use crate::SysinfoError;
+
+/// The interesting parts of `/proc/meminfo`, in bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct MemInfo {
+    /// `MemTotal`
+    pub total: u64,
+    /// `MemFree`
+    pub free: u64,
+    /// `MemAvailable`, missing on kernels older than 3.14
+    pub available: Option<u64>,
+    /// `Buffers`
+    pub buffers: u64,
+    /// `Cached`, the page cache
+    pub cached: u64,
+    /// `Shmem`, including tmpfs
+    pub shmem: u64,
+    /// `SReclaimable`, slab memory the kernel can give back (dentries, inodes, ...)
+    pub s_reclaimable: u64,
+    /// `Active(file)`, recently used page cache
+    pub active_file: u64,
+    /// `Inactive(file)`, page cache that's first in line for reclaim
+    pub inactive_file: u64,
+    /// `SwapTotal`
+    pub swap_total: u64,
+    /// `SwapFree`
+    pub swap_free: u64,
+    /// `HighTotal`, 0 on 64-bit kernels which have no high memory
+    pub high_total: u64,
+    /// `HighFree`
+    pub high_free: u64,
+    /// `CommitLimit`, how much can be committed with [`Overcommit::Never`]
+    pub commit_limit: u64,
+    /// `Committed_AS`, how much is committed (allocated, not necessarily touched) right now
+    pub committed_as: u64,
+}
+
+/// `vm.overcommit_memory`, how the kernel decides whether an allocation can have the memory it
+/// asks for, see `Documentation/mm/overcommit-accounting.rst`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Overcommit {
+    /// 0, the default: refuse only what obviously can't fit
+    #[default]
+    Heuristic,
+    /// 1: never refuse, the OOM killer sorts it out later
+    Always,
+    /// 2: refuse anything past `CommitLimit`
+    Never,
+}
+
+impl Overcommit {
+    /// Reads `/proc/sys/vm/overcommit_memory`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or holds something other than 0, 1 or 2.
+    pub fn read() -> Result<Self, SysinfoError> {
+        crate::read_proc("/proc/sys/vm/overcommit_memory")?.parse()
+    }
+}
+
+impl FromStr for Overcommit {
+    type Err = SysinfoError;
+
+    fn from_str(s: &str) -> Result<Self, SysinfoError> {
+        match s.trim() {
+            "0" => Ok(Overcommit::Heuristic),
+            "1" => Ok(Overcommit::Always),
+            "2" => Ok(Overcommit::Never),
+            _ => Err(SysinfoError::parse("/proc/sys/vm/overcommit_memory", s)),
+        }
+    }
+}
+
+/// The kernel's low watermarks and reserved pages, summed over every zone of `/proc/zoneinfo`,
+/// in bytes: what [`MemInfo::estimate_available`] needs to follow the kernel's calculation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Watermarks {
+    /// The `low` watermarks, reclaim starts below them
+    pub low: u64,
+    /// `totalreserve_pages`: the `high` watermarks plus the largest `protection` of each zone,
+    /// which allocations can't have
+    pub reserve: u64,
+}
+
+impl Watermarks {
+    /// Reads `/proc/zoneinfo`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or has no zones.
+    pub fn read() -> Result<Self, SysinfoError> {
+        Self::parse(&crate::read_proc("/proc/zoneinfo")?, page_size())
+    }
+
+    /// Parses the contents of `/proc/zoneinfo`, which counts in pages of `page_size` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no zones, or a watermark isn't a number.
+    pub fn parse(zoneinfo: &str, page_size: u64) -> Result<Self, SysinfoError> {
+        let invalid = |line: &str| SysinfoError::parse("/proc/zoneinfo", line);
+        let (mut low, mut reserve, mut zones) = (0u64, 0u64, 0);
+        for line in zoneinfo.lines() {
+            let line = line.trim();
+            if line.starts_with("Node ") {
+                zones += 1;
+            } else if let Some(protection) = line.strip_prefix("protection:") {
+                let protection = protection.trim().trim_start_matches('(').trim_end_matches(')');
+                let most = protection.split(',').map(|pages| pages.trim().parse::<u64>()).try_fold(0, |most, pages| pages.map(|pages| most.max(pages)));
+                reserve = reserve.saturating_add(most.map_err(|_| invalid(line))?);
+            } else if let Some((key @ ("low" | "high"), pages)) = line.split_once(char::is_whitespace) {
+                let pages: u64 = pages.trim().parse().map_err(|_| invalid(line))?;
+                match key {
+                    "low" => low = low.saturating_add(pages),
+                    _ => reserve = reserve.saturating_add(pages),
+                }
+            }
+        }
+        if zones == 0 {
+            return Err(SysinfoError::missing("/proc/zoneinfo", "zones"));
+        }
+        Ok(Self { low: low.saturating_mul(page_size), reserve: reserve.saturating_mul(page_size) })
+    }
+}
+
+/// The size of a page, which `/proc/zoneinfo` counts in.
+fn page_size() -> u64 {
+    #[cfg(target_os = "linux")]
+    if let Ok(size @ 1..) = crate::sysconf::page_size() {
+        return size;
+    }
+    4096
+}
+
+/// How much memory can be allocated without swapping: `MemAvailable`, or on kernels older than
+/// 3.14 that don't have it, the same estimate the kernel would make
+/// ([`MemInfo::estimate_available`]).
+///
+/// ```rust
+/// let available = sysinfo_dot_h::meminfo::available_memory().unwrap();
+/// assert!(available > 0);
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if `/proc/meminfo` can't be read, or `MemAvailable` is missing and
+/// `/proc/zoneinfo` can't be read.
+pub fn available_memory() -> Result<u64, SysinfoError> {
+    let meminfo = MemInfo::read()?;
+    match meminfo.available {
+        Some(available) => Ok(available),
+        None => Ok(meminfo.estimate_available(Watermarks::read()?)),
+    }
+}
+
+impl crate::SystemInfo {
+    /// [`available_memory`], which the snapshot doesn't have. It's read now rather than when the
+    /// snapshot was taken, and capped at its `total_ram` so the two stay consistent.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`available_memory`].
+    pub fn available_memory(&self) -> Result<u64, SysinfoError> {
+        available_memory().map(|available| available.min(self.total_ram))
+    }
+}
+
+/// [`MemInfo::really_available`] for this machine, right now.
+///
+/// # Errors
+///
+/// Returns an error if `/proc/meminfo` or `/proc/sys/vm/overcommit_memory` can't be read.
+pub fn really_available_bytes() -> Result<u64, SysinfoError> {
+    Ok(MemInfo::read()?.really_available(Overcommit::read()?))
+}
+
+impl MemInfo {
+    /// Reads `/proc/meminfo`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or doesn't look like meminfo.
+    pub fn read() -> Result<Self, SysinfoError> {
+        crate::read_proc("/proc/meminfo")?.parse()
+    }
+
+    /// How much a new allocation can realistically get and use without swapping, which is what
+    /// admission control wants to know and no single field says.
+    ///
+    /// That's `MemAvailable` (or an estimate out of free memory and the caches on kernels
+    /// without it), except with [`Overcommit::Never`] allocations past `CommitLimit` fail no
+    /// matter how much is free, so whatever is left of the commit limit caps it. The other
+    /// policies hand out address space freely, and using it is what runs out.
+    #[must_use] pub fn really_available(&self, policy: Overcommit) -> u64 {
+        let available = self.available.unwrap_or_else(|| self.estimate_available(Watermarks::default())).min(self.total);
+        match policy {
+            Overcommit::Never => available.min(self.commit_limit.saturating_sub(self.committed_as)),
+            Overcommit::Heuristic | Overcommit::Always => available,
+        }
+    }
+
+    /// `MemAvailable` the way the kernel calculates it (`si_mem_available()`), for kernels that
+    /// are too old to show it: free memory minus the reserves, plus the page cache and the
+    /// reclaimable slab except for what reclaim has to leave alone to stay above the low
+    /// watermark. With [`Watermarks::default()`] (nothing read out of `/proc/zoneinfo`) that's an
+    /// overestimate.
+    #[must_use] pub fn estimate_available(&self, watermarks: Watermarks) -> u64 {
+        let page_cache = self.active_file.saturating_add(self.inactive_file);
+        let page_cache = page_cache - (page_cache / 2).min(watermarks.low);
+        let reclaimable = self.s_reclaimable - (self.s_reclaimable / 2).min(watermarks.low);
+        // signed like the kernel's, free memory below the reserves eats into the rest; wide
+        // enough that nothing overflows, clamped once at the end
+        let available = i128::from(self.free) - i128::from(watermarks.reserve) + i128::from(page_cache) + i128::from(reclaimable);
+        available.clamp(0, i128::from(self.total)) as u64
+    }
+
+    /// What `free` shows as buff/cache: buffers, page cache and reclaimable slab.
+    #[must_use] pub fn buff_cache(&self) -> u64 {
+        self.buffers.saturating_add(self.cached).saturating_add(self.s_reclaimable)
+    }
+}
+
+impl FromStr for MemInfo {
+    type Err = SysinfoError;
+
+    /// Parses the contents of `/proc/meminfo`. Unknown lines are ignored, only `MemTotal` is
+    /// required.
+    fn from_str(s: &str) -> Result<Self, SysinfoError> {
+        let mut meminfo = MemInfo::default();
+        let mut has_total = false;
+        for line in s.lines() {
+            let Some((key, value)) = line.split_once(':') else { continue };
+            let field = match key {
+                "MemTotal" => {
+                    has_total = true;
+                    &mut meminfo.total
+                }
+                "MemFree" => &mut meminfo.free,
+                "MemAvailable" => meminfo.available.insert(0),
+                "Buffers" => &mut meminfo.buffers,
+                "Cached" => &mut meminfo.cached,
+                "Shmem" => &mut meminfo.shmem,
+                "SReclaimable" => &mut meminfo.s_reclaimable,
+                "Active(file)" => &mut meminfo.active_file,
+                "Inactive(file)" => &mut meminfo.inactive_file,
+                "SwapTotal" => &mut meminfo.swap_total,
+                "SwapFree" => &mut meminfo.swap_free,
+                "HighTotal" => &mut meminfo.high_total,
+                "HighFree" => &mut meminfo.high_free,
+                "CommitLimit" => &mut meminfo.commit_limit,
+                "Committed_AS" => &mut meminfo.committed_as,
+                _ => continue,
+            };
+            *field = parse_kb(value).ok_or_else(|| SysinfoError::parse("/proc/meminfo", line))?;
+        }
+        if !has_total {
+            return Err(SysinfoError::missing("/proc/meminfo", "MemTotal"));
+        }
+        Ok(meminfo)
+    }
+}
+
+/// ` 6147400 kB` as bytes. Despite the name, the kernel means KiB.
+fn parse_kb(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (number, multiplier) = match value.strip_suffix(" kB") {
+        Some(number) => (number, 1024),
+        None => (value, 1),
+    };
+    number.trim().parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEMINFO: &str = "\
+MemTotal:        6147400 kB
+MemFree:          390248 kB
+MemAvailable:    5527028 kB
+Buffers:           61276 kB
+Cached:          5199940 kB
+SwapCached:            0 kB
+Active(file):    1805604 kB
+Inactive(file):  3446464 kB
+SwapTotal:             0 kB
+SwapFree:              0 kB
+Shmem:              9048 kB
+SReclaimable:     182128 kB
+CommitLimit:     3073700 kB
+Committed_AS:    2871564 kB
+HugePages_Total:       0
+";
+
+    #[test]
+    fn parses() {
+        let meminfo: MemInfo = MEMINFO.parse().unwrap();
+        assert_eq!(meminfo.total, 6147400 * 1024);
+        assert_eq!(meminfo.available, Some(5527028 * 1024));
+        assert_eq!(meminfo.shmem, 9048 * 1024);
+        // the same as `free`'s buff/cache column
+        assert_eq!(meminfo.buff_cache(), 5443344 * 1024);
+        assert_eq!(MemInfo { buffers: u64::MAX, ..meminfo }.buff_cache(), u64::MAX);
+    }
+
+    #[test]
+    fn really_available() {
+        let meminfo: MemInfo = MEMINFO.parse().unwrap();
+        assert_eq!((meminfo.commit_limit, meminfo.committed_as), (3073700 * 1024, 2871564 * 1024));
+        assert_eq!(meminfo.really_available(Overcommit::Heuristic), 5527028 * 1024);
+        assert_eq!(meminfo.really_available(Overcommit::Always), 5527028 * 1024);
+        // plenty free, but only 197 MiB of commit limit left
+        assert_eq!(meminfo.really_available(Overcommit::Never), (3073700 - 2871564) * 1024);
+        let overcommitted = MemInfo { committed_as: meminfo.commit_limit + 1, ..meminfo };
+        assert_eq!(overcommitted.really_available(Overcommit::Never), 0);
+
+        let old = MemInfo { available: None, ..meminfo };
+        assert_eq!(old.really_available(Overcommit::Heuristic), (390248 + 1805604 + 3446464 + 182128) * 1024);
+
+        assert_eq!(" 2\n".parse::<Overcommit>().unwrap(), Overcommit::Never);
+        assert!("3".parse::<Overcommit>().is_err());
+        Overcommit::read().unwrap();
+    }
+
+    const ZONEINFO: &str = "\
+Node 0, zone      DMA
+  per-node stats
+      nr_inactive_anon 2043
+  pages free     3840
+        boost    0
+        min      12
+        low      15
+        high     18
+        spanned  4095
+        protection: (0, 2659, 15544, 15544, 15544)
+Node 0, zone   Normal
+  pages free     80000
+        min      8000
+        low      10000
+        high     12000
+        protection: (0, 0, 0, 0, 0)
+";
+
+    #[test]
+    fn estimates_like_the_kernel() {
+        let watermarks = Watermarks::parse(ZONEINFO, 4096).unwrap();
+        assert_eq!(watermarks, Watermarks { low: 10015 * 4096, reserve: (18 + 15544 + 12000) * 4096 });
+        assert!(Watermarks::parse("", 4096).is_err());
+        assert!(Watermarks::parse("Node 0, zone DMA\n low x\n", 4096).is_err());
+
+        let meminfo: MemInfo = MEMINFO.parse().unwrap();
+        assert_eq!((meminfo.active_file, meminfo.inactive_file), (1805604 * 1024, 3446464 * 1024));
+        let low = 10015 * 4096;
+        let reserve = (18 + 15544 + 12000) * 4096;
+        let page_cache = (1805604 + 3446464) * 1024;
+        let slab = 182128 * 1024;
+        assert_eq!(meminfo.estimate_available(watermarks), 390248 * 1024 - reserve + page_cache - low + slab - low);
+        // free memory short of the reserves is taken out of the page cache, not ignored
+        let short = MemInfo { free: reserve / 2, ..meminfo };
+        assert_eq!(short.estimate_available(watermarks), page_cache - low + slab - low - reserve / 2);
+        assert_eq!(MemInfo { free: 0, active_file: 0, inactive_file: 0, s_reclaimable: 0, ..meminfo }.estimate_available(watermarks), 0);
+        // not far from what the kernel says
+        assert!(meminfo.estimate_available(watermarks).abs_diff(meminfo.available.unwrap()) < 256 << 20);
+
+        assert!(available_memory().unwrap() > 0);
+        let info = crate::SystemInfo { total_ram: 1, ..Default::default() };
+        assert_eq!(info.available_memory().unwrap(), 1);
+        Watermarks::read().unwrap();
+    }
+
+    #[test]
+    fn old_kernels_and_garbage() {
+        let old: MemInfo = "MemTotal: 1024 kB\nMemFree: 512 kB\n".parse().unwrap();
+        assert_eq!((old.total, old.available), (1 << 20, None));
+        assert!("MemFree: 512 kB\n".parse::<MemInfo>().is_err());
+        assert!("MemTotal: lots\n".parse::<MemInfo>().is_err());
+    }
+
+    #[test]
+    fn reads_the_real_thing() {
+        let meminfo = MemInfo::read().unwrap();
+        assert!(meminfo.total >= meminfo.free);
+    }
+}
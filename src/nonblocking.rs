@@ -0,0 +1,73 @@
+//! Snapshots in async code (tokio), behind the `async` feature.
+//!
+//! `sysinfo(2)` copies a few counters out of the kernel and never waits for anything, so it's
+//! fine to call from a task as it is: [`try_collect_async`] doesn't go through `spawn_blocking`,
+//! which would cost more than the call. What async code really needs is [`samples`], a
+//! [`Stream`] of snapshots on a fixed cadence, driven by the runtime's timer instead of a
+//! [sampler](crate::sampler) thread:
+//!
+//! ```rust
+//! # tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap().block_on(async {
+//! use std::time::Duration;
+//! use tokio_stream::StreamExt;
+//!
+//! let info = sysinfo_dot_h::nonblocking::try_collect_async().await.unwrap();
+//! println!("up {}s", info.uptime);
+//!
+//! let mut samples = sysinfo_dot_h::nonblocking::samples(Duration::from_millis(10)).take(3);
+//! while let Some(info) = samples.next().await {
+//!     println!("{} bytes free", info.free_ram);
+//! }
+//! # });
+//! ```
+use std::time::Duration;
+
+use tokio::time::MissedTickBehavior;
+use tokio_stream::wrappers::IntervalStream;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::{sysinfo, try_collect_info, SysinfoError, SystemInfo};
+
+/// [`try_collect`](crate::try_collect) as a future, which is ready right away.
+///
+/// # Errors
+///
+/// Same as [`try_collect`](crate::try_collect).
+pub async fn try_collect_async() -> Result<sysinfo, SysinfoError> {
+    crate::syscall()
+}
+
+/// A snapshot every `interval`, the first one right away. Failed collections are skipped like in
+/// the [`Sampler`](crate::sampler::Sampler), and so are ticks missed because the consumer was
+/// busy: it gets the current state, not a backlog.
+///
+/// Must be called from within a tokio runtime with the time driver enabled. Panics if it isn't,
+/// or if `interval` is zero.
+pub fn samples(interval: Duration) -> impl Stream<Item = SystemInfo> + Send {
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    IntervalStream::new(ticks).filter_map(|_| try_collect_info().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap().block_on(future)
+    }
+
+    #[test]
+    fn collects() {
+        assert!(block_on(try_collect_async()).unwrap().totalram > 0);
+    }
+
+    #[test]
+    fn streams_on_a_cadence() {
+        let start = std::time::Instant::now();
+        let samples: Vec<SystemInfo> = block_on(async { samples(Duration::from_millis(5)).take(3).collect().await });
+        assert_eq!(samples.len(), 3);
+        assert!(samples.iter().all(|info| info.total_ram > 0));
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+}
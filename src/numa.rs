@@ -0,0 +1,178 @@
+//! Memory per NUMA node, out of `/sys/devices/system/node/node*/meminfo`. On a multi-socket
+//! machine the totals can look fine while one node is out of memory and its CPUs allocate from
+//! the other, slower, one.
+//!
+//! Kernels built without NUMA support don't have `/sys/devices/system/node` at all; everything
+//! else has at least node 0.
+//!
+//! ```rust
+//! use sysinfo_dot_h::numa::NumaMemory;
+//!
+//! let numa = NumaMemory::read().unwrap();
+//! for node in &numa.nodes {
+//!     println!("node {}: {} of {} bytes free", node.id, node.free, node.total);
+//! }
+//! let info = sysinfo_dot_h::try_collect_info().unwrap();
+//! numa.cross_check(&info, info.total_ram / 20).unwrap(); // free memory moves in between
+//! ```
+use std::str::FromStr;
+
+use crate::{SysinfoError, SystemInfo};
+
+const ROOT: &str = "/sys/devices/system/node";
+
+/// One node's `meminfo`, in bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct NodeMemory {
+    /// The node number, `N` in `nodeN`
+    pub id: u32,
+    /// `MemTotal`
+    pub total: u64,
+    /// `MemFree`
+    pub free: u64,
+}
+
+impl NodeMemory {
+    /// Reads `/sys/devices/system/node/node{id}/meminfo`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there's no such node or its file doesn't parse.
+    pub fn read(id: u32) -> Result<Self, SysinfoError> {
+        crate::read_proc(&format!("{ROOT}/node{id}/meminfo"))?.parse()
+    }
+
+    /// `total - free`, what the kernel reports as `MemUsed`. Includes the page cache, like
+    /// `sysinfo(2)`'s idea of used memory.
+    #[must_use] pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.free)
+    }
+}
+
+impl FromStr for NodeMemory {
+    type Err = SysinfoError;
+
+    /// Parses the contents of a node's `meminfo`, where every line starts with `Node N`.
+    fn from_str(s: &str) -> Result<Self, SysinfoError> {
+        let (mut id, mut total, mut free) = (None, None, None);
+        for line in s.lines() {
+            let invalid = || SysinfoError::parse("node meminfo", line);
+            let Some(rest) = line.strip_prefix("Node ") else { continue };
+            let (n, rest) = rest.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let Some((key, value)) = rest.split_once(':') else { continue };
+            let field = match key.trim() {
+                "MemTotal" => &mut total,
+                "MemFree" => &mut free,
+                _ => continue,
+            };
+            let kib: u64 = value.trim().trim_end_matches("kB").trim().parse().map_err(|_| invalid())?;
+            *field = Some(kib.saturating_mul(1024));
+            id = Some(n.parse().map_err(|_| invalid())?);
+        }
+        let missing = |what: &str| SysinfoError::missing("node meminfo", what);
+        Ok(NodeMemory {
+            id: id.ok_or_else(|| missing("node number"))?,
+            total: total.ok_or_else(|| missing("MemTotal"))?,
+            free: free.ok_or_else(|| missing("MemFree"))?,
+        })
+    }
+}
+
+/// Every online node.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NumaMemory {
+    /// By node number
+    pub nodes: Vec<NodeMemory>,
+}
+
+impl NumaMemory {
+    /// Reads the nodes listed in `/sys/devices/system/node/online`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the kernel has no NUMA support, or a node's file can't be read or
+    /// doesn't parse.
+    pub fn read() -> Result<Self, SysinfoError> {
+        let online = parse_list(&crate::read_proc(&format!("{ROOT}/online"))?)?;
+        let nodes = online.into_iter().map(NodeMemory::read).collect::<Result<_, _>>()?;
+        Ok(Self { nodes })
+    }
+
+    /// `MemTotal` over all nodes, the same as `total_ram`.
+    #[must_use] pub fn total(&self) -> u64 {
+        self.nodes.iter().map(|node| node.total).sum()
+    }
+
+    /// `MemFree` over all nodes, about `free_ram`.
+    #[must_use] pub fn free(&self) -> u64 {
+        self.nodes.iter().map(|node| node.free).sum()
+    }
+
+    /// [`total`](Self::total) minus [`free`](Self::free).
+    #[must_use] pub fn used(&self) -> u64 {
+        self.total().saturating_sub(self.free())
+    }
+
+    /// Checks the nodes add up to `info`'s totals, `total_ram` and `free_ram` each within
+    /// `tolerance` bytes. Free memory moves between reading the nodes and collecting `info`, so
+    /// a tolerance of 0 only works on an idle machine; the totals only differ if memory was
+    /// hotplugged in between, or a node went missing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SysinfoError::Mismatch`] saying which of the two is off, and by how much.
+    pub fn cross_check(&self, info: &SystemInfo, tolerance: u64) -> Result<(), SysinfoError> {
+        for (name, nodes, whole) in [("total", self.total(), info.total_ram), ("free", self.free(), info.free_ram)] {
+            if nodes.abs_diff(whole) > tolerance {
+                return Err(SysinfoError::Mismatch(format!("{} nodes have {nodes} bytes {name}, sysinfo says {whole}", self.nodes.len())));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A sysfs list like `0-3,8,10-11`, the format of `online`.
+fn parse_list(list: &str) -> Result<Vec<u32>, SysinfoError> {
+    let invalid = || SysinfoError::parse(format!("{ROOT}/online"), list.trim());
+    let mut ids = Vec::new();
+    for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+        let (first, last) = range.split_once('-').unwrap_or((range, range));
+        let (first, last): (u32, u32) = (first.parse().map_err(|_| invalid())?, last.parse().map_err(|_| invalid())?);
+        if first > last {
+            return Err(invalid());
+        }
+        ids.extend(first..=last);
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses() {
+        let node: NodeMemory = "Node 1 MemTotal:       32768 kB\nNode 1 MemFree:         8192 kB\nNode 1 MemUsed:        24576 kB\nNode 1 Active(file):    1024 kB\n"
+            .parse()
+            .unwrap();
+        assert_eq!(node, NodeMemory { id: 1, total: 32 << 20, free: 8 << 20 });
+        assert_eq!(node.used(), 24 << 20);
+        assert!("Node 1 MemTotal: 32768 kB\n".parse::<NodeMemory>().is_err());
+        assert!("Node x MemTotal: 1 kB\nNode x MemFree: 1 kB\n".parse::<NodeMemory>().is_err());
+
+        assert_eq!(parse_list("0-2,4,6-7\n").unwrap(), [0, 1, 2, 4, 6, 7]);
+        assert_eq!(parse_list("0\n").unwrap(), [0]);
+        assert!(parse_list("2-1").is_err() && parse_list("a").is_err());
+    }
+
+    #[test]
+    fn adds_up_to_sysinfo() {
+        let numa = NumaMemory::read().unwrap();
+        assert_eq!(numa.nodes[0].id, 0);
+        assert_eq!(numa.used(), numa.nodes.iter().map(NodeMemory::used).sum::<u64>());
+        let info = crate::try_collect_info().unwrap();
+        numa.cross_check(&info, info.total_ram / 10).unwrap();
+        let info = SystemInfo { total_ram: numa.total() + (1 << 30), ..info };
+        assert!(matches!(numa.cross_check(&info, 1 << 20), Err(SysinfoError::Mismatch(e)) if e.contains("total")));
+    }
+}
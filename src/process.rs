@@ -0,0 +1,189 @@
+//! One process out of `/proc/[pid]`: its footprint, to go next to the system-wide numbers (`procs`
+//! only counts them).
+//!
+//! ```rust
+//! use sysinfo_dot_h::process::Process;
+//!
+//! let me = Process::current().unwrap();
+//! let info = sysinfo_dot_h::try_collect_info().unwrap();
+//! println!("{} ({}) uses {} of {} bytes, running for {:?}", me.name, me.pid, me.rss, info.total_ram, me.age(&info));
+//! ```
+use std::time::Duration;
+
+use crate::{SysinfoError, SystemInfo};
+
+/// What the process is doing, the third field of `/proc/[pid]/stat`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProcessState {
+    /// `R`, on a CPU or waiting for one
+    Running,
+    /// `S`, waiting for something, interruptibly
+    Sleeping,
+    /// `D`, waiting uninterruptibly, usually for I/O
+    DiskSleep,
+    /// `Z`, exited and not reaped yet
+    Zombie,
+    /// `T`, stopped by a signal
+    Stopped,
+    /// `t`, stopped by a debugger
+    TracingStop,
+    /// `X`, about to disappear
+    Dead,
+    /// `I`, an idle kernel thread
+    Idle,
+    /// Any other letter, some kernels have had more
+    Other(char),
+}
+
+impl From<char> for ProcessState {
+    fn from(state: char) -> Self {
+        match state {
+            'R' => Self::Running,
+            'S' => Self::Sleeping,
+            'D' => Self::DiskSleep,
+            'Z' => Self::Zombie,
+            'T' => Self::Stopped,
+            't' => Self::TracingStop,
+            'X' | 'x' => Self::Dead,
+            'I' => Self::Idle,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// A process, as of when it was read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Process {
+    pub pid: u32,
+    /// The executable's name, cut at 15 bytes by the kernel
+    pub name: String,
+    pub state: ProcessState,
+    pub threads: u64,
+    /// Bytes of RAM in use (`VmRSS`), 0 for kernel threads
+    pub rss: u64,
+    /// Bytes of address space mapped (`VmSize`), 0 for kernel threads
+    pub vm_size: u64,
+    /// When it started, counted from boot like the uptime
+    pub start_time: Duration,
+}
+
+impl Process {
+    /// Reads the calling process.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `/proc/self` can't be read or doesn't parse.
+    pub fn current() -> Result<Self, SysinfoError> {
+        Self::read("/proc/self")
+    }
+
+    /// Reads the process `pid`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there's no such process (anymore) or its files don't parse.
+    pub fn from_pid(pid: u32) -> Result<Self, SysinfoError> {
+        Self::read(&format!("/proc/{pid}"))
+    }
+
+    fn read(dir: &str) -> Result<Self, SysinfoError> {
+        let status = crate::read_proc(&format!("{dir}/status"))?;
+        let stat = crate::read_proc(&format!("{dir}/stat"))?;
+        Self::parse(&status, &stat, clock_ticks())
+    }
+
+    /// Parses the contents of `/proc/[pid]/status` and `/proc/[pid]/stat`, with `ticks_per_second`
+    /// the `USER_HZ` the start time is counted in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `stat` is cut short or a number isn't one.
+    pub fn parse(status: &str, stat: &str, ticks_per_second: u64) -> Result<Self, SysinfoError> {
+        let invalid = || SysinfoError::parse("/proc/[pid]/stat", stat.trim_end());
+        // the name is in parentheses and can have anything in it, parentheses and spaces included
+        let (pid, rest) = stat.split_once(" (").ok_or_else(invalid)?;
+        let (name, rest) = rest.rsplit_once(") ").ok_or_else(invalid)?;
+        // the fields after the name, starting with the third: state
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        let field = |n: usize| fields.get(n - 3).copied().ok_or_else(invalid);
+        let number = |n: usize| field(n)?.parse::<u64>().map_err(|_| invalid());
+
+        let mut process = Process {
+            pid: pid.trim().parse().map_err(|_| invalid())?,
+            name: name.to_string(),
+            state: field(3)?.chars().next().ok_or_else(invalid)?.into(),
+            threads: number(20)?,
+            rss: 0,
+            vm_size: 0,
+            start_time: ticks(number(22)?, ticks_per_second.max(1)),
+        };
+        for line in status.lines() {
+            let Some((key, value)) = line.split_once(':') else { continue };
+            let bytes = || -> Result<u64, SysinfoError> {
+                let kib = value.trim().trim_end_matches("kB").trim();
+                kib.parse::<u64>().map(|kib| kib * 1024).map_err(|_| SysinfoError::parse("/proc/[pid]/status", line))
+            };
+            match key {
+                "VmRSS" => process.rss = bytes()?,
+                "VmSize" => process.vm_size = bytes()?,
+                _ => {}
+            }
+        }
+        Ok(process)
+    }
+
+    /// How long it has been running as of `info` (0 if `info` is older than the process).
+    #[must_use] pub fn age(&self, info: &SystemInfo) -> Duration {
+        info.uptime.saturating_sub(self.start_time)
+    }
+}
+
+/// `count` ticks of `1 / per_second` seconds.
+fn ticks(count: u64, per_second: u64) -> Duration {
+    Duration::from_secs(count / per_second) + Duration::from_nanos((count % per_second) * 1_000_000_000 / per_second)
+}
+
+/// `USER_HZ`, what `/proc` counts times in.
+fn clock_ticks() -> u64 {
+    #[cfg(target_os = "linux")]
+    if let Ok(hz @ 1..) = crate::sysconf::clock_ticks() {
+        return hz;
+    }
+    100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &str = "Name:\tmy (weird) daemon\nState:\tS (sleeping)\nVmPeak:\t  20000 kB\nVmSize:\t   16384 kB\nVmRSS:\t    4096 kB\nThreads:\t3\n";
+    const STAT: &str = "4242 (my (weird) daemon) S 1 4242 4242 0 -1 4194560 1000 0 0 0 12 5 0 0 20 0 3 0 12345 16777216 1024 18446744073709551615\n";
+
+    #[test]
+    fn parses() {
+        let process = Process::parse(STATUS, STAT, 100).unwrap();
+        assert_eq!((process.pid, process.name.as_str()), (4242, "my (weird) daemon"));
+        assert_eq!((process.state, process.threads), (ProcessState::Sleeping, 3));
+        assert_eq!((process.rss, process.vm_size), (4 << 20, 16 << 20));
+        assert_eq!(process.start_time, Duration::from_millis(123450));
+        let info = SystemInfo { uptime: Duration::from_secs(200), ..Default::default() };
+        assert_eq!(process.age(&info), Duration::from_millis(76550));
+
+        let kthread = Process::parse("Name:\tkworker/0:1\n", "7 (kworker/0:1) I 2 0 0 0 -1 0 0 0 0 0 0 0 0 0 20 0 1 0 5\n", 100).unwrap();
+        assert_eq!((kthread.state, kthread.rss), (ProcessState::Idle, 0));
+        assert!(Process::parse(STATUS, "4242 (cut) S 1 2\n", 100).is_err());
+        assert!(Process::parse("VmRSS:\tlots kB\n", STAT, 100).is_err());
+    }
+
+    #[test]
+    fn reads_itself() {
+        let me = Process::current().unwrap();
+        assert!(me.rss > 0 && me.threads > 0);
+        #[cfg(target_os = "linux")]
+        {
+            assert_eq!(me.pid, std::process::id());
+            assert_eq!(Process::from_pid(me.pid).unwrap().start_time, me.start_time);
+            assert!(Process::from_pid(u32::MAX).is_err());
+        }
+    }
+}
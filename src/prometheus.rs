@@ -0,0 +1,222 @@
+//! Snapshots as Prometheus gauges, in the text exposition format.
+//!
+//! The names and meanings are node_exporter's (`node_load1`, `node_memory_MemTotal_bytes`,
+//! `node_boot_time_seconds`, ...), so dashboards and alerts written for it keep working.
+//!
+//! ```rust
+//! use sysinfo_dot_h::prometheus;
+//!
+//! let text = prometheus::encode(&prometheus::gauges(&sysinfo_dot_h::collect_info()));
+//! assert!(text.contains("\nnode_load1 "));
+//! ```
+//!
+//! A scrape endpoint only needs [`scrape`] (or [`scrape_openmetrics`], for scrapers asking for
+//! `application/openmetrics-text`) and an HTTP server to serve it with [`CONTENT_TYPE`].
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::meminfo::MemInfo;
+use crate::{SysinfoError, SystemInfo};
+
+/// The content type of [`encode`]'s output.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// The content type of [`encode_openmetrics`]'s output.
+pub const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// One gauge and its current value.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Gauge {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub value: f64,
+}
+
+impl Gauge {
+    const fn new(name: &'static str, help: &'static str, value: f64) -> Self {
+        Self { name, help, value }
+    }
+}
+
+/// Every field of `info` as a gauge.
+#[must_use] pub fn gauges(info: &SystemInfo) -> Vec<Gauge> {
+    // node_exporter exports when the machine booted rather than for how long it's been up
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    vec![
+        Gauge::new("node_boot_time_seconds", "Node boot time, in unixtime.", now.saturating_sub(info.uptime).as_secs() as f64),
+        Gauge::new("node_load1", "1m load average.", info.loads[0]),
+        Gauge::new("node_load5", "5m load average.", info.loads[1]),
+        Gauge::new("node_load15", "15m load average.", info.loads[2]),
+        Gauge::new("node_memory_MemTotal_bytes", "Memory information field MemTotal_bytes.", info.total_ram as f64),
+        Gauge::new("node_memory_MemFree_bytes", "Memory information field MemFree_bytes.", info.free_ram as f64),
+        Gauge::new("node_memory_Shmem_bytes", "Memory information field Shmem_bytes.", info.shared_ram as f64),
+        Gauge::new("node_memory_Buffers_bytes", "Memory information field Buffers_bytes.", info.buffer_ram as f64),
+        Gauge::new("node_memory_SwapTotal_bytes", "Memory information field SwapTotal_bytes.", info.total_swap as f64),
+        Gauge::new("node_memory_SwapFree_bytes", "Memory information field SwapFree_bytes.", info.free_swap as f64),
+        Gauge::new("node_memory_HighTotal_bytes", "Memory information field HighTotal_bytes.", info.total_high as f64),
+        Gauge::new("node_memory_HighFree_bytes", "Memory information field HighFree_bytes.", info.free_high as f64),
+        // sysinfo(2)'s procs is the kernel's nr_threads, what the processes collector calls threads
+        Gauge::new("node_processes_threads", "Allocated threads in system.", f64::from(info.procs)),
+    ]
+}
+
+/// The `/proc/meminfo` only gauges, to go along with [`gauges`].
+#[must_use] pub fn meminfo_gauges(meminfo: &MemInfo) -> Vec<Gauge> {
+    let mut gauges = vec![
+        Gauge::new("node_memory_Cached_bytes", "Memory information field Cached_bytes.", meminfo.cached as f64),
+    ];
+    if let Some(available) = meminfo.available {
+        gauges.push(Gauge::new("node_memory_MemAvailable_bytes", "Memory information field MemAvailable_bytes.", available as f64));
+    }
+    gauges
+}
+
+/// Renders `gauges` in the text exposition format.
+#[must_use] pub fn encode(gauges: &[Gauge]) -> String {
+    let mut out = String::new();
+    write_gauges(&mut out, gauges, &[]);
+    out
+}
+
+/// Like [`encode`], with `labels` (`("host", "web-01")`, ...) on every sample. Values can be
+/// anything, they're escaped.
+///
+/// # Errors
+///
+/// Returns [`SysinfoError::Invalid`] if a label name isn't one, see [`check_label_name`].
+pub fn encode_with_labels(gauges: &[Gauge], labels: &[(&str, &str)]) -> Result<String, SysinfoError> {
+    labels.iter().try_for_each(|(name, _)| check_label_name(name))?;
+    let mut out = String::new();
+    write_gauges(&mut out, gauges, labels);
+    Ok(out)
+}
+
+/// [`encode_with_labels`] in the OpenMetrics text format, which is the same gauges terminated
+/// by `# EOF`.
+///
+/// # Errors
+///
+/// Same as [`encode_with_labels`].
+pub fn encode_openmetrics(gauges: &[Gauge], labels: &[(&str, &str)]) -> Result<String, SysinfoError> {
+    let mut out = encode_with_labels(gauges, labels)?;
+    out.push_str("# EOF\n");
+    Ok(out)
+}
+
+/// Checks `name` can be a label name: `[a-zA-Z_][a-zA-Z0-9_]*`, and not starting with `__`,
+/// which is reserved for Prometheus' own.
+///
+/// # Errors
+///
+/// Returns [`SysinfoError::Invalid`], saying what's wrong with it.
+pub fn check_label_name(name: &str) -> Result<(), SysinfoError> {
+    let valid = name.chars().enumerate().all(|(i, c)| c == '_' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit()));
+    if name.is_empty() || !valid {
+        Err(SysinfoError::Invalid(format!("invalid label name {name:?}, expected letters, digits and _ not starting with a digit")))
+    } else if name.starts_with("__") {
+        Err(SysinfoError::Invalid(format!("invalid label name {name:?}, names starting with __ are reserved")))
+    } else {
+        Ok(())
+    }
+}
+
+/// A fresh snapshot, and `/proc/meminfo` when it can be read, as [`encode`]d gauges: a whole
+/// scrape.
+///
+/// # Errors
+///
+/// Returns an error if there's no snapshot. A missing `/proc/meminfo` only leaves its gauges out.
+pub fn scrape() -> Result<String, SysinfoError> {
+    Ok(encode(&scrape_gauges()?))
+}
+
+/// [`scrape`] in the OpenMetrics text format.
+///
+/// # Errors
+///
+/// Same as [`scrape`].
+pub fn scrape_openmetrics() -> Result<String, SysinfoError> {
+    encode_openmetrics(&scrape_gauges()?, &[])
+}
+
+fn scrape_gauges() -> Result<Vec<Gauge>, SysinfoError> {
+    let mut all = gauges(&crate::try_collect_info()?);
+    if let Ok(meminfo) = MemInfo::read() {
+        all.extend(meminfo_gauges(&meminfo));
+    }
+    Ok(all)
+}
+
+fn write_gauges(out: &mut String, gauges: &[Gauge], labels: &[(&str, &str)]) {
+    let labels = if labels.is_empty() {
+        String::new()
+    } else {
+        let labels: Vec<String> = labels.iter().map(|(name, value)| format!("{name}=\"{}\"", escape(value))).collect();
+        format!("{{{}}}", labels.join(","))
+    };
+    for gauge in gauges {
+        // writing to a String can't fail
+        let _ = writeln!(out, "# HELP {} {}", gauge.name, gauge.help);
+        let _ = writeln!(out, "# TYPE {} gauge", gauge.name);
+        let _ = writeln!(out, "{}{labels} {}", gauge.name, gauge.value);
+    }
+}
+
+fn escape(label_value: &str) -> String {
+    label_value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_format() {
+        let info = SystemInfo { loads: [0.5, 0.25, 0.125], free_ram: 4096, procs: 12, ..Default::default() };
+        let text = encode(&gauges(&info));
+        assert!(text.starts_with("# HELP node_boot_time_seconds Node boot time, in unixtime.\n# TYPE node_boot_time_seconds gauge\nnode_boot_time_seconds "));
+        assert!(text.contains("\nnode_load1 0.5\n"));
+        assert!(text.contains("\nnode_memory_MemFree_bytes 4096\n"));
+        assert!(text.ends_with("\nnode_processes_threads 12\n"));
+    }
+
+    #[test]
+    fn labels() {
+        let gauges = gauges(&SystemInfo { procs: 12, ..Default::default() });
+        let text = encode_with_labels(&gauges, &[("host", "web-01"), ("note", "a \"b\"\n")]).unwrap();
+        assert!(text.ends_with("\nnode_processes_threads{host=\"web-01\",note=\"a \\\"b\\\"\\n\"} 12\n"));
+        for invalid in ["", "1host", "host-name", "ho\"st", "hôst", "__name__"] {
+            assert!(encode_with_labels(&gauges, &[(invalid, "x")]).is_err(), "{invalid}");
+            assert!(encode_openmetrics(&gauges, &[(invalid, "x")]).is_err(), "{invalid}");
+        }
+        assert!(check_label_name("_dc2").is_ok());
+    }
+
+    #[test]
+    fn openmetrics_and_scrapes() {
+        let gauges = gauges(&SystemInfo { procs: 12, ..Default::default() });
+        let text = encode_openmetrics(&gauges, &[("host", "web-01")]).unwrap();
+        assert!(text.ends_with("\nnode_processes_threads{host=\"web-01\"} 12\n# EOF\n"));
+        assert_eq!(text.trim_end_matches("# EOF\n"), encode_with_labels(&gauges, &[("host", "web-01")]).unwrap());
+
+        let scrape = scrape().unwrap();
+        assert!(scrape.contains("\nnode_memory_MemTotal_bytes ") && scrape.contains("\nnode_memory_Cached_bytes "));
+        assert!(scrape_openmetrics().unwrap().ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn boot_time() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as f64;
+        let boot = gauges(&SystemInfo { uptime: std::time::Duration::from_secs(3600), ..Default::default() })[0];
+        assert_eq!(boot.name, "node_boot_time_seconds");
+        assert!((now - 3600.0 - boot.value).abs() <= 1.0, "{boot:?}");
+    }
+
+    #[test]
+    fn available_only_when_known() {
+        let old_kernel = MemInfo { total: 1024, ..Default::default() };
+        assert_eq!(meminfo_gauges(&old_kernel).len(), 1);
+        let meminfo = MemInfo { available: Some(2048), ..old_kernel };
+        assert_eq!(meminfo_gauges(&meminfo)[1].value, 2048.0);
+    }
+}
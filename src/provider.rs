@@ -0,0 +1,116 @@
+//! Where snapshots come from, as a trait: the [`sampler`](crate::sampler), [`check`](crate::check)
+//! and [`health`](crate::health) take any [`SysinfoProvider`], so applications can hand them a
+//! fake in their own tests instead of the kernel.
+//!
+//! ```rust
+//! use sysinfo_dot_h::check::{Check, Metric, Status};
+//! use sysinfo_dot_h::provider::{self, SysinfoProvider};
+//! use sysinfo_dot_h::SystemInfo;
+//!
+//! // always low on memory
+//! let fake = provider::from_fn(|| Ok(SystemInfo { total_ram: 100, free_ram: 1, ..Default::default() }));
+//! let report = Check::new("MEM").rule(Metric::FreeRamPercent, "10:", "5:").unwrap().run(&fake);
+//! assert_eq!(report.status(), Status::Critical);
+//! ```
+//!
+//! Code of its own can take one the same way, with [`Backend::Libc`] in production and a
+//! [`MockProvider`](crate::testing::MockProvider) in tests. Downstream crates get the fakes in
+//! their dev-dependencies with the `test-util` feature:
+//!
+//! ```rust
+//! use sysinfo_dot_h::provider::SysinfoProvider;
+//! use sysinfo_dot_h::testing::MockProvider;
+//!
+//! fn swap_in_use(provider: &impl SysinfoProvider) -> bool {
+//!     provider.collect_raw().is_ok_and(|raw| raw.freeswap < raw.totalswap)
+//! }
+//!
+//! let mut raw = sysinfo_dot_h::collect();
+//! (raw.totalswap, raw.freeswap) = (1024, 512);
+//! assert!(swap_in_use(&MockProvider::raw([raw])));
+//! ```
+use std::sync::Arc;
+
+use crate::backend::Backend;
+use crate::{sysinfo, SysinfoError, SystemInfo};
+
+/// Something that produces snapshots. [`Backend`]s are the real ones, [`Backend::Libc`] being
+/// what everything uses by default.
+pub trait SysinfoProvider {
+    /// Takes a snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there's no snapshot to be had, which consumers treat like a failed
+    /// `sysinfo(2)`. Providers failing their own way can return [`SysinfoError::Other`], which a
+    /// `String` converts into.
+    fn collect(&self) -> Result<SystemInfo, SysinfoError>;
+
+    /// [`collect`](Self::collect) as the raw struct, for code written against
+    /// [`try_collect`](crate::try_collect) that wants to take a provider instead.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`collect`](Self::collect).
+    fn collect_raw(&self) -> Result<sysinfo, SysinfoError> {
+        self.collect().map(sysinfo::from)
+    }
+}
+
+impl SysinfoProvider for Backend {
+    fn collect(&self) -> Result<SystemInfo, SysinfoError> {
+        Backend::collect(*self)
+    }
+}
+
+impl<P: SysinfoProvider + ?Sized> SysinfoProvider for &P {
+    fn collect(&self) -> Result<SystemInfo, SysinfoError> {
+        (**self).collect()
+    }
+}
+
+impl<P: SysinfoProvider + ?Sized> SysinfoProvider for Box<P> {
+    fn collect(&self) -> Result<SystemInfo, SysinfoError> {
+        (**self).collect()
+    }
+}
+
+impl<P: SysinfoProvider + ?Sized> SysinfoProvider for Arc<P> {
+    fn collect(&self) -> Result<SystemInfo, SysinfoError> {
+        (**self).collect()
+    }
+}
+
+/// A provider calling `f`, see [`from_fn`].
+#[derive(Debug, Copy, Clone)]
+pub struct FromFn<F>(F);
+
+/// A provider out of a closure, like [`std::iter::from_fn`].
+pub fn from_fn<F: Fn() -> Result<SystemInfo, SysinfoError>>(f: F) -> FromFn<F> {
+    FromFn(f)
+}
+
+impl<F: Fn() -> Result<SystemInfo, SysinfoError>> SysinfoProvider for FromFn<F> {
+    fn collect(&self) -> Result<SystemInfo, SysinfoError> {
+        (self.0)()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn providers() {
+        fn procs(provider: impl SysinfoProvider) -> u16 {
+            provider.collect().unwrap().procs
+        }
+        let label = String::from("fake");
+        let fake = from_fn(move || Ok(SystemInfo { procs: label.len() as u16, ..Default::default() }));
+        assert_eq!(procs(&fake), 4);
+        let boxed: Box<dyn SysinfoProvider + '_> = Box::new(&fake);
+        assert_eq!(procs(boxed), 4);
+        assert_eq!(procs(Arc::new(fake)), 4);
+        assert!(procs(Backend::Libc) > 0);
+    }
+}
@@ -0,0 +1,265 @@
+//! Pressure stall information from `/proc/pressure` (Linux 4.20+): the share of time tasks were
+//! stalled waiting for cpu, memory or io. Unlike the load average it tells the three apart, and
+//! it reacts within seconds.
+//!
+//! Also available as `sysinfo_dot_h::pressure`, after the directory.
+//!
+//! ```rust
+//! use sysinfo_dot_h::psi::{Pressure, Resource};
+//!
+//! if let Ok(memory) = Pressure::read(Resource::Memory) {
+//!     println!("stalled on memory {:.2}% of the last 10s", memory.some.avg10);
+//! }
+//! ```
+//!
+//! On Linux, a [`Trigger`] has the kernel say when a stall threshold is crossed, instead of
+//! polling the averages:
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use sysinfo_dot_h::psi::{Resource, Stall, Trigger};
+//!
+//! // 150ms of stall within any 2s
+//! let trigger = Trigger::new(Resource::Memory, Stall::Some, Duration::from_millis(150), Duration::from_secs(2)).unwrap();
+//! while trigger.wait(None).unwrap() {
+//!     println!("memory pressure!");
+//! }
+//! ```
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::SysinfoError;
+
+/// What tasks can stall on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Resource {
+    Cpu,
+    Memory,
+    Io,
+}
+
+impl Resource {
+    /// Every resource, in the kernel docs' order.
+    pub const ALL: [Resource; 3] = [Resource::Cpu, Resource::Memory, Resource::Io];
+
+    /// `cpu`, `memory` or `io`, the file name under `/proc/pressure`.
+    #[must_use] pub fn name(self) -> &'static str {
+        match self {
+            Resource::Cpu => "cpu",
+            Resource::Memory => "memory",
+            Resource::Io => "io",
+        }
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// One line of a pressure file. The averages are percentages.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Averages {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    /// Total stall time since boot
+    pub total: Duration,
+}
+
+/// A pressure file.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Pressure {
+    /// Time at least one task was stalled
+    pub some: Averages,
+    /// Time every non-idle task was stalled at once. Missing for cpu before Linux 5.13.
+    pub full: Option<Averages>,
+}
+
+impl Pressure {
+    /// Reads `/proc/pressure/<resource>`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read, which is the case on kernels before 4.20 or
+    /// built without `CONFIG_PSI`, and when booted with `psi=0`.
+    pub fn read(resource: Resource) -> Result<Self, SysinfoError> {
+        crate::read_proc(&format!("/proc/pressure/{resource}"))?.parse()
+    }
+}
+
+impl FromStr for Pressure {
+    type Err = SysinfoError;
+
+    /// Parses `some avg10=0.09 avg60=0.13 avg300=0.11 total=18083266` and the optional `full`
+    /// line. Unknown keys are ignored.
+    fn from_str(s: &str) -> Result<Self, SysinfoError> {
+        let mut some = None;
+        let mut full = None;
+        for line in s.lines() {
+            let Some((kind, values)) = line.split_once(' ') else { continue };
+            let line_of = match kind {
+                "some" => &mut some,
+                "full" => &mut full,
+                _ => continue,
+            };
+            *line_of = Some(parse_averages(values).ok_or_else(|| SysinfoError::parse("/proc/pressure", line))?);
+        }
+        Ok(Self { some: some.ok_or_else(|| SysinfoError::missing("/proc/pressure", "\"some\" line"))?, full })
+    }
+}
+
+/// Which line a [`Trigger`] watches.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Stall {
+    /// At least one task stalled
+    Some,
+    /// Every non-idle task stalled at once
+    Full,
+}
+
+/// A pressure trigger: an open pressure file the kernel wakes up when tasks were stalled for
+/// more than `stall` within a `window`. It's armed for as long as it's alive, dropping it removes
+/// it.
+///
+/// The file descriptor can go into an event loop as it is (`POLLPRI`), through [`AsRawFd`].
+///
+/// [`AsRawFd`]: std::os::fd::AsRawFd
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+pub struct Trigger {
+    file: std::fs::File,
+    path: String,
+}
+
+#[cfg(target_os = "linux")]
+impl Trigger {
+    /// Registers a trigger on `/proc/pressure/<resource>`. The window must be between 500ms and
+    /// 10s, and without `CAP_SYS_RESOURCE` a multiple of 2s (since Linux 6.5).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SysinfoError::Proc`] if the pressure file can't be opened (no PSI, no
+    /// permission), and [`SysinfoError::Invalid`] if the durations are out of range or the
+    /// kernel refuses them anyway.
+    pub fn new(resource: Resource, kind: Stall, stall: Duration, window: Duration) -> Result<Self, SysinfoError> {
+        use std::io::Write;
+
+        if !(Duration::from_millis(500)..=Duration::from_secs(10)).contains(&window) {
+            return Err(SysinfoError::Invalid(format!("a pressure window must be between 500ms and 10s, not {window:?}")));
+        }
+        if stall.is_zero() || stall > window {
+            return Err(SysinfoError::Invalid(format!("a pressure stall must be within the window ({window:?}), not {stall:?}")));
+        }
+        let path = format!("/proc/pressure/{resource}");
+        let mut file = std::fs::OpenOptions::new().read(true).write(true).open(&path).map_err(|e| SysinfoError::proc(&path, e))?;
+        let kind = match kind {
+            Stall::Some => "some",
+            Stall::Full => "full",
+        };
+        // one write, with the terminating nul the kernel wants
+        let request = format!("{kind} {} {}\0", stall.as_micros(), window.as_micros());
+        file.write_all(request.as_bytes()).map_err(|e| SysinfoError::Invalid(format!("{path} refused the trigger: {e}")))?;
+        Ok(Self { file, path })
+    }
+
+    /// Waits until the trigger fires, `true`, or `timeout` passes, `false`. `None` waits forever.
+    /// It fires at most once per window.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SysinfoError::Syscall`] if `poll()` fails, and [`SysinfoError::Proc`] if the
+    /// pressure file went away (its cgroup was removed).
+    pub fn wait(&self, timeout: Option<Duration>) -> Result<bool, SysinfoError> {
+        use std::os::fd::AsRawFd;
+        use std::os::raw::{c_int, c_short, c_ulong};
+
+        #[repr(C)]
+        struct PollFd {
+            fd: c_int,
+            events: c_short,
+            revents: c_short,
+        }
+        extern "C" {
+            fn poll(fds: *mut PollFd, nfds: c_ulong, timeout: c_int) -> c_int;
+        }
+        const POLLPRI: c_short = 0x2;
+        const POLLERR: c_short = 0x8;
+
+        let timeout = timeout.map_or(-1, |timeout| c_int::try_from(timeout.as_millis()).unwrap_or(c_int::MAX));
+        let mut fd = PollFd { fd: self.file.as_raw_fd(), events: POLLPRI, revents: 0 };
+        loop {
+            match unsafe { poll(&mut fd, 1, timeout) } {
+                0 => return Ok(false),
+                1.. if fd.revents & POLLERR != 0 => {
+                    return Err(SysinfoError::proc(&self.path, std::io::Error::new(std::io::ErrorKind::NotFound, "the pressure file went away")));
+                }
+                1.. => return Ok(fd.revents & POLLPRI != 0),
+                _ => {
+                    let error = std::io::Error::last_os_error();
+                    if error.kind() != std::io::ErrorKind::Interrupted {
+                        return Err(SysinfoError::Syscall(error));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl std::os::fd::AsRawFd for Trigger {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+fn parse_averages(values: &str) -> Option<Averages> {
+    let mut averages = Averages::default();
+    for pair in values.split_whitespace() {
+        let (key, value) = pair.split_once('=')?;
+        match key {
+            "avg10" => averages.avg10 = value.parse().ok()?,
+            "avg60" => averages.avg60 = value.parse().ok()?,
+            "avg300" => averages.avg300 = value.parse().ok()?,
+            // microseconds
+            "total" => averages.total = Duration::from_micros(value.parse().ok()?),
+            _ => {}
+        }
+    }
+    Some(averages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses() {
+        let pressure: Pressure = "some avg10=0.09 avg60=0.13 avg300=0.11 total=18083266\n\
+                                  full avg10=0.00 avg60=0.00 avg300=0.03 total=14915474\n".parse().unwrap();
+        assert_eq!(pressure.some.avg60, 0.13);
+        assert_eq!(pressure.some.total, Duration::from_micros(18083266));
+        assert_eq!(pressure.full.map(|full| full.avg300), Some(0.03));
+
+        // cpu before 5.13
+        let cpu: Pressure = "some avg10=4.68 avg60=4.90 avg300=4.55 total=167977666\n".parse().unwrap();
+        assert_eq!((cpu.some.avg10, cpu.full), (4.68, None));
+        assert!("full avg10=0.00 avg60=0.00 avg300=0.00 total=0\n".parse::<Pressure>().is_err());
+        assert!("some avg10=lots\n".parse::<Pressure>().is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn triggers() {
+        let second = Duration::from_secs(1);
+        assert!(Trigger::new(Resource::Memory, Stall::Some, Duration::from_millis(100), Duration::from_millis(100)).is_err());
+        assert!(Trigger::new(Resource::Memory, Stall::Some, 3 * second, 2 * second).is_err());
+        assert!(Trigger::new(Resource::Memory, Stall::Full, Duration::ZERO, 2 * second).is_err());
+        // not every kernel (or container) lets us register one
+        if let Ok(trigger) = Trigger::new(Resource::Memory, Stall::Full, Duration::from_millis(500), 2 * second) {
+            assert!(!trigger.wait(Some(Duration::from_millis(10))).unwrap());
+        }
+    }
+}
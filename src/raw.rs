@@ -0,0 +1,98 @@
+//! `sysinfo(2)` without the C library: the syscall instruction itself, with the error reduced to
+//! the `errno`. Only x86_64 and aarch64 Linux, elsewhere it fails with `ENOSYS`.
+//!
+//! This is what `Backend::Syscall` uses, and it only needs
+//! `core`, so with the `std` feature off (`default-features = false, features =
+//! ["raw-syscall"]`) the crate is `#![no_std]` and can go into static binaries that don't link a
+//! libc at all. What's left then is this module, the [`sysinfo`](struct@sysinfo) struct, the
+//! normalized [`SystemInfo`](crate::SystemInfo) (with its conversions), [`LoadAvg`](crate::LoadAvg)
+//! and [deltas](crate::delta).
+//!
+//! ```rust
+//! use sysinfo_dot_h::{raw, SystemInfo};
+//!
+//! let info = SystemInfo::from(raw::try_collect().unwrap());
+//! assert!(info.total_ram > 0);
+//! ```
+use core::fmt;
+
+use crate::sysinfo;
+
+/// The error number a failed syscall returned, like `errno` (14 is `EFAULT`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Errno(pub i32);
+
+impl fmt::Display for Errno {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "errno {}", self.0)
+    }
+}
+
+/// Calls `sysinfo(2)`, filling `info`.
+///
+/// # Errors
+///
+/// Returns the error number the kernel returned.
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn try_collect_into(info: &mut sysinfo) -> Result<(), Errno> {
+    let ret: isize;
+    // SAFETY: sysinfo(2) only writes a struct sysinfo to the pointer, which points at one
+    unsafe {
+        #[cfg(target_arch = "x86_64")]
+        core::arch::asm!(
+            "syscall",
+            inlateout("rax") 99isize => ret, // __NR_sysinfo
+            in("rdi") info as *mut sysinfo,
+            lateout("rcx") _,
+            lateout("r11") _,
+            options(nostack),
+        );
+        #[cfg(target_arch = "aarch64")]
+        core::arch::asm!(
+            "svc 0",
+            in("x8") 179isize, // __NR_sysinfo
+            inlateout("x0") info as *mut sysinfo => ret,
+            options(nostack),
+        );
+    }
+    match ret {
+        0 => Ok(()),
+        // the kernel returns -errno, which always fits
+        _ => Err(Errno(-ret as i32)),
+    }
+}
+
+/// Calls `sysinfo(2)`, filling `info`.
+///
+/// # Errors
+///
+/// Always `ENOSYS` (38) here, there's no syscall to make.
+#[cfg(not(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+pub fn try_collect_into(_info: &mut sysinfo) -> Result<(), Errno> {
+    Err(Errno(38))
+}
+
+/// [`try_collect_into`] a fresh struct.
+///
+/// # Errors
+///
+/// Returns the error number the kernel returned.
+pub fn try_collect() -> Result<sysinfo, Errno> {
+    // SAFETY: all zeroes is a valid struct sysinfo
+    let mut info: sysinfo = unsafe { core::mem::zeroed() };
+    try_collect_into(&mut info)?;
+    Ok(info)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agrees_with_libc() {
+        let raw = try_collect().unwrap();
+        let libc = crate::compat::collect();
+        assert_eq!((raw.totalram, raw.mem_unit), (libc.totalram, libc.mem_unit));
+        assert_eq!(Errno(14).to_string(), "errno 14");
+    }
+}
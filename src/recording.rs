@@ -0,0 +1,331 @@
+//! Recorded snapshots, for reproducing a machine's state somewhere else.
+//!
+//! A recording is a text file with one JSON object per line: the
+//! [`to_json`](SystemInfo::to_json) snapshot with an extra `elapsed_ms` member holding the time
+//! since the recording started.
+//!
+//! ```text
+//! {"elapsed_ms":0,"uptime":3011,"loads":[0.68,0.71,0.58],...}
+//! {"elapsed_ms":1000,"uptime":3012,"loads":[0.66,0.71,0.58],...}
+//! ```
+//!
+//! ```rust
+//! use sysinfo_dot_h::recording::{self, Recorder};
+//!
+//! let mut recorder = Recorder::new(Vec::new());
+//! recorder.record(&sysinfo_dot_h::collect_info()).unwrap();
+//!
+//! let samples = recording::read(&recorder.into_inner()[..]).unwrap();
+//! assert_eq!(samples.len(), 1);
+//! ```
+//!
+//! As providers, [`RecordingProvider`] writes down whatever another provider returns (a
+//! "memory trace" for a bug report), and [`ReplayProvider`] plays it back to the code under
+//! test:
+//!
+//! ```rust
+//! use sysinfo_dot_h::backend::Backend;
+//! use sysinfo_dot_h::provider::SysinfoProvider;
+//! use sysinfo_dot_h::recording::{self, RecordingProvider, ReplayProvider};
+//!
+//! let recording = RecordingProvider::new(Backend::Libc, Vec::new());
+//! let live = recording.collect().unwrap();
+//!
+//! let replay = ReplayProvider::new(recording::read(&recording.into_writer()[..]).unwrap());
+//! assert_eq!(replay.collect().unwrap(), live);
+//! ```
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::clock::{self, Clock, SystemClock};
+use crate::provider::SysinfoProvider;
+use crate::{SysinfoError, SystemInfo};
+
+/// One line of a recording.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Recorded {
+    /// Time since the recording started
+    pub elapsed: Duration,
+    pub info: SystemInfo,
+}
+
+/// Writes a recording.
+#[derive(Debug)]
+pub struct Recorder<W> {
+    writer: W,
+    start: Option<Instant>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<W: Write> Recorder<W> {
+    /// A recorder writing to `writer`. The clock starts with the first sample.
+    pub fn new(writer: W) -> Self {
+        Self { writer, start: None, clock: clock::system() }
+    }
+
+    /// Measures the time since the first sample with `clock` instead of the system's.
+    #[must_use] pub fn clock<C: Clock + 'static>(self, clock: C) -> Self {
+        Self { clock: Arc::new(clock), ..self }
+    }
+
+    /// Appends `info`, stamped with the time since the first sample, and flushes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn record(&mut self, info: &SystemInfo) -> io::Result<()> {
+        let start = *self.start.get_or_insert_with(|| self.clock.now());
+        let elapsed = self.clock.now().duration_since(start);
+        let line = Recorded { elapsed, info: *info }.to_json();
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+
+    /// Gets the writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl Recorded {
+    /// The line for this sample, without the newline.
+    #[must_use] pub fn to_json(&self) -> String {
+        let json = self.info.to_json();
+        format!("{{\"elapsed_ms\":{},{}", self.elapsed.as_millis(), &json[1..])
+    }
+
+    /// Parses a line of a recording.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SysinfoError::Missing`] if `elapsed_ms` or a snapshot field is missing, and
+    /// [`SysinfoError::Parse`] if one isn't a number.
+    pub fn from_json(line: &str) -> Result<Self, SysinfoError> {
+        let missing = || SysinfoError::missing("the recording", "\"elapsed_ms\"");
+        let start = line.find("\"elapsed_ms\"").ok_or_else(missing)? + "\"elapsed_ms\"".len();
+        let rest = line[start..].trim_start().strip_prefix(':').ok_or_else(missing)?.trim_start();
+        let millis = rest[..rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len())]
+            .parse()
+            .map_err(|_| SysinfoError::parse("elapsed_ms", line))?;
+        Ok(Self { elapsed: Duration::from_millis(millis), info: SystemInfo::from_json(line)? })
+    }
+}
+
+/// Reads a whole recording. Blank lines are skipped.
+///
+/// # Errors
+///
+/// Returns [`SysinfoError::Proc`] if reading fails, and [`SysinfoError::Parse`] (with the line
+/// number) if a line isn't a recorded sample.
+pub fn read<R: BufRead>(reader: R) -> Result<Vec<Recorded>, SysinfoError> {
+    read_named(reader, "the recording")
+}
+
+fn read_named<R: BufRead>(reader: R, name: &str) -> Result<Vec<Recorded>, SysinfoError> {
+    let mut samples = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| SysinfoError::proc(name, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let at = || format!("{name} line {}", number + 1);
+        samples.push(Recorded::from_json(&line).map_err(|e| match e {
+            SysinfoError::Missing { what, .. } => SysinfoError::missing(at(), what),
+            SysinfoError::Parse { file, line } => SysinfoError::parse(format!("{file} on {}", at()), line),
+            e => e,
+        })?);
+    }
+    Ok(samples)
+}
+
+/// [`read`]s the recording at `path`.
+///
+/// # Errors
+///
+/// Returns the errors of [`read`], with `path` instead of "the recording", and
+/// [`SysinfoError::Proc`] if the file can't be opened.
+pub fn open(path: &Path) -> Result<Vec<Recorded>, SysinfoError> {
+    let name = path.display().to_string();
+    let file = std::fs::File::open(path).map_err(|e| SysinfoError::proc(name.as_str(), e))?;
+    read_named(io::BufReader::new(file), &name)
+}
+
+/// A provider passing on `inner`'s snapshots and recording them to a writer. Failed snapshots
+/// aren't recorded.
+#[derive(Debug)]
+pub struct RecordingProvider<P, W> {
+    inner: P,
+    recorder: Mutex<Recorder<W>>,
+}
+
+impl<P: SysinfoProvider, W: Write> RecordingProvider<P, W> {
+    /// Records `inner` to `writer`.
+    pub fn new(inner: P, writer: W) -> Self {
+        Self::with_recorder(inner, Recorder::new(writer))
+    }
+
+    /// Records `inner` with `recorder`, one with a [`clock`](Recorder::clock) for example.
+    pub fn with_recorder(inner: P, recorder: Recorder<W>) -> Self {
+        Self { inner, recorder: Mutex::new(recorder) }
+    }
+
+    /// Gets the writer back.
+    pub fn into_writer(self) -> W {
+        self.recorder.into_inner().unwrap_or_else(PoisonError::into_inner).into_inner()
+    }
+}
+
+impl<P: SysinfoProvider, W: Write> SysinfoProvider for RecordingProvider<P, W> {
+    /// `inner`'s snapshot, or an error if it couldn't be recorded: a trace with holes in it
+    /// would be misleading.
+    fn collect(&self) -> Result<SystemInfo, SysinfoError> {
+        let info = self.inner.collect()?;
+        let mut recorder = self.recorder.lock().unwrap_or_else(PoisonError::into_inner);
+        recorder.record(&info).map_err(|e| SysinfoError::Other(format!("can't write the recording: {e}")))?;
+        Ok(info)
+    }
+}
+
+/// A provider playing a recording back, staying on the last sample once it's over.
+///
+/// By default every call gets the next sample, which keeps tests deterministic;
+/// [`timed`](Self::timed) follows the recorded timing instead.
+#[derive(Debug)]
+pub struct ReplayProvider {
+    samples: Arc<[Recorded]>,
+    /// The clock followed, if timed
+    timed: Option<Arc<dyn Clock>>,
+    /// The next sample, or when the first one was asked for if `timed`
+    position: Mutex<(usize, Option<Instant>)>,
+}
+
+impl ReplayProvider {
+    /// Plays `samples`.
+    pub fn new(samples: impl Into<Arc<[Recorded]>>) -> Self {
+        Self { samples: samples.into(), timed: None, position: Mutex::new((0, None)) }
+    }
+
+    /// Plays the recording at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it can't be [`open`]ed.
+    pub fn open(path: &Path) -> Result<Self, SysinfoError> {
+        Ok(Self::new(open(path)?))
+    }
+
+    /// Returns the sample that was current as long after the recording started as it's been
+    /// since the first call, whatever the sampler's interval.
+    #[must_use] pub fn timed(self) -> Self {
+        self.timed_by(SystemClock)
+    }
+
+    /// Like [`timed`](Self::timed), going by `clock`.
+    #[must_use] pub fn timed_by<C: Clock + 'static>(self, clock: C) -> Self {
+        Self { timed: Some(Arc::new(clock)), ..self }
+    }
+}
+
+impl SysinfoProvider for ReplayProvider {
+    fn collect(&self) -> Result<SystemInfo, SysinfoError> {
+        if self.samples.is_empty() {
+            return Err(SysinfoError::missing("the recording", "samples"));
+        }
+        let mut position = self.position.lock().unwrap_or_else(PoisonError::into_inner);
+        let (next, start) = &mut *position;
+        let sample = if let Some(clock) = &self.timed {
+            let now = clock.now();
+            playback(&self.samples, now.duration_since(*start.get_or_insert(now)))
+        } else {
+            let sample = &self.samples[(*next).min(self.samples.len() - 1)];
+            *next += 1;
+            sample
+        };
+        Ok(sample.info)
+    }
+}
+
+/// The sample that was current `elapsed` into the recording.
+fn playback(samples: &[Recorded], elapsed: Duration) -> &Recorded {
+    let played = samples.partition_point(|sample| sample.elapsed <= elapsed);
+    &samples[played.saturating_sub(1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut recorder = Recorder::new(Vec::new());
+        let info = SystemInfo { uptime: Duration::from_secs(5), loads: [0.5, 0.25, 0.125], free_ram: 4096, procs: 7, ..Default::default() };
+        recorder.record(&info).unwrap();
+        recorder.record(&SystemInfo { procs: 8, ..info }).unwrap();
+
+        let text = String::from_utf8(recorder.into_inner()).unwrap();
+        assert!(text.starts_with("{\"elapsed_ms\":0,\"uptime\":5,\"loads\":[0.5,0.25,0.125],"));
+
+        let samples = read(text.as_bytes()).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].info, info);
+        assert_eq!(samples[1].info.procs, 8);
+        assert!(samples[1].elapsed >= samples[0].elapsed);
+    }
+
+    #[test]
+    fn rejects_plain_snapshots() {
+        let snapshot = SystemInfo::default().to_json();
+        assert_eq!(read(snapshot.as_bytes()).unwrap_err().to_string(), "no \"elapsed_ms\" in the recording line 1");
+        let path = Path::new("/nonexistent/recording.jsonl");
+        assert!(matches!(open(path), Err(SysinfoError::Proc { path, .. }) if path == "/nonexistent/recording.jsonl"));
+    }
+
+    #[test]
+    fn record_and_replay_providers() {
+        let mock = crate::testing::MockProvider::results([
+            Ok(SystemInfo { procs: 1, ..Default::default() }),
+            Err("boom".into()),
+            Ok(SystemInfo { procs: 2, ..Default::default() }),
+        ]);
+        let recording = RecordingProvider::new(mock, Vec::new());
+        let procs: Vec<Option<u16>> = (0..3).map(|_| recording.collect().ok().map(|info| info.procs)).collect();
+        assert_eq!(procs, [Some(1), None, Some(2)]);
+
+        let samples = read(&recording.into_writer()[..]).unwrap();
+        let replay = ReplayProvider::new(samples);
+        let procs: Vec<u16> = (0..3).map(|_| replay.collect().unwrap().procs).collect();
+        assert_eq!(procs, [1, 2, 2]);
+        assert!(ReplayProvider::new(Vec::new()).collect().is_err());
+    }
+
+    #[test]
+    fn follows_the_recorded_timing() {
+        let samples: Vec<Recorded> = (0..3u16)
+            .map(|i| Recorded { elapsed: Duration::from_secs(u64::from(i)), info: SystemInfo { procs: i, ..Default::default() } })
+            .collect();
+        let procs = |secs: f64| playback(&samples, Duration::from_secs_f64(secs)).info.procs;
+        assert_eq!([procs(0.0), procs(0.5), procs(1.0), procs(2.5), procs(60.0)], [0, 0, 1, 2, 2]);
+        assert_eq!(ReplayProvider::new(samples.clone()).timed().collect().unwrap().procs, 0);
+
+        let clock = crate::testing::MockClock::new();
+        let replay = ReplayProvider::new(samples).timed_by(clock.clone());
+        assert_eq!(replay.collect().unwrap().procs, 0);
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(replay.collect().unwrap().procs, 1);
+    }
+
+    #[test]
+    fn recorder_clock() {
+        let clock = crate::testing::MockClock::new();
+        let mut recorder = Recorder::new(Vec::new()).clock(clock.clone());
+        clock.advance(Duration::from_secs(60));
+        recorder.record(&SystemInfo::default()).unwrap();
+        clock.advance(Duration::from_millis(1500));
+        recorder.record(&SystemInfo::default()).unwrap();
+        let elapsed: Vec<Duration> = read(&recorder.into_inner()[..]).unwrap().iter().map(|sample| sample.elapsed).collect();
+        assert_eq!(elapsed, [Duration::ZERO, Duration::from_millis(1500)]);
+    }
+}
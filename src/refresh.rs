@@ -0,0 +1,87 @@
+//! A process-wide snapshot kept fresh by a background thread, for hot paths (a game loop, an
+//! overlay drawn every frame) that want the latest numbers without a syscall each time.
+//!
+//! [`latest`] is a handful of atomic loads out of a [`SeqLock`]: it never blocks, not even on
+//! the refresher thread writing. The thread is a [`Sampler`] and runs until the guard
+//! [`start_background_refresh`] returns is dropped.
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use sysinfo_dot_h::refresh;
+//!
+//! let _refresh = refresh::start_background_refresh(Duration::from_millis(100));
+//! for _frame in 0..3 {
+//!     let info = refresh::latest();
+//!     println!("{} of {} bytes free", info.freeram, info.totalram);
+//! }
+//! // the thread stops here, with `_refresh`
+//! ```
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::sampler::Sampler;
+use crate::seqlock::SeqLock;
+use crate::{sysinfo, SystemInfo};
+
+fn snapshot() -> &'static SeqLock {
+    static LATEST: OnceLock<SeqLock> = OnceLock::new();
+    LATEST.get_or_init(SeqLock::new)
+}
+
+/// Keeps the refresher thread running, stops (and joins) it when dropped.
+#[derive(Debug)]
+#[must_use = "the refresher stops when the guard is dropped"]
+pub struct RefreshGuard {
+    sampler: Sampler,
+}
+
+impl RefreshGuard {
+    /// Stops the thread and waits for it to exit. Same as dropping the guard.
+    pub fn stop(self) {
+        self.sampler.stop();
+    }
+}
+
+/// Spawns a thread collecting a snapshot every `interval` for [`latest`], the first one right
+/// away. Failed collections are skipped, keeping the previous snapshot.
+///
+/// There's one snapshot for the whole process. Starting a second refresher while one runs
+/// works, but it's two threads doing the same job.
+pub fn start_background_refresh(interval: Duration) -> RefreshGuard {
+    let sampler = Sampler::builder().interval(interval).on_sample(|info| snapshot().store(*info)).start();
+    RefreshGuard { sampler }
+}
+
+/// The refresher's latest snapshot, `None` before its first one. Keeps returning the last one
+/// after it stopped.
+#[must_use] pub fn latest_info() -> Option<SystemInfo> {
+    snapshot().load()
+}
+
+/// [`latest_info`] as the raw struct (with a `mem_unit` of 1 on 64-bit targets, see the
+/// `From<SystemInfo>` conversion on [`sysinfo`](struct@sysinfo)). Before the
+/// refresher's first snapshot, or without a refresher, that's a `sysinfo(2)` call like
+/// [`collect`](crate::compat::collect).
+#[must_use] pub fn latest() -> sysinfo {
+    latest_info().map_or_else(crate::compat::collect, sysinfo::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refreshes_in_the_background() {
+        let refresh = start_background_refresh(Duration::from_millis(1));
+        let started = std::time::Instant::now();
+        while latest_info().is_none() {
+            assert!(started.elapsed() < Duration::from_secs(5), "no snapshot yet");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let info = latest();
+        assert!(info.totalram > 0 && info.procs > 0);
+        assert_eq!(SystemInfo::from(info).total_ram, latest_info().unwrap().total_ram);
+        refresh.stop();
+        assert!(latest_info().is_some());
+    }
+}
@@ -0,0 +1,195 @@
+//! [`Sysinfo`], the raw struct under the name it gets in v1.
+//!
+//! `sysinfo` is named like the C struct, which Rust code has to `#[allow]` and which reads like
+//! a function. v1 renames it, with the fields spelled the Rust way, and makes it
+//! `#[non_exhaustive]`, so a field added by a future kernel isn't a breaking change. The layout
+//! is the C one, the same as `sysinfo`'s, so converting either way is a copy.
+//!
+//! Moving over can happen one call site at a time, the two convert into each other:
+//!
+//! 1. `try_collect()` becomes [`try_collect_sysinfo()`](crate::try_collect_sysinfo),
+//!    `try_collect_into()` becomes [`try_collect_sysinfo_into()`](crate::try_collect_sysinfo_into),
+//!    and the fields lose the C spelling: `totalram` becomes `total_ram`, `freeswap` becomes
+//!    `free_swap`, and so on. The `*_bytes()` accessors are the same on both. Code that only
+//!    needs bytes should go to [`SystemInfo`](crate::SystemInfo) instead.
+//! 2. The `deprecate-v0` feature marks `collect()`, `try_collect()` and `try_collect_into()`
+//!    deprecated, so the compiler lists whatever is left.
+//! 3. In v1 they leave the crate root, and so does `sysinfo` (the `sysinfo()` binding keeps
+//!    it). Both stay in [`compat`](crate::compat) for code that doesn't want to move.
+//!
+//! ```rust
+//! use sysinfo_dot_h::{sysinfo, Sysinfo};
+//!
+//! let info = sysinfo_dot_h::try_collect_sysinfo().unwrap();
+//! println!("{} of {} bytes free", info.free_ram_bytes(), info.total_ram_bytes());
+//!
+//! // old code keeps working on what new code collected, and the other way around
+//! let old = sysinfo::from(info);
+//! assert_eq!(Sysinfo::from(old), info);
+//! ```
+use core::ffi::{c_char, c_long, c_uint, c_ulong, c_ushort};
+
+use crate::{sysinfo, SystemInfo};
+
+/// `struct sysinfo` with Rust names and `#[non_exhaustive]`, what [`sysinfo`](struct@sysinfo)
+/// becomes in v1. The layout is the same, the two convert into each other.
+#[repr(C)]
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Sysinfo {
+    /// Seconds since boot
+    pub uptime: c_long,
+    /// 1, 5, and 15 minute load averages, scaled by `1 << 16`
+    pub loads: [c_ulong; 3],
+    /// Total usable main memory size, in `mem_unit`s
+    pub total_ram: c_ulong,
+    /// Free memory, in `mem_unit`s (note: free != available memory)
+    pub free_ram: c_ulong,
+    /// Amount of shared memory, in `mem_unit`s
+    pub shared_ram: c_ulong,
+    /// Memory used by buffers, in `mem_unit`s
+    pub buffer_ram: c_ulong,
+    /// Total swap space size, in `mem_unit`s
+    pub total_swap: c_ulong,
+    /// Swap space still available, in `mem_unit`s
+    pub free_swap: c_ulong,
+    /// Number of current processes
+    pub procs: c_ushort,
+    pad: c_ushort,
+    /// Total high memory size, in `mem_unit`s
+    pub total_high: c_ulong,
+    /// Available high memory size, in `mem_unit`s
+    pub free_high: c_ulong,
+    /// Memory unit size in bytes
+    pub mem_unit: c_uint,
+    _f: [c_char; crate::TAIL],
+}
+
+impl From<sysinfo> for Sysinfo {
+    fn from(raw: sysinfo) -> Self {
+        Self {
+            uptime: raw.uptime,
+            loads: raw.loads,
+            total_ram: raw.totalram,
+            free_ram: raw.freeram,
+            shared_ram: raw.sharedram,
+            buffer_ram: raw.bufferram,
+            total_swap: raw.totalswap,
+            free_swap: raw.freeswap,
+            procs: raw.procs,
+            pad: raw.pad,
+            total_high: raw.totalhigh,
+            free_high: raw.freehigh,
+            mem_unit: raw.mem_unit,
+            _f: [0; crate::TAIL],
+        }
+    }
+}
+
+impl From<Sysinfo> for sysinfo {
+    fn from(info: Sysinfo) -> Self {
+        Self {
+            uptime: info.uptime,
+            loads: info.loads,
+            totalram: info.total_ram,
+            freeram: info.free_ram,
+            sharedram: info.shared_ram,
+            bufferram: info.buffer_ram,
+            totalswap: info.total_swap,
+            freeswap: info.free_swap,
+            procs: info.procs,
+            pad: info.pad,
+            totalhigh: info.total_high,
+            freehigh: info.free_high,
+            mem_unit: info.mem_unit,
+            _f: [0; crate::TAIL],
+        }
+    }
+}
+
+impl From<Sysinfo> for SystemInfo {
+    fn from(info: Sysinfo) -> Self {
+        SystemInfo::from(sysinfo::from(info))
+    }
+}
+
+crate::info::byte_accessors! {
+    Sysinfo {
+        total_ram: total_ram_bytes, checked_total_ram_bytes, "total usable main memory";
+        free_ram: free_ram_bytes, checked_free_ram_bytes, "free memory";
+        shared_ram: shared_ram_bytes, checked_shared_ram_bytes, "shared memory";
+        buffer_ram: buffer_ram_bytes, checked_buffer_ram_bytes, "memory used by buffers";
+        total_swap: total_swap_bytes, checked_total_swap_bytes, "total swap space";
+        free_swap: free_swap_bytes, checked_free_swap_bytes, "free swap space";
+        total_high: total_high_bytes, checked_total_high_bytes, "total high memory";
+        free_high: free_high_bytes, checked_free_high_bytes, "free high memory";
+    }
+}
+
+impl Sysinfo {
+    /// `mem_unit`, with the 0 of kernels before 2.3.23 meaning bytes.
+    fn unit(&self) -> u64 {
+        u64::from(self.mem_unit.max(1))
+    }
+}
+
+/// Calls `sysinfo(2)`, see [`try_collect`](crate::try_collect), which this replaces in v1.
+///
+/// # Errors
+///
+/// Same as [`try_collect`](crate::try_collect).
+#[cfg(feature = "std")]
+pub fn try_collect_sysinfo() -> Result<Sysinfo, crate::SysinfoError> {
+    crate::syscall().map(Sysinfo::from)
+}
+
+/// [`try_collect_sysinfo`] into a struct the caller owns, what
+/// [`try_collect_into`](crate::try_collect_into) becomes in v1. Nothing is allocated, unless the
+/// `fake` feature is on.
+///
+/// ```rust
+/// let mut info = sysinfo_dot_h::try_collect_sysinfo().unwrap();
+/// for _ in 0..1000 {
+///     sysinfo_dot_h::try_collect_sysinfo_into(&mut info).unwrap();
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Same as [`try_collect_sysinfo`]. `info` may have been partly written then.
+#[cfg(feature = "std")]
+pub fn try_collect_sysinfo_into(info: &mut Sysinfo) -> Result<(), crate::SysinfoError> {
+    // SAFETY: the same layout (see the tests), only the field names differ
+    crate::syscall_into(unsafe { &mut *(info as *mut Sysinfo).cast::<sysinfo>() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::{align_of, offset_of, size_of};
+
+    #[test]
+    fn same_layout_as_the_v0_struct() {
+        assert_eq!((size_of::<Sysinfo>(), align_of::<Sysinfo>()), (size_of::<sysinfo>(), align_of::<sysinfo>()));
+        assert_eq!(offset_of!(Sysinfo, total_ram), offset_of!(sysinfo, totalram));
+        assert_eq!(offset_of!(Sysinfo, procs), offset_of!(sysinfo, procs));
+        assert_eq!(offset_of!(Sysinfo, total_high), offset_of!(sysinfo, totalhigh));
+        assert_eq!(offset_of!(Sysinfo, mem_unit), offset_of!(sysinfo, mem_unit));
+    }
+
+    #[test]
+    fn converts_both_ways() {
+        let info = try_collect_sysinfo().unwrap();
+        let old = sysinfo::from(info);
+        assert_eq!((old.totalram, old.freeswap, old.mem_unit), (info.total_ram, info.free_swap, info.mem_unit));
+        assert_eq!(Sysinfo::from(old), info);
+        assert_eq!(SystemInfo::from(info), SystemInfo::from(old));
+        assert_eq!(info.total_ram_bytes(), old.total_ram_bytes());
+        assert_eq!(info.checked_buffer_ram_bytes(), old.checked_buffer_ram_bytes());
+        assert_eq!((info.shared_ram_bytes(), info.free_high_bytes()), (old.shared_ram_bytes(), old.free_high_bytes()));
+
+        let mut into = Sysinfo::default();
+        try_collect_sysinfo_into(&mut into).unwrap();
+        assert!(into.total_ram == info.total_ram && into.mem_unit > 0);
+    }
+}
@@ -0,0 +1,413 @@
+//! A background thread collecting snapshots on a fixed cadence.
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use sysinfo_dot_h::sampler::Sampler;
+//!
+//! let sampler = Sampler::builder()
+//!     .interval(Duration::from_millis(10))
+//!     .history(64)
+//!     .on_sample(|info| println!("{} bytes free", info.free_ram))
+//!     .start();
+//!
+//! std::thread::sleep(Duration::from_millis(50));
+//! dbg!(sampler.latest());
+//! // the thread is stopped (and joined) when `sampler` is dropped
+//! ```
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::clock::{self, Clock};
+use crate::provider::SysinfoProvider;
+use crate::seqlock::SeqLock;
+use crate::{try_collect_info, SysinfoError, SystemInfo};
+
+/// How often a [`Sampler`] collects when no interval is given.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A snapshot and when it was collected, to tell a fresh one from the leftover of a sampler that
+/// stopped working.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sample {
+    pub info: SystemInfo,
+    /// Right after the collection returned, by the sampler's [`Clock`]
+    pub collected_at: Instant,
+}
+
+impl Sample {
+    /// How long ago the snapshot was collected.
+    #[must_use] pub fn age(&self) -> Duration {
+        self.age_at(Instant::now())
+    }
+
+    /// [`age`](Self::age) as of `now`, for samples timed by another [`Clock`].
+    #[must_use] pub fn age_at(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.collected_at)
+    }
+
+    /// Whether the snapshot is older than `max_age`.
+    #[must_use] pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.age() > max_age
+    }
+}
+
+type Callback = Box<dyn FnMut(&SystemInfo) + Send>;
+type Source = Box<dyn FnMut() -> Result<SystemInfo, SysinfoError> + Send>;
+
+/// Builds a [`Sampler`], see [`Sampler::builder`].
+#[must_use]
+pub struct Builder {
+    interval: Duration,
+    history: usize,
+    callbacks: Vec<Callback>,
+    source: Source,
+    clock: Arc<dyn Clock>,
+}
+
+impl std::fmt::Debug for Builder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Builder")
+            .field("interval", &self.interval)
+            .field("history", &self.history)
+            .field("callbacks", &self.callbacks.len())
+            .field("clock", &self.clock)
+            .finish()
+    }
+}
+
+impl Builder {
+    /// Time between two samples. Defaults to [`DEFAULT_INTERVAL`].
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// How many samples to keep around for [`Sampler::history`]. Defaults to 0 (only the latest
+    /// one is kept).
+    pub fn history(mut self, samples: usize) -> Self {
+        self.history = samples;
+        self
+    }
+
+    /// Calls `callback` on the sampler thread for every sample. Can be called more than once.
+    ///
+    /// Don't block in here, the next sample is only taken after every callback returned.
+    pub fn on_sample<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&SystemInfo) + Send + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Where the samples come from. Defaults to [`try_collect_info`], replace it to replay
+    /// recorded samples or to use another [backend](crate::backend). Failures are skipped like
+    /// failed collections.
+    pub fn source<F>(mut self, source: F) -> Self
+    where
+        F: FnMut() -> Result<SystemInfo, SysinfoError> + Send + 'static,
+    {
+        self.source = Box::new(source);
+        self
+    }
+
+    /// Takes the samples from `provider`, a fake one in tests for example. Same as a
+    /// [`source`](Self::source) calling [`SysinfoProvider::collect`].
+    pub fn provider<P>(self, provider: P) -> Self
+    where
+        P: SysinfoProvider + Send + 'static,
+    {
+        self.source(move || provider.collect())
+    }
+
+    /// Where the time between samples is measured. Defaults to the
+    /// [`SystemClock`](clock::SystemClock), tests can drive the sampler with a
+    /// [`MockClock`](crate::testing::MockClock).
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Spawns the sampler thread. The first sample is taken right away.
+    #[must_use] pub fn start(self) -> Sampler {
+        let shared = Arc::new(Shared {
+            stopped: Mutex::new(false),
+            wakeup: Condvar::new(),
+            samples: Mutex::new(VecDeque::with_capacity(self.history.max(1))),
+            capacity: self.history.max(1),
+            latest: SeqLock::new(),
+        });
+        let clock = Arc::clone(&self.clock);
+        let thread = std::thread::Builder::new()
+            .name("sysinfo_dot_h sampler".to_string())
+            .spawn({
+                let shared = Arc::clone(&shared);
+                move || run(&shared, &*self.clock, self.interval, self.source, self.callbacks)
+            })
+            .expect("failed to spawn the sampler thread");
+
+        Sampler { shared, clock, thread: Some(thread) }
+    }
+
+    /// [`start`](Self::start), with every sample also sent to the returned receiver, for
+    /// consuming them on another thread instead of in an [`on_sample`](Self::on_sample)
+    /// callback.
+    ///
+    /// Up to `capacity` samples wait in the channel. A receiver that falls further behind
+    /// misses samples rather than holding up the sampler or growing a queue, and one that's
+    /// dropped just stops getting them.
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use sysinfo_dot_h::sampler::Sampler;
+    ///
+    /// let (sampler, samples) = Sampler::builder().interval(Duration::from_millis(10)).start_with_channel(16);
+    /// for info in samples.iter().take(3) {
+    ///     println!("{} bytes free", info.free_ram);
+    /// }
+    /// drop(sampler);
+    /// ```
+    #[must_use] pub fn start_with_channel(self, capacity: usize) -> (Sampler, Receiver<SystemInfo>) {
+        let (tx, rx) = mpsc::sync_channel(capacity.max(1));
+        let sampler = self
+            .on_sample(move |info| {
+                let _ = tx.try_send(*info);
+            })
+            .start();
+        (sampler, rx)
+    }
+}
+
+struct Shared {
+    stopped: Mutex<bool>,
+    wakeup: Condvar,
+    samples: Mutex<VecDeque<Sample>>,
+    capacity: usize,
+    /// The back of `samples`, for reading it without the lock
+    latest: SeqLock,
+}
+
+// nothing guarded here can be left half written by a panic, so poisoning is harmless
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Shared {
+    fn push(&self, sample: Sample) {
+        let mut samples = lock(&self.samples);
+        if samples.len() == self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+        self.latest.store(sample.info);
+    }
+}
+
+fn run(shared: &Shared, clock: &dyn Clock, interval: Duration, mut source: Source, mut callbacks: Vec<Callback>) {
+    let mut next = clock.now();
+    loop {
+        // a failed collection is skipped, the next tick will try again
+        if let Ok(info) = source() {
+            shared.push(Sample { info, collected_at: clock.now() });
+            for callback in &mut callbacks {
+                callback(&info);
+            }
+        }
+
+        next += interval;
+        let mut stopped = lock(&shared.stopped);
+        while !*stopped {
+            // a fake clock can land exactly on `next`, and waiting 0 for it would spin
+            let Some(remaining) = next.checked_duration_since(clock.now()).filter(|remaining| !remaining.is_zero()) else { break };
+            stopped = shared.wakeup.wait_timeout(stopped, clock.wait_for(remaining)).unwrap_or_else(PoisonError::into_inner).0;
+        }
+        if *stopped {
+            return;
+        }
+    }
+}
+
+/// A running background sampler. Stops its thread when dropped.
+pub struct Sampler {
+    shared: Arc<Shared>,
+    clock: Arc<dyn Clock>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for Sampler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sampler").field("latest", &self.latest()).finish_non_exhaustive()
+    }
+}
+
+impl Sampler {
+    /// Starts configuring a new sampler.
+    pub fn builder() -> Builder {
+        Builder { interval: DEFAULT_INTERVAL, history: 0, callbacks: Vec::new(), source: Box::new(try_collect_info), clock: clock::system() }
+    }
+
+    /// The most recent sample, or `None` if none was taken successfully yet. Doesn't lock
+    /// anything, see [`SeqLock`].
+    #[must_use] pub fn latest(&self) -> Option<SystemInfo> {
+        self.shared.latest.load()
+    }
+
+    /// [`latest`](Self::latest) with when it was collected.
+    #[must_use] pub fn latest_sample(&self) -> Option<Sample> {
+        lock(&self.shared.samples).back().copied()
+    }
+
+    /// Whether the latest sample is older than `max_age` by the sampler's [`Clock`], or there's
+    /// none at all. Usually means the collections keep failing.
+    #[must_use] pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.latest_sample().is_none_or(|sample| sample.age_at(self.clock.now()) > max_age)
+    }
+
+    /// The retained samples, oldest first. See [`Builder::history`].
+    #[must_use] pub fn history(&self) -> Vec<SystemInfo> {
+        lock(&self.shared.samples).iter().map(|sample| sample.info).collect()
+    }
+
+    /// Stops the sampler thread and waits for it to exit. Same as dropping the sampler.
+    pub fn stop(self) {}
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        *lock(&self.shared.stopped) = true;
+        self.shared.wakeup.notify_all();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn samples_and_keeps_history() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let sampler = Sampler::builder()
+            .interval(Duration::from_millis(1))
+            .history(4)
+            .on_sample({
+                let calls = Arc::clone(&calls);
+                move |_| { calls.fetch_add(1, Ordering::SeqCst); }
+            })
+            .start();
+
+        while calls.load(Ordering::SeqCst) < 6 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(sampler.latest().is_some());
+        assert_eq!(sampler.history().len(), 4);
+    }
+
+    #[test]
+    fn custom_source() {
+        let mut procs = 0;
+        let sampler = Sampler::builder()
+            .interval(Duration::from_millis(1))
+            .history(8)
+            .source(move || {
+                procs += 1;
+                if procs % 2 == 0 { Err("skipped".into()) } else { Ok(SystemInfo { procs, ..Default::default() }) }
+            })
+            .start();
+
+        while sampler.history().len() < 3 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let procs: Vec<u16> = sampler.history().iter().take(3).map(|info| info.procs).collect();
+        assert_eq!(procs, [1, 3, 5]);
+    }
+
+    #[test]
+    fn provider() {
+        let fake = crate::provider::from_fn(|| Ok(SystemInfo { procs: 7, ..Default::default() }));
+        let sampler = Sampler::builder().interval(Duration::from_millis(1)).provider(fake).start();
+        while sampler.latest().is_none() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(sampler.latest().map(|info| info.procs), Some(7));
+    }
+
+    #[test]
+    fn mock_clock() {
+        let clock = crate::testing::MockClock::new();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let _sampler = Sampler::builder()
+            .interval(Duration::from_secs(3600))
+            .clock(clock.clone())
+            .on_sample(move |info| tx.send(*info).unwrap())
+            .start();
+        rx.recv().unwrap();
+        assert!(rx.recv_timeout(Duration::from_millis(20)).is_err());
+        clock.advance(Duration::from_secs(3600));
+        rx.recv().unwrap();
+    }
+
+    #[test]
+    fn channel() {
+        let clock = crate::testing::MockClock::new();
+        let (sampler, rx) = Sampler::builder()
+            .interval(Duration::from_secs(1))
+            .history(8)
+            .clock(clock.clone())
+            .provider(crate::provider::from_fn(|| Ok(SystemInfo { procs: 3, ..Default::default() })))
+            .start_with_channel(2);
+        assert_eq!(rx.recv().map(|info| info.procs), Ok(3));
+
+        // nobody reads, the third of these doesn't fit
+        for _ in 0..3 {
+            let ticks = sampler.history().len();
+            clock.advance(Duration::from_secs(1));
+            while sampler.history().len() == ticks {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+        drop(sampler);
+        assert_eq!(rx.try_iter().count(), 2);
+        assert!(rx.recv().is_err(), "closed once the sampler is gone");
+    }
+
+    #[test]
+    fn staleness() {
+        let clock = crate::testing::MockClock::new();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut calls = 0;
+        let sampler = Sampler::builder()
+            .interval(Duration::from_secs(10))
+            .clock(clock.clone())
+            .source(move || {
+                calls += 1;
+                if calls == 1 { Ok(SystemInfo::default()) } else { Err("dead".into()) }
+            })
+            .on_sample(move |_| tx.send(()).unwrap())
+            .start();
+        rx.recv().unwrap();
+        let sample = sampler.latest_sample().unwrap();
+        assert_eq!(sample.age_at(clock.now()), Duration::ZERO);
+        assert!(!sampler.is_stale(Duration::from_secs(15)));
+
+        // the second collection fails, so the first sample just gets older
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(sampler.latest_sample(), Some(sample));
+        assert!(sampler.is_stale(Duration::from_secs(15)));
+        assert!(!sample.is_stale(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn drop_stops_a_slow_sampler_promptly() {
+        let sampler = Sampler::builder().interval(Duration::from_secs(3600)).start();
+        let start = Instant::now();
+        drop(sampler);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,193 @@
+//! A seqlock holding one [`SystemInfo`], for sharing the latest snapshot without making the
+//! readers wait for a lock (the [sampler](crate::sampler)'s [`latest`](crate::sampler::Sampler::latest)).
+//!
+//! Readers never block and never see half of one snapshot and half of another: the snapshot is
+//! kept as atomic words next to a sequence number that's odd while a write is in progress, and a
+//! read that overlapped a write is retried. Writers wait for each other. The memory orderings are
+//! checked with [loom](https://docs.rs/loom):
+//!
+//! ```sh
+//! RUSTFLAGS="--cfg loom" cargo test --release --lib seqlock
+//! ```
+use std::time::Duration;
+
+#[cfg(not(loom))] use std::sync::atomic::{fence, AtomicU64, Ordering};
+#[cfg(loom)] use loom::sync::atomic::{fence, AtomicU64, Ordering};
+
+use crate::SystemInfo;
+
+/// The uptime (seconds and nanoseconds), 3 loads, 8 amounts and the process count.
+const WORDS: usize = 14;
+
+fn to_words(info: &SystemInfo) -> [u64; WORDS] {
+    [
+        info.uptime.as_secs(),
+        u64::from(info.uptime.subsec_nanos()),
+        info.loads[0].to_bits(),
+        info.loads[1].to_bits(),
+        info.loads[2].to_bits(),
+        info.total_ram,
+        info.free_ram,
+        info.shared_ram,
+        info.buffer_ram,
+        info.total_swap,
+        info.free_swap,
+        info.total_high,
+        info.free_high,
+        u64::from(info.procs),
+    ]
+}
+
+fn from_words(words: [u64; WORDS]) -> SystemInfo {
+    SystemInfo {
+        uptime: Duration::new(words[0], words[1] as u32),
+        loads: [f64::from_bits(words[2]), f64::from_bits(words[3]), f64::from_bits(words[4])],
+        total_ram: words[5],
+        free_ram: words[6],
+        shared_ram: words[7],
+        buffer_ram: words[8],
+        total_swap: words[9],
+        free_swap: words[10],
+        total_high: words[11],
+        free_high: words[12],
+        procs: words[13] as u16,
+    }
+}
+
+fn spin() {
+    #[cfg(loom)] loom::thread::yield_now();
+    #[cfg(not(loom))] std::hint::spin_loop();
+}
+
+/// The latest snapshot, readable from any number of threads without locking.
+#[derive(Debug)]
+pub struct SeqLock {
+    /// 0 while empty, odd while being written. 64 bits even on 32-bit targets, where a `usize`
+    /// would wrap back to 0 (empty) after 2^31 stores; this one doesn't in centuries
+    sequence: AtomicU64,
+    words: [AtomicU64; WORDS],
+}
+
+impl Default for SeqLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SeqLock {
+    /// An empty one, [`load`](Self::load) returns `None` until the first [`store`](Self::store).
+    #[must_use] pub fn new() -> Self {
+        Self { sequence: AtomicU64::new(0), words: std::array::from_fn(|_| AtomicU64::new(0)) }
+    }
+
+    /// Replaces the snapshot. Waits for concurrent `store`s, never for readers.
+    pub fn store(&self, info: SystemInfo) {
+        let mut sequence = self.sequence.load(Ordering::Relaxed);
+        loop {
+            if sequence % 2 == 1 {
+                spin();
+                sequence = self.sequence.load(Ordering::Relaxed);
+                continue;
+            }
+            match self.sequence.compare_exchange_weak(sequence, sequence + 1, Ordering::Acquire, Ordering::Relaxed) {
+                Ok(_) => break,
+                Err(current) => sequence = current,
+            }
+        }
+        // the odd sequence number has to be visible before any of the new words
+        fence(Ordering::Release);
+        for (word, value) in self.words.iter().zip(to_words(&info)) {
+            word.store(value, Ordering::Relaxed);
+        }
+        self.sequence.store(sequence + 2, Ordering::Release);
+    }
+
+    /// The latest snapshot, `None` if nothing was stored yet.
+    #[must_use] pub fn load(&self) -> Option<SystemInfo> {
+        loop {
+            let before = self.sequence.load(Ordering::Acquire);
+            if before == 0 {
+                return None;
+            }
+            if before % 2 == 1 {
+                spin();
+                continue;
+            }
+            let words = std::array::from_fn(|i| self.words[i].load(Ordering::Relaxed));
+            // the words have to be read before checking that nobody wrote them meanwhile
+            fence(Ordering::Acquire);
+            if self.sequence.load(Ordering::Relaxed) == before {
+                return Some(from_words(words));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every field set to something derived from `n`, so a torn read is easy to spot.
+    fn uniform(n: u64) -> SystemInfo {
+        SystemInfo {
+            uptime: Duration::new(n, n as u32),
+            loads: [n as f64; 3],
+            total_ram: n,
+            free_ram: n,
+            shared_ram: n,
+            buffer_ram: n,
+            total_swap: n,
+            free_swap: n,
+            total_high: n,
+            free_high: n,
+            procs: n as u16,
+        }
+    }
+
+    #[cfg(not(loom))]
+    #[test]
+    fn round_trips() {
+        let lock = SeqLock::new();
+        assert_eq!(lock.load(), None);
+        let info = SystemInfo { uptime: Duration::from_millis(1500), loads: [0.5, f64::MAX, 0.0], total_ram: u64::MAX, procs: u16::MAX, ..uniform(7) };
+        lock.store(info);
+        assert_eq!(lock.load(), Some(info));
+    }
+
+    #[cfg(not(loom))]
+    #[test]
+    fn never_torn() {
+        let lock = std::sync::Arc::new(SeqLock::new());
+        lock.store(uniform(0));
+        let writers: Vec<_> = (0..2)
+            .map(|writer| {
+                let lock = std::sync::Arc::clone(&lock);
+                std::thread::spawn(move || (0..20_000).for_each(|n| lock.store(uniform(n * 2 + writer))))
+            })
+            .collect();
+        for _ in 0..20_000 {
+            let info = lock.load().unwrap();
+            assert_eq!(info, uniform(info.total_ram));
+        }
+        writers.into_iter().for_each(|writer| writer.join().unwrap());
+    }
+
+    #[cfg(loom)]
+    #[test]
+    fn never_torn_under_loom() {
+        let mut model = loom::model::Builder::new();
+        model.preemption_bound = Some(3);
+        model.check(|| {
+            let lock = loom::sync::Arc::new(SeqLock::new());
+            lock.store(uniform(1));
+            let writer = {
+                let lock = loom::sync::Arc::clone(&lock);
+                loom::thread::spawn(move || lock.store(uniform(2)))
+            };
+            let info = lock.load().unwrap();
+            assert_eq!(info, uniform(info.total_ram));
+            writer.join().unwrap();
+            assert_eq!(lock.load(), Some(uniform(2)));
+        });
+    }
+}
@@ -0,0 +1,67 @@
+//! The parts of the crate with a strict semver promise, for long lived agents that want to
+//! upgrade without churn.
+//!
+//! Everything in [`v1`] keeps its name, signature and fields (and their meaning) for as long as
+//! the crate's major version is 0 or 1: no field is added, removed or renamed, no function
+//! changes. Things that need to change go into a `v2` next to it, and `v1` stays. The rest of
+//! the crate (the sampler, the exporters, the `/proc` readers...) follows the usual 0.x rules and
+//! may change in minor versions.
+//!
+//! ```rust
+//! use sysinfo_dot_h::stable::v1;
+//!
+//! let info: v1::SystemInfo = v1::try_collect_info().unwrap();
+//! println!("{} of {} bytes free", info.free_ram, info.total_ram);
+//! ```
+
+/// The first stable API, see the [module docs](self).
+pub mod v1 {
+    pub use crate::{collect_info, SystemInfo};
+
+    /// Collects a normalized snapshot, see [`crate::try_collect_info`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the error as a `String` if `sysinfo(2)` fails.
+    pub fn try_collect_info() -> Result<SystemInfo, String> {
+        crate::try_collect_info().map_err(String::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::v1;
+    use std::time::Duration;
+
+    /// Breaks the build if `v1` changes, which it mustn't.
+    #[test]
+    fn v1_is_unchanged() {
+        let _: fn() -> Result<v1::SystemInfo, String> = v1::try_collect_info;
+        let _: fn() -> v1::SystemInfo = v1::collect_info;
+        let v1::SystemInfo {
+            uptime: _,
+            loads: _,
+            total_ram: _,
+            free_ram: _,
+            shared_ram: _,
+            buffer_ram: _,
+            total_swap: _,
+            free_swap: _,
+            total_high: _,
+            free_high: _,
+            procs: _,
+        } = v1::SystemInfo {
+            uptime: Duration::ZERO,
+            loads: [0.0_f64; 3],
+            total_ram: 0_u64,
+            free_ram: 0_u64,
+            shared_ram: 0_u64,
+            buffer_ram: 0_u64,
+            total_swap: 0_u64,
+            free_swap: 0_u64,
+            total_high: 0_u64,
+            free_high: 0_u64,
+            procs: 0_u16,
+        };
+    }
+}
@@ -0,0 +1,111 @@
+//! A pretend kernel for where there's no `sysinfo(2)` or `/proc` (Windows, the BSDs other than
+//! FreeBSD, ...), so the crate still builds there and its doctests and examples run against the
+//! same made up machine every time instead of not at all. macOS and FreeBSD get a real
+//! `sysinfo()` out of sysctls, but still read their `/proc` files from here.
+//!
+//! The machine: up for a day, 8 GiB of memory of which 2 GiB free and 5 GiB available, 2 GiB of
+//! swap half used, loads of 0.5, 0.25 and 0.125, 420 threads.
+use std::os::raw::c_int;
+
+// renamed, `sysinfo` is the function here
+use crate::sysinfo as RawSysinfo;
+
+const KB: u64 = 1024;
+
+/// Everything in kB, like `/proc/meminfo` and a `mem_unit` of 1024.
+const TOTAL_KB: u64 = 8 << 20;
+const FREE_KB: u64 = 2 << 20;
+const AVAILABLE_KB: u64 = 5 << 20;
+const BUFFERS_KB: u64 = 256 << 10;
+const CACHED_KB: u64 = 2 << 20;
+const SHMEM_KB: u64 = 128 << 10;
+const SWAP_KB: u64 = 2 << 20;
+const SWAP_FREE_KB: u64 = 1 << 20;
+
+/// Stands in for libc's `sysinfo()`.
+///
+/// # Safety
+///
+/// `info` must be valid for writing a `struct sysinfo`, like for the real one.
+#[cfg_attr(any(target_os = "macos", target_os = "freebsd"), allow(dead_code))]
+pub unsafe extern "C" fn sysinfo(info: *mut RawSysinfo) -> c_int {
+    let mut stub: RawSysinfo = std::mem::zeroed();
+    stub.uptime = 86400;
+    // the kernel's fixed point, see `SystemInfo::loads`
+    stub.loads = [32768, 16384, 8192];
+    stub.totalram = TOTAL_KB as _;
+    stub.freeram = FREE_KB as _;
+    stub.sharedram = SHMEM_KB as _;
+    stub.bufferram = BUFFERS_KB as _;
+    stub.totalswap = SWAP_KB as _;
+    stub.freeswap = SWAP_FREE_KB as _;
+    stub.procs = 420;
+    stub.mem_unit = KB as _;
+    info.write(stub);
+    0
+}
+
+/// The made up contents of the `/proc` (and `/sys`) files the crate reads, `None` for the others.
+#[cfg(feature = "procfs")]
+pub(crate) fn proc_file(path: &str) -> Option<String> {
+    Some(match path {
+        "/proc/uptime" => "86400.00 172000.00\n".to_string(),
+        "/proc/loadavg" => "0.50 0.25 0.12 1/420 4242\n".to_string(),
+        "/proc/meminfo" => format!(
+            "MemTotal: {TOTAL_KB} kB\nMemFree: {FREE_KB} kB\nMemAvailable: {AVAILABLE_KB} kB\nBuffers: {BUFFERS_KB} kB\n\
+             Cached: {CACHED_KB} kB\nShmem: {SHMEM_KB} kB\nSReclaimable: {} kB\nSwapTotal: {SWAP_KB} kB\nSwapFree: {SWAP_FREE_KB} kB\n\
+             CommitLimit: {} kB\nCommitted_AS: {} kB\n",
+            64 << 10,
+            TOTAL_KB / 2 + SWAP_KB,
+            3 << 20,
+        ),
+        "/proc/sys/vm/overcommit_memory" => "0\n".to_string(),
+        "/proc/zoneinfo" => "Node 0, zone   Normal\n  pages free     524288\n        min      8192\n        low      10240\n        high     12288\n        protection: (0, 0, 0, 0, 0)\n".to_string(),
+        "/proc/stat" => "cpu  4000 100 1000 80000 200 0 50 0 0 0\ncpu0 2000 50 500 40000 100 0 25 0 0 0\ncpu1 2000 50 500 40000 100 0 25 0 0 0\nctxt 1000000\n".to_string(),
+        "/proc/self/status" => "Name:\tstub\nState:\tR (running)\nVmSize:\t  16384 kB\nVmRSS:\t   4096 kB\nThreads:\t1\n".to_string(),
+        "/proc/self/stat" => "4242 (stub) R 1 4242 4242 0 -1 0 0 0 0 0 0 0 0 0 20 0 1 0 8000000 16777216 1024\n".to_string(),
+        "/sys/devices/system/node/online" => "0-1\n".to_string(),
+        "/sys/devices/system/node/node0/meminfo" | "/sys/devices/system/node/node1/meminfo" => {
+            let node = if path.contains("node1/") { 1 } else { 0 };
+            format!("Node {node} MemTotal: {} kB\nNode {node} MemFree: {} kB\nNode {node} MemUsed: {} kB\n", TOTAL_KB / 2, FREE_KB / 2, (TOTAL_KB - FREE_KB) / 2)
+        }
+        "/proc/vmstat" => "pswpin 1024\npswpout 2048\npgfault 1000000\npgmajfault 100\n".to_string(),
+        "/proc/pressure/cpu" | "/proc/pressure/memory" | "/proc/pressure/io" => {
+            "some avg10=0.50 avg60=0.25 avg300=0.10 total=123456\nfull avg10=0.00 avg60=0.00 avg300=0.00 total=0\n".to_string()
+        }
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::parse_uptime_and_loadavg;
+    use crate::cpu::CpuStat;
+    use crate::meminfo::MemInfo;
+    use crate::numa::NodeMemory;
+    use crate::psi::Pressure;
+    use crate::vmstat::VmStat;
+    use crate::SystemInfo;
+
+    #[test]
+    fn files_agree_with_the_syscall() {
+        let mut raw: RawSysinfo = unsafe { std::mem::zeroed() };
+        assert_eq!(unsafe { sysinfo(&mut raw) }, 0);
+        let info = SystemInfo::from(raw);
+        let meminfo: MemInfo = proc_file("/proc/meminfo").unwrap().parse().unwrap();
+        assert_eq!((info.total_ram, info.free_ram, info.total_swap), (meminfo.total, meminfo.free, meminfo.swap_total));
+        assert_eq!(meminfo.available, Some(5 << 30));
+
+        let (uptime, loads, procs) = parse_uptime_and_loadavg(&proc_file("/proc/uptime").unwrap(), &proc_file("/proc/loadavg").unwrap()).unwrap();
+        assert_eq!((uptime, procs), (info.uptime, info.procs));
+        assert!(loads.iter().zip(info.loads).all(|(file, syscall)| (file - syscall).abs() < 0.01));
+
+        let node: NodeMemory = proc_file("/sys/devices/system/node/node1/meminfo").unwrap().parse().unwrap();
+        assert_eq!((node.id, node.total * 2, node.free * 2), (1, info.total_ram, info.free_ram));
+        proc_file("/proc/vmstat").unwrap().parse::<VmStat>().unwrap();
+        assert_eq!(proc_file("/proc/stat").unwrap().parse::<CpuStat>().unwrap().cores.len(), 2);
+        proc_file("/proc/pressure/memory").unwrap().parse::<Pressure>().unwrap();
+        assert_eq!(proc_file("/proc/self/cgroup"), None);
+    }
+}
@@ -0,0 +1,97 @@
+//! The `sysconf(3)` values needed to make sense of the snapshot: how many CPUs the load averages
+//! are shared between, and what the kernel counts pages and ticks in.
+//!
+//! ```rust
+//! use sysinfo_dot_h::sysconf;
+//!
+//! let info = sysinfo_dot_h::collect_info();
+//! let cpus = sysconf::nprocs_online().unwrap();
+//! println!("load per CPU: {:.2}", info.loads[0] / cpus as f64);
+//! println!("{} byte pages, {} ticks a second", sysconf::page_size().unwrap(), sysconf::clock_ticks().unwrap());
+//! ```
+use std::os::raw::{c_int, c_long};
+
+use crate::SysinfoError;
+
+extern "C" {
+    fn sysconf(name: c_int) -> c_long;
+}
+
+/// The `_SC_*` names, which are numbered differently everywhere (the same on glibc and musl).
+#[cfg(target_os = "linux")]
+mod names {
+    pub(super) const CLK_TCK: super::c_int = 2;
+    pub(super) const PAGESIZE: super::c_int = 30;
+    pub(super) const NPROCESSORS_CONF: super::c_int = 83;
+    pub(super) const NPROCESSORS_ONLN: super::c_int = 84;
+}
+#[cfg(target_os = "macos")]
+mod names {
+    pub(super) const CLK_TCK: super::c_int = 3;
+    pub(super) const PAGESIZE: super::c_int = 29;
+    pub(super) const NPROCESSORS_CONF: super::c_int = 57;
+    pub(super) const NPROCESSORS_ONLN: super::c_int = 58;
+}
+#[cfg(target_os = "freebsd")]
+mod names {
+    pub(super) const CLK_TCK: super::c_int = 3;
+    pub(super) const PAGESIZE: super::c_int = 47;
+    pub(super) const NPROCESSORS_CONF: super::c_int = 57;
+    pub(super) const NPROCESSORS_ONLN: super::c_int = 58;
+}
+
+/// `sysconf(name)`, which is -1 on errors.
+fn get(name: c_int) -> Result<u64, SysinfoError> {
+    u64::try_from(unsafe { sysconf(name) }).map_err(|_| SysinfoError::last_os_error())
+}
+
+/// The CPUs online now (`_SC_NPROCESSORS_ONLN`), what the load averages should be compared to.
+/// Not the CPUs this process may run on, see `std::thread::available_parallelism` for that.
+///
+/// # Errors
+///
+/// Returns [`SysinfoError::Syscall`] if `sysconf()` fails.
+pub fn nprocs_online() -> Result<u64, SysinfoError> {
+    get(names::NPROCESSORS_ONLN)
+}
+
+/// The CPUs the system has (`_SC_NPROCESSORS_CONF`), online or not.
+///
+/// # Errors
+///
+/// Returns [`SysinfoError::Syscall`] if `sysconf()` fails.
+pub fn nprocs_configured() -> Result<u64, SysinfoError> {
+    get(names::NPROCESSORS_CONF)
+}
+
+/// Bytes per page (`_SC_PAGESIZE`), what `/proc/zoneinfo` and `/proc/vmstat` count in.
+///
+/// # Errors
+///
+/// Returns [`SysinfoError::Syscall`] if `sysconf()` fails.
+pub fn page_size() -> Result<u64, SysinfoError> {
+    get(names::PAGESIZE)
+}
+
+/// Clock ticks per second (`_SC_CLK_TCK`, `USER_HZ`), what `/proc/stat` and `/proc/[pid]/stat`
+/// count times in.
+///
+/// # Errors
+///
+/// Returns [`SysinfoError::Syscall`] if `sysconf()` fails.
+pub fn clock_ticks() -> Result<u64, SysinfoError> {
+    get(names::CLK_TCK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sensible_values() {
+        let online = nprocs_online().unwrap();
+        assert!(online >= 1 && online <= nprocs_configured().unwrap());
+        assert!(page_size().unwrap().is_power_of_two());
+        assert!(clock_ticks().unwrap() > 0);
+    }
+}
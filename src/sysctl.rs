@@ -0,0 +1,126 @@
+//! `sysinfo()` for macOS and FreeBSD, out of `sysctlbyname(3)`, so the crate (and the snapshots)
+//! work there too instead of only on Linux.
+//!
+//! Not everything has a sysctl, what doesn't stays 0:
+//!
+//! | field | macOS | FreeBSD |
+//! |---|---|---|
+//! | `uptime` | `kern.boottime` | `kern.boottime` |
+//! | `loads` | `vm.loadavg` | `vm.loadavg` |
+//! | `totalram` | `hw.memsize` | `hw.physmem` |
+//! | `freeram` | `vm.page_free_count` | `vm.stats.vm.v_free_count` |
+//! | `bufferram` | 0 | `vfs.bufspace` |
+//! | `totalswap`, `freeswap` | `vm.swapusage` | 0 |
+//! | `sharedram`, `totalhigh`, `freehigh`, `procs` | 0 | 0 |
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_long, c_void};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// renamed, `sysinfo` is the function here
+use crate::sysinfo as RawSysinfo;
+
+extern "C" {
+    fn sysctlbyname(name: *const c_char, oldp: *mut c_void, oldlenp: *mut usize, newp: *mut c_void, newlen: usize) -> c_int;
+}
+
+/// `struct timeval`, only the seconds are used.
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct Timeval {
+    sec: i64,
+    _usec: c_long,
+}
+
+/// `struct loadavg`
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct LoadAvg {
+    ldavg: [u32; 3],
+    fscale: c_long,
+}
+
+/// `struct xsw_usage`, macOS only.
+#[cfg(target_os = "macos")]
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct SwapUsage {
+    total: u64,
+    avail: u64,
+    _used: u64,
+    _pagesize: u32,
+    _encrypted: bool,
+}
+
+/// A sysctl holding a `T`, `None` if it doesn't exist or has another size.
+fn read<T: Copy>(name: &CStr) -> Option<T> {
+    let mut value = std::mem::MaybeUninit::<T>::uninit();
+    let mut len = size_of::<T>();
+    let result = unsafe { sysctlbyname(name.as_ptr(), value.as_mut_ptr().cast(), &mut len, std::ptr::null_mut(), 0) };
+    (result == 0 && len == size_of::<T>()).then(|| unsafe { value.assume_init() })
+}
+
+/// A sysctl holding an integer of 4 or 8 bytes (`int`, `u_int`, `long`...), which depends on the
+/// name and the OS.
+fn number(name: &CStr) -> Option<u64> {
+    let mut bytes = [0u8; 8];
+    let mut len = bytes.len();
+    let result = unsafe { sysctlbyname(name.as_ptr(), bytes.as_mut_ptr().cast(), &mut len, std::ptr::null_mut(), 0) };
+    match (result, len) {
+        (0, 4) => Some(u64::from(u32::from_ne_bytes(bytes[..4].try_into().unwrap()))),
+        (0, 8) => Some(u64::from_ne_bytes(bytes)),
+        _ => None,
+    }
+}
+
+/// Stands in for libc's `sysinfo()`. Fails (-1, with `errno` set by `sysctlbyname`) only if the
+/// total amount of memory can't be read.
+///
+/// # Safety
+///
+/// `info` must be valid for writing a `struct sysinfo`, like for the real one.
+pub unsafe extern "C" fn sysinfo(info: *mut RawSysinfo) -> c_int {
+    #[cfg(target_os = "macos")] let total = number(c"hw.memsize");
+    #[cfg(target_os = "freebsd")] let total = number(c"hw.physmem");
+    let Some(total) = total else {
+        return -1;
+    };
+    let page_size = number(c"hw.pagesize").unwrap_or(4096);
+
+    let mut raw: RawSysinfo = std::mem::zeroed();
+    raw.mem_unit = 1;
+    raw.totalram = total as _;
+    #[cfg(target_os = "macos")] let free_pages = number(c"vm.page_free_count");
+    #[cfg(target_os = "freebsd")] let free_pages = number(c"vm.stats.vm.v_free_count");
+    raw.freeram = free_pages.unwrap_or(0).saturating_mul(page_size) as _;
+    #[cfg(target_os = "freebsd")] {
+        raw.bufferram = number(c"vfs.bufspace").unwrap_or(0) as _;
+    }
+    #[cfg(target_os = "macos")]
+    if let Some(swap) = read::<SwapUsage>(c"vm.swapusage") {
+        raw.totalswap = swap.total as _;
+        raw.freeswap = swap.avail as _;
+    }
+
+    if let (Some(boot), Ok(now)) = (read::<Timeval>(c"kern.boottime"), SystemTime::now().duration_since(UNIX_EPOCH)) {
+        raw.uptime = (now.as_secs() as i64).saturating_sub(boot.sec).max(0) as _;
+    }
+    if let Some(loads) = read::<LoadAvg>(c"vm.loadavg").filter(|loads| loads.fscale > 0) {
+        // from the kernel's fixed point to sysinfo's 16 bits one
+        raw.loads = loads.ldavg.map(|load| ((u64::from(load) << 16) / loads.fscale as u64) as _);
+    }
+    info.write(raw);
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::try_collect_info;
+
+    #[test]
+    fn real_values() {
+        let info = try_collect_info().unwrap();
+        assert!(info.total_ram > 0 && info.free_ram <= info.total_ram);
+        assert!(info.uptime.as_secs() > 0);
+        assert!(info.loads.iter().all(|load| load.is_finite() && *load >= 0.0));
+    }
+}
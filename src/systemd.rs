@@ -0,0 +1,170 @@
+//! `sd_notify(3)` helpers, so a service's `systemctl status` can show live host stats.
+//!
+//! This speaks the notify protocol directly over `$NOTIFY_SOCKET`, so there is no libsystemd
+//! dependency. Every function returns `Ok(false)` when the process isn't running under systemd
+//! (or the unit doesn't have `Type=notify`/`NotifyAccess=`), which makes them safe to call
+//! unconditionally.
+//!
+//! ```rust,no_run
+//! use sysinfo_dot_h::{collect_info, systemd};
+//!
+//! let watchdog = systemd::MemoryWatchdog::new(0.05);
+//! loop {
+//!     let info = collect_info();
+//!     systemd::notify_status(&info).unwrap();
+//!     // stop petting the watchdog when less than 5% of RAM is free, systemd restarts us
+//!     watchdog.keepalive(&info).unwrap();
+//!     std::thread::sleep(systemd::watchdog_interval().unwrap_or_default() / 2);
+//! }
+//! ```
+use std::ffi::OsStr;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::UnixDatagram;
+use std::time::Duration;
+
+use crate::units::{self, Units};
+use crate::SystemInfo;
+
+/// Sends `state` (e.g. `"READY=1"` or `"STATUS=..."`, newline separated) to the service manager.
+///
+/// # Errors
+///
+/// Returns an error if `$NOTIFY_SOCKET` is set but the message couldn't be sent.
+pub fn notify(state: &str) -> io::Result<bool> {
+    match std::env::var_os("NOTIFY_SOCKET") {
+        Some(socket) => notify_to(&socket, state).map(|()| true),
+        None => Ok(false),
+    }
+}
+
+fn notify_to(socket: &OsStr, state: &str) -> io::Result<()> {
+    let datagram = UnixDatagram::unbound()?;
+    match socket.as_bytes().strip_prefix(b"@") {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            datagram.send_to_addr(state.as_bytes(), &addr)?;
+        }
+        // abstract sockets are a Linux thing, and so is systemd
+        #[cfg(not(target_os = "linux"))]
+        Some(_) => return Err(io::Error::new(io::ErrorKind::Unsupported, "abstract NOTIFY_SOCKET outside of Linux")),
+        None => {
+            datagram.send_to(state.as_bytes(), socket)?;
+        }
+    }
+    Ok(())
+}
+
+/// Summarizes memory and load into a single line, e.g.
+/// `"mem 3.2 GiB free of 15.5 GiB, swap 2.0 GiB free of 2.0 GiB, load 0.52 0.40 0.33, 812 procs"`.
+#[must_use] pub fn status_line(info: &SystemInfo) -> String {
+    format!(
+        "mem {} free of {}, swap {} free of {}, load {:.2} {:.2} {:.2}, {} procs",
+        units::bytes(info.free_ram, Units::Binary),
+        units::bytes(info.total_ram, Units::Binary),
+        units::bytes(info.free_swap, Units::Binary),
+        units::bytes(info.total_swap, Units::Binary),
+        info.loads[0], info.loads[1], info.loads[2],
+        info.procs,
+    )
+}
+
+/// Sends [`status_line`] as the unit's `STATUS=`.
+///
+/// # Errors
+///
+/// Same as [`notify`].
+pub fn notify_status(info: &SystemInfo) -> io::Result<bool> {
+    notify(&format!("STATUS={}", status_line(info)))
+}
+
+/// The unit's `WatchdogSec=`, if the watchdog is enabled for this process.
+#[must_use] pub fn watchdog_interval() -> Option<Duration> {
+    if let Some(pid) = std::env::var_os("WATCHDOG_PID") {
+        if pid.to_str()?.parse::<u32>().ok()? != std::process::id() {
+            return None;
+        }
+    }
+    let usec = std::env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    Some(Duration::from_micros(usec))
+}
+
+/// Only sends watchdog keepalives while memory is healthy.
+///
+/// A service stuck in a memory starved host keeps getting restarted by systemd instead of limping
+/// along. "Healthy" means at least a `min_free` fraction of `totalram` is free or used by buffers
+/// (both can be handed out without swapping).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MemoryWatchdog {
+    min_free: f64,
+}
+
+impl MemoryWatchdog {
+    /// `min_free` is a fraction of the total RAM, `0.05` means 5%.
+    #[must_use] pub fn new(min_free: f64) -> Self {
+        Self { min_free }
+    }
+
+    /// Whether `info` is above the threshold.
+    #[must_use] pub fn is_healthy(&self, info: &SystemInfo) -> bool {
+        if info.total_ram == 0 {
+            return false;
+        }
+        let free = info.free_ram.saturating_add(info.buffer_ram) as f64;
+        free / info.total_ram as f64 >= self.min_free
+    }
+
+    /// Sends `WATCHDOG=1` if memory [`is_healthy`](Self::is_healthy). Returns whether a
+    /// keepalive was sent.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`notify`].
+    pub fn keepalive(&self, info: &SystemInfo) -> io::Result<bool> {
+        if self.is_healthy(info) {
+            notify("WATCHDOG=1")
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(free_ram: u64) -> SystemInfo {
+        SystemInfo { total_ram: 1000, free_ram, buffer_ram: 50, loads: [0.5, 0.25, 1.0], procs: 7, ..Default::default() }
+    }
+
+    #[test]
+    fn sends_to_a_path_socket() {
+        let path = std::env::temp_dir().join(format!("sysinfo_dot_h-notify-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let receiver = UnixDatagram::bind(&path).unwrap();
+
+        notify_to(path.as_os_str(), "STATUS=hello").unwrap();
+        let mut buf = [0; 64];
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"STATUS=hello");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn status_line_format() {
+        assert_eq!(
+            status_line(&info(100)),
+            "mem 100 B free of 1000 B, swap 0 B free of 0 B, load 0.50 0.25 1.00, 7 procs"
+        );
+    }
+
+    #[test]
+    fn watchdog_health() {
+        let watchdog = MemoryWatchdog::new(0.1);
+        assert!(watchdog.is_healthy(&info(50)));
+        assert!(!watchdog.is_healthy(&info(49)));
+        assert!(!watchdog.keepalive(&info(0)).unwrap());
+    }
+}
@@ -0,0 +1,487 @@
+//! Fakes for testing code built on this crate: [`MockProvider`] plays a script, and
+//! [`FaultyProvider`] makes a provider fail on demand to exercise error handling.
+//! [`assert_snapshot_matches`] compares snapshots within [`Tolerances`], for tests against a
+//! live system. [`MockClock`] stands in for the time.
+//!
+//! ```rust
+//! use sysinfo_dot_h::check::{Check, Metric, Status};
+//! use sysinfo_dot_h::testing::MockProvider;
+//! use sysinfo_dot_h::SystemInfo;
+//!
+//! // 1 GiB free, then 100 MiB less every tick
+//! let mock = MockProvider::from_fn(|tick| {
+//!     Ok(SystemInfo { total_ram: 8 << 30, free_ram: (1 << 30) - tick as u64 * (100 << 20), ..Default::default() })
+//! });
+//! let check = Check::new("MEM").rule(Metric::FreeRam, "800000000:", "600000000:").unwrap();
+//!
+//! let statuses: Vec<Status> = (0..6).map(|_| check.run(&mock).status()).collect();
+//! assert_eq!(statuses, [Status::Ok, Status::Ok, Status::Ok, Status::Warning, Status::Warning, Status::Critical]);
+//! assert_eq!(mock.calls(), 6);
+//! ```
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::clock::Clock;
+use crate::provider::SysinfoProvider;
+use crate::{sysinfo, SysinfoError, SystemInfo};
+
+type Script = Box<dyn FnMut(usize) -> Result<SystemInfo, SysinfoError> + Send>;
+
+/// A [`SysinfoProvider`] playing a programmed script and counting how often it was asked.
+///
+/// It's `Sync`, so an `Arc<MockProvider>` can drive a [`Sampler`](crate::sampler::Sampler)
+/// while the test keeps a clone to look at [`calls`](Self::calls).
+pub struct MockProvider {
+    script: Mutex<Script>,
+    calls: AtomicUsize,
+}
+
+impl std::fmt::Debug for MockProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockProvider").field("calls", &self.calls()).finish_non_exhaustive()
+    }
+}
+
+impl MockProvider {
+    /// Yields `snapshots` in order, then fails once they're used up.
+    pub fn new<I>(snapshots: I) -> Self
+    where
+        I: IntoIterator<Item = SystemInfo>,
+        I::IntoIter: Send + 'static,
+    {
+        Self::results(snapshots.into_iter().map(Ok))
+    }
+
+    /// Like [`new`](Self::new) with raw structs, normalized like the kernel's would be.
+    pub fn raw<I>(snapshots: I) -> Self
+    where
+        I: IntoIterator<Item = sysinfo>,
+        I::IntoIter: Send + 'static,
+    {
+        Self::new(snapshots.into_iter().map(SystemInfo::from))
+    }
+
+    /// Like [`new`](Self::new), with failures in between.
+    pub fn results<I>(results: I) -> Self
+    where
+        I: IntoIterator<Item = Result<SystemInfo, SysinfoError>>,
+        I::IntoIter: Send + 'static,
+    {
+        let mut results = results.into_iter();
+        Self::from_fn(move |_| results.next().unwrap_or_else(|| Err("the mock provider ran out of snapshots".into())))
+    }
+
+    /// The same snapshot forever.
+    #[must_use] pub fn constant(info: SystemInfo) -> Self {
+        Self::from_fn(move |_| Ok(info))
+    }
+
+    /// Calls `script` with the number of the call (starting at 0) for every snapshot, for
+    /// scripts that go on forever.
+    pub fn from_fn<F>(script: F) -> Self
+    where
+        F: FnMut(usize) -> Result<SystemInfo, SysinfoError> + Send + 'static,
+    {
+        Self { script: Mutex::new(Box::new(script)), calls: AtomicUsize::new(0) }
+    }
+
+    /// How many snapshots were asked for so far, failed ones included.
+    #[must_use] pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+// a panicking script leaves nothing half done, the next call just runs it again
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl SysinfoProvider for MockProvider {
+    fn collect(&self) -> Result<SystemInfo, SysinfoError> {
+        let mut script = lock(&self.script);
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        script(call)
+    }
+}
+
+/// One of the files the [procfs backend](crate::backend::Backend::Procfs) puts a snapshot
+/// together from, for [`Fault`]s hitting only part of it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Part {
+    /// `/proc/uptime`: `uptime`
+    Uptime,
+    /// `/proc/loadavg`: `loads` and `procs`
+    Loadavg,
+    /// `/proc/meminfo`: the memory and swap amounts
+    Meminfo,
+}
+
+impl Part {
+    /// The file behind it.
+    #[must_use] pub fn path(self) -> &'static str {
+        match self {
+            Part::Uptime => "/proc/uptime",
+            Part::Loadavg => "/proc/loadavg",
+            Part::Meminfo => "/proc/meminfo",
+        }
+    }
+
+    fn clear(self, info: &mut SystemInfo) {
+        match self {
+            Part::Uptime => info.uptime = Default::default(),
+            Part::Loadavg => (info.loads, info.procs) = ([0.0; 3], 0),
+            Part::Meminfo => {
+                let SystemInfo { uptime, loads, procs, .. } = *info;
+                *info = SystemInfo { uptime, loads, procs, ..Default::default() };
+            }
+        }
+    }
+}
+
+/// What a [`FaultyProvider`] does instead of passing the snapshot on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Fault {
+    /// Fails like the syscall does, with [`SysinfoError::Syscall`] (`14` being `EFAULT`)
+    Errno(i32),
+    /// Fails like the procfs backend does when it can't read this part, with a
+    /// [`SysinfoError::Proc`] carrying `EACCES`
+    Unreadable(Part),
+    /// Succeeds, but with this part's fields zeroed like a collector that went on without it
+    Missing(Part),
+}
+
+impl Fault {
+    fn apply(self, info: Result<SystemInfo, SysinfoError>) -> Result<SystemInfo, SysinfoError> {
+        match self {
+            Fault::Errno(errno) => Err(SysinfoError::Syscall(std::io::Error::from_raw_os_error(errno))),
+            Fault::Unreadable(part) => Err(SysinfoError::proc(part.path(), std::io::Error::from_raw_os_error(13))),
+            Fault::Missing(part) => info.map(|mut info| {
+                part.clear(&mut info);
+                info
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Schedule {
+    Always,
+    Calls(Vec<usize>),
+    Every(usize),
+}
+
+/// Wraps a provider and injects [`Fault`]s into some of its calls: always, on given calls, every
+/// nth one, or whenever [`set_fault`](Self::set_fault) says so. The first matching rule wins.
+///
+/// ```rust
+/// use sysinfo_dot_h::backend::Backend;
+/// use sysinfo_dot_h::provider::SysinfoProvider;
+/// use sysinfo_dot_h::testing::{Fault, FaultyProvider};
+///
+/// // every third call fails with EFAULT
+/// let flaky = FaultyProvider::new(Backend::Libc).fail_every(3, Fault::Errno(14));
+/// let failed = (0..9).filter(|_| flaky.collect().is_err()).count();
+/// assert_eq!(failed, 3);
+/// ```
+#[derive(Debug)]
+pub struct FaultyProvider<P> {
+    inner: P,
+    rules: Vec<(Schedule, Fault)>,
+    forced: Mutex<Option<Fault>>,
+    calls: AtomicUsize,
+}
+
+impl<P: SysinfoProvider> FaultyProvider<P> {
+    /// Passes `inner`'s snapshots on untouched until told otherwise.
+    pub fn new(inner: P) -> Self {
+        Self { inner, rules: Vec::new(), forced: Mutex::new(None), calls: AtomicUsize::new(0) }
+    }
+
+    /// Every call fails with `fault`.
+    #[must_use] pub fn always(mut self, fault: Fault) -> Self {
+        self.rules.push((Schedule::Always, fault));
+        self
+    }
+
+    /// The calls numbered `calls` (starting at 0, `3..6` for an outage) fail with `fault`.
+    #[must_use] pub fn fail_on(mut self, calls: impl IntoIterator<Item = usize>, fault: Fault) -> Self {
+        self.rules.push((Schedule::Calls(calls.into_iter().collect()), fault));
+        self
+    }
+
+    /// Every `n`th call fails with `fault`, for intermittent failures.
+    #[must_use] pub fn fail_every(mut self, n: usize, fault: Fault) -> Self {
+        self.rules.push((Schedule::Every(n.max(1)), fault));
+        self
+    }
+
+    /// Makes every call from now on fail with `fault`, over the other rules, or goes back to
+    /// the rules with `None`. Works through an `Arc` while a sampler is polling.
+    pub fn set_fault(&self, fault: Option<Fault>) {
+        *lock(&self.forced) = fault;
+    }
+
+    /// How many snapshots were asked for so far, failed ones included.
+    #[must_use] pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    fn fault(&self, call: usize) -> Option<Fault> {
+        let scheduled = self.rules.iter().find(|(schedule, _)| match schedule {
+            Schedule::Always => true,
+            Schedule::Calls(calls) => calls.contains(&call),
+            Schedule::Every(n) => (call + 1).is_multiple_of(*n),
+        });
+        (*lock(&self.forced)).or(scheduled.map(|&(_, fault)| fault))
+    }
+}
+
+impl<P: SysinfoProvider> SysinfoProvider for FaultyProvider<P> {
+    fn collect(&self) -> Result<SystemInfo, SysinfoError> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        match self.fault(call) {
+            // failing faults don't even ask `inner`, like a syscall that never happened
+            Some(fault @ (Fault::Errno(_) | Fault::Unreadable(_))) => fault.apply(Err(SysinfoError::Other(String::new()))),
+            Some(fault) => fault.apply(self.inner.collect()),
+            None => self.inner.collect(),
+        }
+    }
+}
+
+/// How far apart two snapshots' fields may be for [`assert_snapshot_matches`]. The default is
+/// exact.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Tolerances {
+    uptime: Duration,
+    loads: f64,
+    memory: u64,
+    procs: u16,
+}
+
+impl Tolerances {
+    /// Every field has to be equal.
+    #[must_use] pub fn exact() -> Self {
+        Self::default()
+    }
+
+    /// For two snapshots of a live machine taken a moment apart: 2 seconds of uptime, 1.0 of
+    /// load, 256 MiB of any memory or swap amount and 100 processes.
+    #[must_use] pub fn live() -> Self {
+        Self { uptime: Duration::from_secs(2), loads: 1.0, memory: 256 << 20, procs: 100 }
+    }
+
+    /// How far apart the uptimes may be.
+    #[must_use] pub fn uptime(self, uptime: Duration) -> Self {
+        Self { uptime, ..self }
+    }
+
+    /// How far apart each load average may be.
+    #[must_use] pub fn loads(self, loads: f64) -> Self {
+        Self { loads, ..self }
+    }
+
+    /// How many bytes apart each memory and swap amount may be.
+    #[must_use] pub fn memory(self, bytes: u64) -> Self {
+        Self { memory: bytes, ..self }
+    }
+
+    /// How far apart the process counts may be.
+    #[must_use] pub fn procs(self, procs: u16) -> Self {
+        Self { procs, ..self }
+    }
+
+    /// The fields of `actual` too far from `expected`, one line each.
+    #[must_use] pub fn mismatches(&self, actual: &SystemInfo, expected: &SystemInfo) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        if actual.uptime.abs_diff(expected.uptime) > self.uptime {
+            mismatches.push(format!("uptime: {:?}, expected {:?} (within {:?})", actual.uptime, expected.uptime, self.uptime));
+        }
+        for (name, (actual, expected)) in ["load1", "load5", "load15"].into_iter().zip(actual.loads.into_iter().zip(expected.loads)) {
+            let close = (actual - expected).abs() <= self.loads;
+            // NaN is never close to anything
+            if !close {
+                mismatches.push(format!("{name}: {actual}, expected {expected} (within {})", self.loads));
+            }
+        }
+        let memory = [
+            ("total_ram", actual.total_ram, expected.total_ram),
+            ("free_ram", actual.free_ram, expected.free_ram),
+            ("shared_ram", actual.shared_ram, expected.shared_ram),
+            ("buffer_ram", actual.buffer_ram, expected.buffer_ram),
+            ("total_swap", actual.total_swap, expected.total_swap),
+            ("free_swap", actual.free_swap, expected.free_swap),
+            ("total_high", actual.total_high, expected.total_high),
+            ("free_high", actual.free_high, expected.free_high),
+        ];
+        for (name, actual, expected) in memory {
+            if actual.abs_diff(expected) > self.memory {
+                mismatches.push(format!("{name}: {actual}, expected {expected} (within {})", self.memory));
+            }
+        }
+        if actual.procs.abs_diff(expected.procs) > self.procs {
+            mismatches.push(format!("procs: {}, expected {} (within {})", actual.procs, expected.procs, self.procs));
+        }
+        mismatches
+    }
+}
+
+/// Panics, listing every field that's off, unless `actual` matches `expected` within
+/// `tolerances`.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use sysinfo_dot_h::testing::{assert_snapshot_matches, Tolerances};
+///
+/// let before = sysinfo_dot_h::collect_info();
+/// let after = sysinfo_dot_h::collect_info();
+/// assert_snapshot_matches(&after, &before, &Tolerances::live().uptime(Duration::from_secs(5)));
+/// ```
+#[track_caller]
+pub fn assert_snapshot_matches(actual: &SystemInfo, expected: &SystemInfo, tolerances: &Tolerances) {
+    let mismatches = tolerances.mismatches(actual, expected);
+    assert!(mismatches.is_empty(), "snapshots don't match:\n  {}", mismatches.join("\n  "));
+}
+
+/// A [`Clock`] that only moves when [`advance`](Self::advance)d. Clones share the time, so a
+/// test keeps one and hands another to the sampler.
+///
+/// Whatever waits on it (the sampler between two samples) checks back every millisecond, so
+/// things come due right after an `advance` without the test sleeping through the interval.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    start: Instant,
+    system_start: SystemTime,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockClock {
+    /// A clock stopped at the current time.
+    #[must_use] pub fn new() -> Self {
+        Self::at(SystemTime::now())
+    }
+
+    /// A clock stopped with its wall clock at `system_time`.
+    #[must_use] pub fn at(system_time: SystemTime) -> Self {
+        Self { start: Instant::now(), system_start: system_time, elapsed: Arc::new(Mutex::new(Duration::ZERO)) }
+    }
+
+    /// Moves the time forward.
+    pub fn advance(&self, by: Duration) {
+        *lock(&self.elapsed) += by;
+    }
+
+    /// How much it was advanced so far.
+    #[must_use] pub fn elapsed(&self) -> Duration {
+        *lock(&self.elapsed)
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.system_start + self.elapsed()
+    }
+
+    fn wait_for(&self, remaining: Duration) -> Duration {
+        remaining.min(Duration::from_millis(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_the_script() {
+        let info = |procs| SystemInfo { procs, ..Default::default() };
+        let mock = MockProvider::results([Ok(info(1)), Err("boom".into()), Ok(info(2))]);
+        let procs: Vec<Result<u16, String>> = (0..4).map(|_| mock.collect().map(|info| info.procs).map_err(String::from)).collect();
+        assert_eq!(procs[..3], [Ok(1), Err("boom".to_string()), Ok(2)]);
+        assert!(procs[3].is_err());
+        assert_eq!(mock.calls(), 4);
+        assert_eq!(MockProvider::constant(info(9)).collect().unwrap().procs, 9);
+
+        let mut raw: sysinfo = unsafe { std::mem::zeroed() };
+        (raw.totalram, raw.mem_unit, raw.procs) = (1024, 4096, 3);
+        let mock = MockProvider::raw([raw]);
+        let back = mock.collect_raw().unwrap();
+        assert_eq!((back.totalram as u64 * u64::from(back.mem_unit), back.procs), (4 << 20, 3));
+        assert!(mock.collect_raw().is_err());
+    }
+
+    #[test]
+    fn drives_a_sampler() {
+        let mock = Arc::new(MockProvider::new((1..=3).map(|procs| SystemInfo { procs, ..Default::default() })));
+        let sampler = crate::sampler::Sampler::builder().interval(Duration::from_millis(1)).history(8).provider(Arc::clone(&mock)).start();
+        while mock.calls() < 5 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        // the failures after the end are skipped
+        let procs: Vec<u16> = sampler.history().iter().map(|info| info.procs).collect();
+        assert_eq!(procs, [1, 2, 3]);
+    }
+
+    #[test]
+    fn injects_faults() {
+        let info = SystemInfo { uptime: Duration::from_secs(9), procs: 3, total_ram: 100, free_swap: 5, ..Default::default() };
+        let faulty = FaultyProvider::new(MockProvider::constant(info))
+            .fail_on(1..3, Fault::Unreadable(Part::Meminfo))
+            .fail_on([3], Fault::Missing(Part::Meminfo))
+            .fail_on([4], Fault::Missing(Part::Loadavg))
+            .fail_every(2, Fault::Errno(14));
+        let results: Vec<Result<SystemInfo, SysinfoError>> = (0..6).map(|_| faulty.collect()).collect();
+        assert_eq!(results[0].as_ref().unwrap(), &info);
+        assert!(matches!(&results[1], Err(e @ SysinfoError::Proc { path, .. }) if path == "/proc/meminfo" && e.errno() == Some(13)));
+        assert!(results[2].is_err());
+        assert_eq!(results[3].as_ref().unwrap(), &SystemInfo { total_ram: 0, free_swap: 0, ..info });
+        assert_eq!(results[4].as_ref().unwrap(), &SystemInfo { procs: 0, ..info });
+        assert!(matches!(&results[5], Err(e @ SysinfoError::Syscall(_)) if e.errno() == Some(14)));
+
+        faulty.set_fault(Some(Fault::Missing(Part::Uptime)));
+        assert_eq!(faulty.collect().unwrap().uptime, Duration::ZERO);
+        faulty.set_fault(None);
+        // back to the rules: 7 fails with the every other call one, 8 is fine
+        assert!(faulty.collect().is_err());
+        assert_eq!(faulty.collect().unwrap(), info);
+        assert_eq!(faulty.calls(), 9);
+    }
+
+    #[test]
+    fn sampler_skips_failures() {
+        let faulty = Arc::new(FaultyProvider::new(MockProvider::from_fn(|call| Ok(SystemInfo { procs: call as u16, ..Default::default() }))).fail_every(2, Fault::Errno(4)));
+        let sampler = crate::sampler::Sampler::builder().interval(Duration::from_millis(1)).history(8).provider(Arc::clone(&faulty)).start();
+        while faulty.calls() < 6 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        // every other call failed before reaching the mock, so it only saw the good ones
+        let procs: Vec<u16> = sampler.history().iter().map(|info| info.procs).collect();
+        assert_eq!(procs, (0..procs.len() as u16).collect::<Vec<_>>());
+        assert!(procs.len() >= 3);
+    }
+
+    #[test]
+    fn tolerances() {
+        let expected = SystemInfo { uptime: Duration::from_secs(100), loads: [1.0; 3], free_ram: 1000, procs: 50, ..Default::default() };
+        let actual = SystemInfo { uptime: Duration::from_secs(102), loads: [1.5, 1.0, f64::NAN], free_ram: 1500, procs: 40, ..expected };
+        assert_eq!(Tolerances::exact().mismatches(&expected, &expected), Vec::<String>::new());
+        let mismatches = Tolerances::exact().uptime(Duration::from_secs(2)).loads(0.5).procs(5).mismatches(&actual, &expected);
+        assert_eq!(mismatches, [
+            "load15: NaN, expected 1 (within 0.5)",
+            "free_ram: 1500, expected 1000 (within 0)",
+            "procs: 40, expected 50 (within 5)",
+        ]);
+        assert_snapshot_matches(&SystemInfo { loads: [1.0; 3], ..actual }, &expected, &Tolerances::live());
+        let panic = std::panic::catch_unwind(|| assert_snapshot_matches(&actual, &expected, &Tolerances::exact())).unwrap_err();
+        assert!(panic.downcast_ref::<String>().unwrap().starts_with("snapshots don't match:\n  uptime: 102s, expected 100s"));
+    }
+}
@@ -0,0 +1,185 @@
+//! A tiny Unix domain socket server that hands out the latest snapshot.
+//!
+//! This lets local tooling (sidecars, shell scripts with `socat`, ...) query a process that
+//! already links this crate without going through HTTP or shared memory.
+//!
+//! # Protocol
+//!
+//! Requests are newline terminated lines, and every request gets exactly one newline terminated
+//! line back. A connection may send as many requests as it wants, but it's closed once it's
+//! been idle (or stuck writing) for the server's [timeout](UdsServer::timeout).
+//!
+//! | Request    | Response                                          |
+//! |------------|---------------------------------------------------|
+//! | `GET json` | [`SystemInfo::to_json`](crate::SystemInfo::to_json) |
+//! | `PING`     | `PONG`                                            |
+//! | other      | `ERR <reason>`                                    |
+//!
+//! ```sh
+//! $ printf 'GET json\n' | socat - UNIX-CONNECT:/run/myagent.sock
+//! {"uptime":1234,"loads":[0.5,0.25,0.125],...}
+//! ```
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::{try_collect_info, SysinfoError, SystemInfo};
+
+/// A Unix domain socket server answering the [protocol](self) described in the module docs.
+///
+/// The socket file is removed when the server is dropped.
+#[derive(Debug)]
+pub struct UdsServer {
+    listener: UnixListener,
+    path: PathBuf,
+    timeout: Duration,
+}
+
+/// How long a connection may sit idle before the server moves on, see [`UdsServer::timeout`].
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl UdsServer {
+    /// Binds a new server to `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket couldn't be bound, for example because `path` already
+    /// exists.
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let listener = UnixListener::bind(&path)?;
+        Ok(Self { listener, path, timeout: DEFAULT_TIMEOUT })
+    }
+
+    /// How long a client may take to send its next request or read a response before it's
+    /// disconnected. Connections are served one at a time, so this is how long one client
+    /// that connects and never says anything can hold up everybody else. Defaults to
+    /// [`DEFAULT_TIMEOUT`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `timeout` is zero, which sockets don't accept.
+    #[must_use] pub fn timeout(mut self, timeout: Duration) -> Self {
+        assert!(!timeout.is_zero(), "the timeout can't be zero");
+        self.timeout = timeout;
+        self
+    }
+
+    /// The path the server is listening on.
+    #[must_use] pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Serves clients forever, collecting a fresh snapshot for every request.
+    ///
+    /// Connections are handled one after another on the calling thread (each for at most the
+    /// [timeout](Self::timeout) between requests), so you probably want to run this on its own
+    /// thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if accepting a connection fails. Errors on individual connections (a
+    /// client hanging up early, ...) are ignored.
+    pub fn serve(&self) -> io::Result<()> {
+        self.serve_with(try_collect_info)
+    }
+
+    /// Like [`serve`](Self::serve), but gets its snapshots from `source`.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`serve`](Self::serve).
+    pub fn serve_with<F>(&self, source: F) -> io::Result<()>
+    where
+        F: Fn() -> Result<SystemInfo, SysinfoError>,
+    {
+        loop {
+            let (stream, _) = self.listener.accept()?;
+            let _ = handle(stream, self.timeout, &source);
+        }
+    }
+}
+
+impl Drop for UdsServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn handle<F>(stream: UnixStream, timeout: Duration, source: &F) -> io::Result<()>
+where
+    F: Fn() -> Result<SystemInfo, SysinfoError>,
+{
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let response = respond(line?.trim(), source);
+        writer.write_all(response.as_bytes())?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+fn respond<F>(request: &str, source: &F) -> String
+where
+    F: Fn() -> Result<SystemInfo, SysinfoError>,
+{
+    match request {
+        "GET json" => match source() {
+            Ok(info) => info.to_json(),
+            Err(e) => format!("ERR {e}"),
+        },
+        "PING" => "PONG".to_string(),
+        _ => format!("ERR unknown request {request:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_requests() {
+        let path = std::env::temp_dir().join(format!("sysinfo_dot_h-{}.sock", std::process::id()));
+        let server = UdsServer::bind(&path).unwrap();
+
+        let client = std::thread::spawn({
+            let path = path.clone();
+            move || {
+                let mut stream = UnixStream::connect(path).unwrap();
+                stream.write_all(b"PING\nGET json\nGET yaml\n").unwrap();
+                stream.shutdown(std::net::Shutdown::Write).unwrap();
+                BufReader::new(stream).lines().collect::<io::Result<Vec<_>>>().unwrap()
+            }
+        });
+        let (stream, _) = server.listener.accept().unwrap();
+        handle(stream, DEFAULT_TIMEOUT, &try_collect_info).unwrap();
+
+        let lines = client.join().unwrap();
+        assert_eq!(lines[0], "PONG");
+        assert!(lines[1].starts_with("{\"uptime\":"));
+        assert!(lines[2].starts_with("ERR "));
+
+        drop(server);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn silent_clients_time_out() {
+        let path = std::env::temp_dir().join(format!("sysinfo_dot_h-silent-{}.sock", std::process::id()));
+        let server = UdsServer::bind(&path).unwrap().timeout(Duration::from_millis(50));
+        let server = std::thread::spawn(move || server.serve());
+
+        let _silent = UnixStream::connect(&path).unwrap();
+        let mut stream = UnixStream::connect(&path).unwrap();
+        stream.write_all(b"PING\n").unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        assert_eq!(line, "PONG\n");
+        // the server thread serves forever, it ends with the test process
+        drop(server);
+        let _ = std::fs::remove_file(&path);
+    }
+}
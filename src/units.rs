@@ -0,0 +1,133 @@
+//! Byte amounts and durations for people: "3.2 GiB", "3.4 GB" or "3435973836 B", "1d 4h 23m".
+//! Used by the exporters and the `sysinfo-dot-h` CLI, so wherever a size shows up it's written
+//! the same way.
+//!
+//! [`SystemInfo`] prints as a one-line summary with them:
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use sysinfo_dot_h::{units::Units, SystemInfo};
+//!
+//! let info = SystemInfo { uptime: Duration::from_secs(101_000), total_ram: 8 << 30, free_ram: 6 << 30, procs: 420, ..Default::default() };
+//! assert_eq!(info.to_string(), "up 1d 4h 3m, 2.0 GiB / 8.0 GiB RAM, 0 B / 0 B swap, load 0.00 0.00 0.00, 420 procs");
+//! assert!(info.summary(Units::Si).to_string().contains("2.1 GB / 8.6 GB RAM"));
+//! ```
+use std::fmt;
+use std::time::Duration;
+
+use crate::SystemInfo;
+
+/// Which prefixes [`bytes`] uses, like `free`'s `--binary` (the default), `--si` and `--bytes`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Units {
+    /// Powers of 1024, "3.2 GiB"
+    #[default]
+    Binary,
+    /// Powers of 1000, "3.4 GB"
+    Si,
+    /// Just the number, "3435973836 B"
+    Bytes,
+}
+
+/// Formats `bytes` in `units` with one decimal, like `free -h` does ("3.2 GiB").
+#[must_use] pub fn bytes(bytes: u64, units: Units) -> String {
+    let (base, prefixes) = match units {
+        Units::Binary => (1024.0, ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"]),
+        Units::Si => (1000.0, ["kB", "MB", "GB", "TB", "PB", "EB"]),
+        Units::Bytes => return format!("{bytes} B"),
+    };
+    if (bytes as f64) < base {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / base;
+    let mut unit = 0;
+    while value >= base && unit < prefixes.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    format!("{value:.1} {}", prefixes[unit])
+}
+
+/// "3d 4h 23m", leaving out leading zero units ("2h 5m", "0m 59s").
+#[must_use] pub fn duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+    match (days, hours) {
+        (0, 0) => format!("{minutes}m {}s", secs % 60),
+        (0, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h {minutes}m"),
+    }
+}
+
+/// [`SystemInfo::summary`]: the snapshot on one line.
+#[derive(Debug, Copy, Clone)]
+pub struct Summary<'a> {
+    info: &'a SystemInfo,
+    units: Units,
+}
+
+impl SystemInfo {
+    /// A one-line summary with the amounts in `units`. [`Display`](fmt::Display) is this in
+    /// [`Units::Binary`].
+    #[must_use] pub fn summary(&self, units: Units) -> Summary<'_> {
+        Summary { info: self, units }
+    }
+}
+
+/// `up 1d 4h 23m, 12.3 GiB / 31.2 GiB RAM, 0 B / 2.0 GiB swap, load 0.52 0.40 0.33, 420 procs`,
+/// used out of total.
+impl fmt::Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let info = self.info;
+        write!(
+            f,
+            "up {}, {} / {} RAM, {} / {} swap, load {}, {} procs",
+            duration(info.uptime),
+            bytes(info.total_ram.saturating_sub(info.free_ram), self.units),
+            bytes(info.total_ram, self.units),
+            bytes(info.total_swap.saturating_sub(info.free_swap), self.units),
+            bytes(info.total_swap, self.units),
+            info.load_avg(),
+            info.procs,
+        )
+    }
+}
+
+/// [`SystemInfo::summary`] in binary units.
+impl fmt::Display for SystemInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.summary(Units::Binary).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes() {
+        assert_eq!(bytes(512, Units::Binary), "512 B");
+        assert_eq!(bytes(1000, Units::Binary), "1000 B");
+        assert_eq!(bytes(1536, Units::Binary), "1.5 KiB");
+        assert_eq!(bytes(3 << 29, Units::Binary), "1.5 GiB");
+        assert_eq!(bytes(16 << 30, Units::Binary), "16.0 GiB");
+        assert_eq!(bytes(u64::MAX, Units::Binary), "16.0 EiB");
+        assert_eq!(bytes(999, Units::Si), "999 B");
+        assert_eq!(bytes(3 << 29, Units::Si), "1.6 GB");
+        assert_eq!(bytes(3 << 29, Units::Bytes), "1610612736 B");
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(duration(Duration::from_secs(59)), "0m 59s");
+        assert_eq!(duration(Duration::from_secs(3 * 3600 + 120)), "3h 2m");
+        assert_eq!(duration(Duration::from_secs(2 * 86400 + 60)), "2d 0h 1m");
+    }
+
+    #[test]
+    fn summary() {
+        let info = SystemInfo { uptime: Duration::from_secs(90), total_ram: 4 << 30, free_ram: 1 << 30, total_swap: 1 << 30, free_swap: 2 << 30, loads: [0.52, 0.4, 0.33], procs: 7, ..Default::default() };
+        assert_eq!(info.to_string(), "up 1m 30s, 3.0 GiB / 4.0 GiB RAM, 0 B / 1.0 GiB swap, load 0.52 0.40 0.33, 7 procs");
+        assert_eq!(info.summary(Units::Bytes).to_string(), "up 1m 30s, 3221225472 B / 4294967296 B RAM, 0 B / 1073741824 B swap, load 0.52 0.40 0.33, 7 procs");
+    }
+}
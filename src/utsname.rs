@@ -0,0 +1,85 @@
+//! `uname(2)`, the other half of "what is this machine": the kernel and its version, the
+//! architecture and the hostname.
+//!
+//! ```rust
+//! let uname = sysinfo_dot_h::try_collect_uname().unwrap();
+//! println!("{} {} on {} ({})", uname.kernel_name, uname.release, uname.hostname, uname.machine);
+//! ```
+use std::os::raw::{c_char, c_int};
+
+use crate::SysinfoError;
+
+/// `struct utsname` with the fields as `String`s, under the names `uname(1)` gives them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Uname {
+    /// `sysname`, "Linux"
+    pub kernel_name: String,
+    /// `nodename`, the hostname (not necessarily the fully qualified one)
+    pub hostname: String,
+    /// `release`, "6.8.0-45-generic"
+    pub release: String,
+    /// `version`, the build: "#45-Ubuntu SMP PREEMPT_DYNAMIC Fri Aug 30 12:02:04 UTC 2024"
+    pub version: String,
+    /// `machine`, "x86_64"
+    pub machine: String,
+}
+
+/// How long each field of `struct utsname` is: `_UTSNAME_LENGTH` on Linux, `_SYS_NAMELEN` on
+/// macOS and `SYS_NMLN` on FreeBSD.
+#[cfg(target_os = "linux")]
+const FIELD: usize = 65;
+#[cfg(not(target_os = "linux"))]
+const FIELD: usize = 256;
+
+/// Enough for every field, Linux has a sixth one (`domainname`).
+#[repr(C)]
+struct RawUtsname {
+    fields: [[c_char; FIELD]; 6],
+}
+
+/// Calls `uname(2)`.
+///
+/// # Errors
+///
+/// Returns [`SysinfoError::Syscall`] if `uname()` fails, which it doesn't in practice.
+pub fn try_collect_uname() -> Result<Uname, SysinfoError> {
+    #[cfg(not(target_os = "freebsd"))]
+    extern "C" {
+        fn uname(buf: *mut RawUtsname) -> c_int;
+    }
+    // FreeBSD's uname() is an inline function in the header, calling this with the field length
+    #[cfg(target_os = "freebsd")]
+    extern "C" {
+        fn __xuname(namesize: c_int, buf: *mut RawUtsname) -> c_int;
+    }
+    #[cfg(target_os = "freebsd")]
+    unsafe fn uname(buf: *mut RawUtsname) -> c_int {
+        __xuname(FIELD as c_int, buf)
+    }
+
+    let mut raw = RawUtsname { fields: [[0; FIELD]; 6] };
+    if unsafe { uname(&mut raw) } != 0 {
+        return Err(SysinfoError::last_os_error());
+    }
+    let [kernel_name, hostname, release, version, machine, _] = raw.fields.map(|field| field_string(&field));
+    Ok(Uname { kernel_name, hostname, release, version, machine })
+}
+
+/// A nul terminated field, lossily decoded. The kernel doesn't promise UTF-8 in the hostname.
+fn field_string(field: &[c_char; FIELD]) -> String {
+    let bytes: Vec<u8> = field.iter().take_while(|&&c| c != 0).map(|&c| c as u8).collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_this_machine() {
+        let uname = try_collect_uname().unwrap();
+        #[cfg(target_os = "linux")]
+        assert_eq!(uname.kernel_name, "Linux");
+        assert!(!uname.release.is_empty() && !uname.machine.is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+//! `/proc/vmstat`, the kernel's counters of paging and swapping activity since boot. Free swap
+//! says how much is swapped out, these say how fast it's happening.
+//!
+//! ```rust
+//! let vmstat = sysinfo_dot_h::vmstat::VmStat::read().unwrap();
+//! println!("{} major faults since boot", vmstat.pgmajfault);
+//! ```
+//!
+//! Swap activity is the difference between two reads, like [`SystemInfo::diff`](crate::SystemInfo::diff)
+//! for the snapshot:
+//!
+//! ```rust
+//! use std::time::{Duration, Instant};
+//! use sysinfo_dot_h::vmstat::VmStat;
+//!
+//! let (before, then) = (VmStat::read().unwrap(), Instant::now());
+//! std::thread::sleep(Duration::from_millis(10));
+//! let delta = before.diff(&VmStat::read().unwrap()).with_elapsed(then.elapsed());
+//! println!("swapping {:.0} pages in and {:.0} out a second", delta.rate(delta.pswpin).unwrap(), delta.rate(delta.pswpout).unwrap());
+//! ```
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::delta::{known, per_second};
+use crate::SysinfoError;
+
+/// The interesting counters of `/proc/vmstat`. They only ever go up (until they wrap).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct VmStat {
+    /// KiB paged in from disk
+    pub pgpgin: u64,
+    /// KiB paged out to disk
+    pub pgpgout: u64,
+    /// Pages swapped in
+    pub pswpin: u64,
+    /// Pages swapped out
+    pub pswpout: u64,
+    /// Page faults, minor and major
+    pub pgfault: u64,
+    /// Page faults that had to wait for the disk
+    pub pgmajfault: u64,
+}
+
+impl VmStat {
+    /// Reads `/proc/vmstat`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or a counter isn't a number.
+    pub fn read() -> Result<Self, SysinfoError> {
+        crate::read_proc("/proc/vmstat")?.parse()
+    }
+}
+
+impl FromStr for VmStat {
+    type Err = SysinfoError;
+
+    /// Parses the contents of `/proc/vmstat`. Unknown and missing counters are ignored (the list
+    /// changes between kernel versions), so this only fails on garbage.
+    fn from_str(s: &str) -> Result<Self, SysinfoError> {
+        let mut vmstat = VmStat::default();
+        for line in s.lines() {
+            let Some((key, value)) = line.split_once(' ') else { continue };
+            let counter = match key {
+                "pgpgin" => &mut vmstat.pgpgin,
+                "pgpgout" => &mut vmstat.pgpgout,
+                "pswpin" => &mut vmstat.pswpin,
+                "pswpout" => &mut vmstat.pswpout,
+                "pgfault" => &mut vmstat.pgfault,
+                "pgmajfault" => &mut vmstat.pgmajfault,
+                _ => continue,
+            };
+            *counter = value.trim().parse().map_err(|_| SysinfoError::parse("/proc/vmstat", line))?;
+        }
+        Ok(vmstat)
+    }
+}
+
+/// `after - before` for every counter. A counter that went backwards (a reboot in between, or
+/// a wrap on a 32-bit kernel) counts as 0.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct VmStatDelta {
+    /// The time between the two, `None` until [`with_elapsed`](Self::with_elapsed): the file
+    /// has no timestamp
+    pub elapsed: Option<Duration>,
+    pub pgpgin: u64,
+    pub pgpgout: u64,
+    pub pswpin: u64,
+    pub pswpout: u64,
+    pub pgfault: u64,
+    pub pgmajfault: u64,
+}
+
+impl VmStatDelta {
+    /// Sets the time between the two reads, measured by the caller (e.g. with `Instant`s).
+    #[must_use] pub fn with_elapsed(mut self, elapsed: Duration) -> Self {
+        self.elapsed = known(elapsed);
+        self
+    }
+
+    /// `delta` (one of the fields) per second, `None` without an elapsed time.
+    #[must_use] pub fn rate(&self, delta: u64) -> Option<f64> {
+        per_second(delta as f64, self.elapsed)
+    }
+}
+
+impl VmStat {
+    /// What changed from `self` to `later`, without an elapsed time.
+    #[must_use] pub fn diff(&self, later: &VmStat) -> VmStatDelta {
+        VmStatDelta {
+            elapsed: None,
+            pgpgin: later.pgpgin.saturating_sub(self.pgpgin),
+            pgpgout: later.pgpgout.saturating_sub(self.pgpgout),
+            pswpin: later.pswpin.saturating_sub(self.pswpin),
+            pswpout: later.pswpout.saturating_sub(self.pswpout),
+            pgfault: later.pgfault.saturating_sub(self.pgfault),
+            pgmajfault: later.pgmajfault.saturating_sub(self.pgmajfault),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses() {
+        let vmstat: VmStat = "nr_free_pages 61562\npgpgin 2775776\npgpgout 9216944\npswpin 12\npswpout 40\npgfault 48111533\npgmajfault 8101\n"
+            .parse()
+            .unwrap();
+        assert_eq!(vmstat, VmStat { pgpgin: 2775776, pgpgout: 9216944, pswpin: 12, pswpout: 40, pgfault: 48111533, pgmajfault: 8101 });
+        assert!("pgmajfault lots\n".parse::<VmStat>().is_err());
+        assert!(VmStat::read().unwrap().pgfault > 0);
+    }
+
+    #[test]
+    fn deltas_and_rates() {
+        let before = VmStat { pswpin: 100, pswpout: 1000, pgfault: 50, ..Default::default() };
+        let after = VmStat { pswpin: 150, pswpout: 1200, pgfault: 10, ..Default::default() };
+        let delta = before.diff(&after);
+        assert_eq!((delta.pswpin, delta.pswpout, delta.pgfault), (50, 200, 0));
+        assert_eq!(delta.rate(delta.pswpout), None);
+        let delta = delta.with_elapsed(Duration::from_millis(500));
+        assert_eq!((delta.rate(delta.pswpin), delta.rate(delta.pswpout)), (Some(100.0), Some(400.0)));
+        assert_eq!(delta.with_elapsed(Duration::ZERO).elapsed, None);
+    }
+}
@@ -0,0 +1,264 @@
+//! Alerting on thresholds: [`Condition`]s like "available memory below 5%" or "load1 above twice
+//! the CPUs", and a [`Watcher`] that says when each one trips and when it recovers.
+//!
+//! A condition can recover at another threshold than the one it trips at (hysteresis), so a
+//! value hovering around the limit doesn't flap, and can have to hold for a while before it
+//! counts (debounce), so a single spike doesn't alert. The watcher is only the state machine:
+//! feed it snapshots with [`observe`](Watcher::observe), or hand it a
+//! [sampler](crate::sampler)'s channel.
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use sysinfo_dot_h::check::Metric;
+//! use sysinfo_dot_h::sampler::Sampler;
+//! use sysinfo_dot_h::watch::{Condition, Event, Watcher};
+//!
+//! let cpus = std::thread::available_parallelism().map_or(1, |cpus| cpus.get()) as f64;
+//! let watcher = Watcher::new()
+//!     .condition(Condition::below("memory", |info| info.available_memory().unwrap_or(info.free_ram) as f64 * 100.0 / info.total_ram as f64, 5.0).recover_at(10.0))
+//!     .condition(Condition::above("load", |info| Metric::Load1.value(info), cpus * 2.0).for_at_least(Duration::from_secs(60)));
+//!
+//! let (sampler, samples) = Sampler::builder().interval(Duration::from_millis(10)).start_with_channel(16);
+//! let events = watcher.spawn(samples);
+//! # drop(sampler);
+//! for event in events {
+//!     match event {
+//!         Event::Tripped { name, value, .. } => println!("{name} tripped at {value:.1}"),
+//!         Event::Recovered { name, value, .. } => println!("{name} recovered at {value:.1}"),
+//!     }
+//! }
+//! ```
+use std::fmt;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::clock::{self, Clock};
+use crate::SystemInfo;
+
+/// A threshold on one value of the snapshot.
+pub struct Condition {
+    name: String,
+    value: Box<dyn Fn(&SystemInfo) -> f64 + Send>,
+    above: bool,
+    trip: f64,
+    recover: f64,
+    debounce: Duration,
+}
+
+impl fmt::Debug for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Condition")
+            .field("name", &self.name)
+            .field("above", &self.above)
+            .field("trip", &self.trip)
+            .field("recover", &self.recover)
+            .field("debounce", &self.debounce)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Condition {
+    /// Trips when `value` goes over `threshold`.
+    pub fn above(name: impl Into<String>, value: impl Fn(&SystemInfo) -> f64 + Send + 'static, threshold: f64) -> Self {
+        Self::new(name.into(), Box::new(value), true, threshold)
+    }
+
+    /// Trips when `value` goes under `threshold`.
+    pub fn below(name: impl Into<String>, value: impl Fn(&SystemInfo) -> f64 + Send + 'static, threshold: f64) -> Self {
+        Self::new(name.into(), Box::new(value), false, threshold)
+    }
+
+    fn new(name: String, value: Box<dyn Fn(&SystemInfo) -> f64 + Send>, above: bool, threshold: f64) -> Self {
+        Self { name, value, above, trip: threshold, recover: threshold, debounce: Duration::ZERO }
+    }
+
+    /// Only recovers once the value is back to `threshold` (below it for [`above`](Self::above),
+    /// above it for [`below`](Self::below)), instead of as soon as it's within the trip threshold.
+    #[must_use] pub fn recover_at(mut self, threshold: f64) -> Self {
+        self.recover = threshold;
+        self
+    }
+
+    /// Only trips, and recovers, once it has been so for `duration` in every snapshot.
+    #[must_use] pub fn for_at_least(mut self, duration: Duration) -> Self {
+        self.debounce = duration;
+        self
+    }
+
+    fn trips(&self, value: f64) -> bool {
+        if self.above { value > self.trip } else { value < self.trip }
+    }
+
+    fn recovers(&self, value: f64) -> bool {
+        if self.above { value <= self.recover } else { value >= self.recover }
+    }
+}
+
+/// A condition changing state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Tripped { name: String, value: f64, at: Instant },
+    Recovered { name: String, value: f64, at: Instant },
+}
+
+#[derive(Debug)]
+struct Watched {
+    condition: Condition,
+    tripped: bool,
+    /// Since when the snapshots have been asking for the other state
+    pending_since: Option<Instant>,
+}
+
+/// Tracks a set of conditions over a stream of snapshots, see the [module docs](self).
+#[derive(Debug)]
+pub struct Watcher {
+    watched: Vec<Watched>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for Watcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Watcher {
+    /// A watcher without conditions.
+    #[must_use] pub fn new() -> Self {
+        Self { watched: Vec::new(), clock: clock::system() }
+    }
+
+    /// Adds `condition`, not tripped to begin with.
+    #[must_use] pub fn condition(mut self, condition: Condition) -> Self {
+        self.watched.push(Watched { condition, tripped: false, pending_since: None });
+        self
+    }
+
+    /// Where the debounce times are measured, the [`SystemClock`](clock::SystemClock) by default.
+    #[must_use] pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Whether the condition called `name` is tripped right now.
+    #[must_use] pub fn is_tripped(&self, name: &str) -> bool {
+        self.watched.iter().any(|watched| watched.tripped && watched.condition.name == name)
+    }
+
+    /// Looks at `info`, and returns what tripped or recovered because of it.
+    pub fn observe(&mut self, info: &SystemInfo) -> Vec<Event> {
+        let now = self.clock.now();
+        let mut events = Vec::new();
+        for watched in &mut self.watched {
+            let condition = &watched.condition;
+            let value = (condition.value)(info);
+            let flips = if watched.tripped { condition.recovers(value) } else { condition.trips(value) };
+            if !flips {
+                watched.pending_since = None;
+                continue;
+            }
+            let since = *watched.pending_since.get_or_insert(now);
+            if now.saturating_duration_since(since) < condition.debounce {
+                continue;
+            }
+            watched.tripped = !watched.tripped;
+            watched.pending_since = None;
+            let name = condition.name.clone();
+            events.push(if watched.tripped { Event::Tripped { name, value, at: now } } else { Event::Recovered { name, value, at: now } });
+        }
+        events
+    }
+
+    /// [`observe`](Self::observe)s every snapshot from `samples` until it's closed, calling
+    /// `on_event` with what happens.
+    pub fn run(mut self, samples: Receiver<SystemInfo>, mut on_event: impl FnMut(Event)) {
+        for info in samples {
+            self.observe(&info).into_iter().for_each(&mut on_event);
+        }
+    }
+
+    /// [`run`](Self::run) on a thread of its own, with the events going into the returned
+    /// channel. It stops when `samples` closes (the sampler stopped) or the events aren't
+    /// listened to anymore.
+    #[must_use] pub fn spawn(mut self, samples: Receiver<SystemInfo>) -> Receiver<Event> {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for info in samples {
+                if self.observe(&info).into_iter().any(|event| tx.send(event).is_err()) {
+                    return;
+                }
+            }
+        });
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockClock;
+
+    fn procs(procs: u16) -> SystemInfo {
+        SystemInfo { procs, ..Default::default() }
+    }
+
+    fn names(events: &[Event]) -> Vec<String> {
+        events
+            .iter()
+            .map(|event| match event {
+                Event::Tripped { name, .. } => format!("{name} tripped"),
+                Event::Recovered { name, .. } => format!("{name} recovered"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hysteresis() {
+        let mut watcher = Watcher::new().condition(Condition::above("procs", |info| f64::from(info.procs), 100.0).recover_at(80.0));
+        assert!(watcher.observe(&procs(100)).is_empty());
+        assert_eq!(names(&watcher.observe(&procs(101))), ["procs tripped"]);
+        assert!(watcher.is_tripped("procs"));
+        assert!(watcher.observe(&procs(200)).is_empty());
+        assert!(watcher.observe(&procs(90)).is_empty());
+        assert_eq!(names(&watcher.observe(&procs(80))), ["procs recovered"]);
+        assert!(!watcher.is_tripped("procs"));
+    }
+
+    #[test]
+    fn debounce() {
+        let clock = MockClock::new();
+        let mut watcher = Watcher::new()
+            .clock(clock.clone())
+            .condition(Condition::below("few", |info| f64::from(info.procs), 10.0).for_at_least(Duration::from_secs(5)));
+        assert!(watcher.observe(&procs(5)).is_empty());
+        clock.advance(Duration::from_secs(4));
+        assert!(watcher.observe(&procs(5)).is_empty());
+        // back up resets the wait
+        assert!(watcher.observe(&procs(50)).is_empty());
+        assert!(watcher.observe(&procs(5)).is_empty());
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(names(&watcher.observe(&procs(5))), ["few tripped"]);
+        assert!(watcher.observe(&procs(50)).is_empty());
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(names(&watcher.observe(&procs(50))), ["few recovered"]);
+    }
+
+    #[test]
+    fn channels() {
+        let (tx, samples) = mpsc::channel();
+        let events = Watcher::new().condition(Condition::above("procs", |info| f64::from(info.procs), 1.0)).spawn(samples);
+        for n in [1, 2, 3, 0] {
+            tx.send(procs(n)).unwrap();
+        }
+        drop(tx);
+        assert_eq!(names(&events.iter().collect::<Vec<_>>()), ["procs tripped", "procs recovered"]);
+
+        let (tx, samples) = mpsc::channel();
+        tx.send(procs(2)).unwrap();
+        drop(tx);
+        let mut seen = Vec::new();
+        Watcher::new().condition(Condition::above("procs", |info| f64::from(info.procs), 1.0)).run(samples, |event| seen.push(event));
+        assert_eq!(names(&seen), ["procs tripped"]);
+    }
+}
@@ -0,0 +1,259 @@
+//! Pushes snapshots to a Zabbix server or proxy with the sender (trapper) protocol, like
+//! `zabbix_sender` does.
+//!
+//! Every value is sent as a trapper item named `<prefix><field>` (`sysinfo.free_ram`,
+//! `sysinfo.load1`, ...), so the host needs matching "Zabbix trapper" items.
+//!
+//! ```rust,no_run
+//! use sysinfo_dot_h::zabbix::ZabbixSender;
+//!
+//! let sender = ZabbixSender::new("zabbix.example.com:10051", "web-01");
+//! let response = sender.send(&sysinfo_dot_h::collect_info()).unwrap();
+//! assert_eq!(response.failed, 0);
+//! ```
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::SystemInfo;
+
+const HEADER: &[u8; 5] = b"ZBXD\x01";
+/// Zabbix refuses bigger packets, so don't bother reading them
+const MAX_RESPONSE: u64 = 128 << 20;
+
+/// A Zabbix sender for one host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZabbixSender {
+    server: String,
+    host: String,
+    prefix: String,
+    timeout: Duration,
+}
+
+/// What the server answered.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Response {
+    /// Values the server accepted
+    pub processed: u32,
+    /// Values the server rejected (usually because there's no such trapper item)
+    pub failed: u32,
+    /// Values sent
+    pub total: u32,
+    /// The raw `info` string, e.g. `processed: 1; failed: 0; total: 1; seconds spent: 0.000055`
+    pub info: String,
+}
+
+impl ZabbixSender {
+    /// A sender talking to `server` (`host:port`, Zabbix uses 10051) on behalf of the Zabbix
+    /// host named `host`.
+    #[must_use] pub fn new(server: &str, host: &str) -> Self {
+        Self {
+            server: server.to_string(),
+            host: host.to_string(),
+            prefix: "sysinfo.".to_string(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Prepended to every item key. Defaults to `sysinfo.`.
+    #[must_use] pub fn key_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Connect, read and write timeout. Defaults to 10 seconds.
+    #[must_use] pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sends every field of `info`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server can't be reached, or doesn't answer with `success`.
+    pub fn send(&self, info: &SystemInfo) -> io::Result<Response> {
+        let values = [
+            ("uptime", info.uptime.as_secs().to_string()),
+            ("load1", info.loads[0].to_string()),
+            ("load5", info.loads[1].to_string()),
+            ("load15", info.loads[2].to_string()),
+            ("total_ram", info.total_ram.to_string()),
+            ("free_ram", info.free_ram.to_string()),
+            ("shared_ram", info.shared_ram.to_string()),
+            ("buffer_ram", info.buffer_ram.to_string()),
+            ("total_swap", info.total_swap.to_string()),
+            ("free_swap", info.free_swap.to_string()),
+            ("total_high", info.total_high.to_string()),
+            ("free_high", info.free_high.to_string()),
+            ("procs", info.procs.to_string()),
+        ];
+        let values = values.map(|(field, value)| (format!("{}{field}", self.prefix), value));
+        self.send_values(&values)
+    }
+
+    /// Sends arbitrary `(key, value)` pairs.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`send`](Self::send).
+    pub fn send_values(&self, values: &[(String, String)]) -> io::Result<Response> {
+        let addr = std::net::ToSocketAddrs::to_socket_addrs(&self.server)?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} didn't resolve", self.server)))?;
+        let mut stream = TcpStream::connect_timeout(&addr, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        stream.write_all(&packet(&self.request(values)))?;
+        let body = read_packet(&mut stream)?;
+        parse_response(&body)
+    }
+
+    fn request(&self, values: &[(String, String)]) -> String {
+        let clock = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let data: Vec<String> = values
+            .iter()
+            .map(|(key, value)| {
+                format!(
+                    "{{\"host\":{},\"key\":{},\"value\":{},\"clock\":{clock}}}",
+                    json_string(&self.host), json_string(key), json_string(value),
+                )
+            })
+            .collect();
+        format!("{{\"request\":\"sender data\",\"data\":[{}],\"clock\":{clock}}}", data.join(","))
+    }
+}
+
+fn packet(body: &str) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(HEADER.len() + 8 + body.len());
+    packet.extend_from_slice(HEADER);
+    packet.extend_from_slice(&(body.len() as u64).to_le_bytes());
+    packet.extend_from_slice(body.as_bytes());
+    packet
+}
+
+fn read_packet<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut header = [0; 13];
+    reader.read_exact(&mut header)?;
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    if header[..4] != HEADER[..4] {
+        return Err(invalid("not a Zabbix response"));
+    }
+    // the flags byte: 0x01 is the protocol, 0x02 compression (zlib) and 0x04 large packets
+    match header[4] {
+        0x01 => {}
+        flags if flags & 0x02 != 0 => return Err(invalid("compressed Zabbix responses aren't supported")),
+        flags if flags & 0x04 != 0 => return Err(invalid("large Zabbix packets aren't supported")),
+        flags => return Err(invalid(&format!("unknown Zabbix protocol flags {flags:#04x}"))),
+    }
+    let len = u64::from_le_bytes(header[5..].try_into().expect("8 bytes"));
+    if len > MAX_RESPONSE {
+        return Err(invalid("Zabbix response too large"));
+    }
+    let mut body = Vec::new();
+    reader.take(len).read_to_end(&mut body)?;
+    Ok(body)
+}
+
+fn parse_response(body: &[u8]) -> io::Result<Response> {
+    let body = String::from_utf8_lossy(body);
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("{msg}: {body}"));
+    if string_field(&body, "response").as_deref() != Some("success") {
+        return Err(invalid("Zabbix didn't accept the data"));
+    }
+    let info = string_field(&body, "info").ok_or_else(|| invalid("Zabbix response without info"))?;
+
+    let mut response = Response { info, ..Response::default() };
+    for part in response.info.split(';') {
+        let Some((name, value)) = part.split_once(':') else { continue };
+        let value = value.trim().parse().unwrap_or(0);
+        match name.trim() {
+            "processed" => response.processed = value,
+            "failed" => response.failed = value,
+            "total" => response.total = value,
+            _ => {}
+        }
+    }
+    Ok(response)
+}
+
+/// The value of a string member called `name`. Good enough for Zabbix's flat, machine written
+/// responses; escapes other than `\"`, `\\` and `\/` are kept as is, backslash included.
+fn string_field(json: &str, name: &str) -> Option<String> {
+    let start = json.find(&format!("\"{name}\""))? + name.len() + 2;
+    let rest = json[start..].trim_start().strip_prefix(':')?.trim_start().strip_prefix('"')?;
+    let mut value = String::new();
+    let mut chars = rest.chars();
+    loop {
+        match chars.next()? {
+            '"' => return Some(value),
+            '\\' => match chars.next()? {
+                c @ ('"' | '\\' | '/') => value.push(c),
+                c => {
+                    value.push('\\');
+                    value.push(c);
+                }
+            },
+            c => value.push(c),
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if u32::from(c) < 0x20 => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn talks_to_a_fake_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = listener.local_addr().unwrap().to_string();
+        let fake = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = String::from_utf8(read_packet(&mut stream).unwrap()).unwrap();
+            let answer = r#"{"response":"success","info":"processed: 12; failed: 1; total: 13; seconds spent: 0.000055"}"#;
+            stream.write_all(&packet(answer)).unwrap();
+            request
+        });
+
+        let response = ZabbixSender::new(&server, "web \"01\"").send(&SystemInfo::default()).unwrap();
+        assert_eq!((response.processed, response.failed, response.total), (12, 1, 13));
+
+        let request = fake.join().unwrap();
+        assert!(request.starts_with("{\"request\":\"sender data\",\"data\":[{\"host\":\"web \\\"01\\\"\",\"key\":\"sysinfo.uptime\""));
+        assert!(request.contains("\"key\":\"sysinfo.procs\",\"value\":\"0\""));
+    }
+
+    #[test]
+    fn rejects_failures() {
+        assert!(parse_response(br#"{"response":"failed","info":"invalid JSON"}"#).is_err());
+        assert!(read_packet(&mut &b"HTTP/1.1 400 Bad Request\r\n"[..]).is_err());
+        let compressed = read_packet(&mut &b"ZBXD\x03\x05\0\0\0\x09\0\0\0xxxxx"[..]).unwrap_err();
+        assert_eq!(compressed.to_string(), "compressed Zabbix responses aren't supported");
+        let large = read_packet(&mut &b"ZBXD\x05\x05\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0xxxxx"[..]).unwrap_err();
+        assert_eq!(large.to_string(), "large Zabbix packets aren't supported");
+    }
+
+    #[test]
+    fn string_escapes() {
+        let json = r#"{"info":"a \"b\" c\\d \/ e\nf \u00e9"}"#;
+        assert_eq!(string_field(json, "info").as_deref(), Some(r#"a "b" c\d / e\nf \u00e9"#));
+        assert_eq!(string_field(json, "missing"), None);
+    }
+}